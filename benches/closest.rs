@@ -0,0 +1,61 @@
+//! Benchmark of the closest points selection against sorting all points.
+//!
+//! Run by `cargo bench --bench closest`, every case prints the mean duration of a query.
+
+extern crate rand;
+extern crate xor_distance_exercise;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use xor_distance_exercise::xor_distance::{Algorithm, IndexKind, XorDistance};
+
+/// Number of queries of every case.
+const QUERIES: u32 = 20;
+
+/// Return the closest points by sorting all of them, the way `closest` did before selecting.
+fn closest_full_sort(points: &[u64], x: u64, count: usize) -> Vec<u64> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by_key(|point| *point ^ x);
+    sorted.truncate(count);
+    sorted
+}
+
+/// Return the mean duration of the query over random positions.
+fn mean<F: FnMut(u64) -> Vec<u64>>(rng: &mut StdRng, mut query: F) -> Duration {
+    let xs: Vec<u64> = (0..QUERIES).map(|_| rng.gen()).collect();
+    let start = Instant::now();
+
+    for x in xs {
+        black_box(query(x));
+    }
+
+    start.elapsed() / QUERIES
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(1511);
+
+    for len in [10_000, 100_000, 1_000_000].iter() {
+        let points: Vec<u64> = (0..*len).map(|_| rng.gen()).collect();
+        let xor_distance = XorDistance::builder(points.clone())
+            .index(IndexKind::Sort)
+            .algorithm(Algorithm::Sort)
+            .build();
+
+        for count in [10, 1000].iter() {
+            let sort = mean(&mut rng, |x| closest_full_sort(&points, x, *count));
+            let select = mean(&mut rng, |x| xor_distance.closest(x, *count));
+
+            println!(
+                "points {:>9} count {:>5}: full sort {:>12?}, selection {:>12?}, speedup {:.1}x",
+                len,
+                count,
+                sort,
+                select,
+                sort.as_secs_f64() / select.as_secs_f64()
+            );
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Benchmark suite of closest points and reverse closest points queries.
+//!
+//! Run by `cargo bench --bench suite`, optionally followed by `-- <max points>` to skip the larger
+//! point sets, e.g. `cargo bench --bench suite -- 100000`. Every case prints the mean duration of
+//! a query and the number of queries per second, over random positions and random valid closest
+//! points of them. With the `parallel` feature `closest_parallel` is measured too.
+//!
+//! Point sets range from 1k to 10M points of `u32`, `u64` and `u128`. The trie index is built
+//! only up to `TRIE_MAX_POINTS`, keeping the memory of the largest cases at the points vector.
+
+extern crate num_traits;
+extern crate rand;
+extern crate xor_distance_exercise;
+
+use num_traits::{PrimInt, Unsigned};
+use rand::distributions::{Distribution, Standard};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::env;
+use std::fmt::Debug;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use xor_distance_exercise::bitops::BitOps;
+use xor_distance_exercise::generator::random_points;
+use xor_distance_exercise::xor_distance::{IndexKind, XorDistance};
+
+/// Numbers of points of the cases.
+const POINTS: [usize; 5] = [1_000, 10_000, 100_000, 1_000_000, 10_000_000];
+
+/// Requested counts of closest points queries.
+const COUNTS: [usize; 4] = [1, 10, 100, 1000];
+
+/// Lengths of the closest points reversed, the reverse is linear in the points times the length.
+const REVERSE_COUNTS: [usize; 3] = [1, 10, 100];
+
+/// The trie index is built up to this number of points.
+const TRIE_MAX_POINTS: usize = 1_000_000;
+
+/// Maximal number of queries of every case.
+const QUERIES: u32 = 50;
+
+/// Time every case is measured for at most, after its first query.
+const CASE_BUDGET: Duration = Duration::from_millis(200);
+
+/// Return the mean duration of the query over random inputs.
+fn mean<I, O, G, F>(mut input: G, mut query: F) -> Duration
+where
+    G: FnMut() -> I,
+    F: FnMut(I) -> O,
+{
+    let mut queries = 0;
+    let mut elapsed = Duration::from_secs(0);
+
+    while queries < QUERIES && (queries == 0 || elapsed < CASE_BUDGET) {
+        let input = input();
+        let start = Instant::now();
+
+        black_box(query(input));
+
+        elapsed += start.elapsed();
+        queries += 1;
+    }
+
+    elapsed / queries
+}
+
+/// Print the measured case.
+fn report(type_name: &str, len: usize, case: &str, count: usize, mean: Duration) {
+    println!(
+        "{:>4} points {:>9} {:<16} count {:>5}: {:>12?}, {:>12.0} queries/s",
+        type_name,
+        len,
+        case,
+        count,
+        mean,
+        1.0 / mean.as_secs_f64()
+    );
+}
+
+/// Measure all cases of the point type up to `max_points` points.
+fn bench<T>(type_name: &str, max_points: usize, rng: &mut StdRng)
+where
+    T: PrimInt + BitOps + Unsigned + Debug + Send + Sync,
+    Standard: Distribution<T>,
+{
+    for len in POINTS.iter().filter(|len| **len <= max_points) {
+        let points: Vec<T> = random_points(*len as u64, *len);
+
+        let mut indexes = vec![IndexKind::Sort];
+        if *len <= TRIE_MAX_POINTS {
+            indexes.push(IndexKind::Trie);
+        }
+
+        for index in indexes {
+            let xor_distance = XorDistance::builder(points.clone()).index(index).build();
+            let case = format!("closest {:?}", index);
+
+            for count in COUNTS.iter() {
+                let mean = mean(
+                    || Standard.sample(&mut *rng),
+                    |x| xor_distance.closest(x, *count),
+                );
+                report(type_name, *len, &case, *count, mean);
+            }
+
+            #[cfg(feature = "parallel")]
+            for count in COUNTS.iter() {
+                let mean = mean(
+                    || Standard.sample(&mut *rng),
+                    |x| xor_distance.closest_parallel(x, *count),
+                );
+                report(type_name, *len, "closest_parallel", *count, mean);
+            }
+
+            if index != IndexKind::Sort {
+                continue;
+            }
+
+            // The index makes no difference to the reverse.
+            for count in REVERSE_COUNTS.iter() {
+                let mean = mean(
+                    || xor_distance.closest(Standard.sample(&mut *rng), *count),
+                    |closest| xor_distance.reverse_closest(&closest),
+                );
+                report(type_name, *len, "reverse_closest", *count, mean);
+            }
+        }
+    }
+}
+
+fn main() {
+    // Cargo passes `--bench` to the benchmark, the only number is the maximal points.
+    let max_points = env::args()
+        .skip(1)
+        .find_map(|arg| arg.parse().ok())
+        .unwrap_or(usize::MAX);
+    let mut rng = StdRng::seed_from_u64(1521);
+
+    bench::<u32>("u32", max_points, &mut rng);
+    bench::<u64>("u64", max_points, &mut rng);
+    bench::<u128>("u128", max_points, &mut rng);
+}
@@ -0,0 +1,58 @@
+/*
+ * C interface of the closest points and their reverse over `uint64_t` points, see the `ffi`
+ * module of the xor-distance-exercise crate. Keep in sync with `src/ffi.rs`.
+ *
+ * Build the static library by
+ * `cargo rustc --lib --release --features ffi --crate-type staticlib`.
+ */
+
+#ifndef XOR_DISTANCE_H
+#define XOR_DISTANCE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The call succeeded. */
+#define XD_OK 0
+/* The closest points are not closest points of any position. */
+#define XD_NOT_FOUND 1
+/* A required pointer argument is null. */
+#define XD_NULL_POINTER -1
+
+/* Opaque handle of points. */
+typedef struct XdHandle XdHandle;
+
+/*
+ * Create a handle of the `len` points at `points`, NULL if `points` is NULL while `len` is not
+ * zero. The handle must be released by `xd_free`.
+ */
+XdHandle *xd_new(const uint64_t *points, size_t len);
+
+/*
+ * Write up to `count` closest points to the position `x` into `out`, ordered from the closest,
+ * and return how many were written. At most `out_len` points are written, nothing at all if
+ * `handle` or `out` is NULL.
+ */
+size_t xd_closest(const XdHandle *handle, uint64_t x, size_t count, uint64_t *out, size_t out_len);
+
+/*
+ * Write a position whose closest points are the `len` points at `closest` into `position`.
+ * Returns XD_OK if the position was written, XD_NOT_FOUND if there is no such a position and
+ * XD_NULL_POINTER if `handle` or `position` is NULL, or `closest` is NULL while `len` is not
+ * zero.
+ */
+int32_t xd_reverse_closest(const XdHandle *handle, const uint64_t *closest, size_t len,
+                           uint64_t *position);
+
+/* Release the handle, nothing is done for NULL. */
+void xd_free(XdHandle *handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* XOR_DISTANCE_H */
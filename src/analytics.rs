@@ -0,0 +1,170 @@
+//! Differentially private export of aggregate delivery statistics.
+//!
+//! Closest farms reveal a lot about a customer's position, as the reverse closest farms solving
+//! shows, so aggregates shared outside (dashboards, reports) get calibrated Laplace noise added.
+//! Every customer contributes to one farm count and one distance histogram bucket, hence the
+//! L1 sensitivity of the whole export is `2` and the noise scale is `2 / epsilon`.
+//!
+//! Available with the `rand` feature only.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate rand;
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::analytics::export_private;
+//! use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+//!
+//! let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+//! let customers: Vec<u64> = (0..1000).map(|position| position % 16).collect();
+//!
+//! let export = export_private(&delivery_system, &customers, 1.0, &mut rand::thread_rng());
+//!
+//! assert_eq!(3, export.farm_counts.len());
+//! assert_eq!(65, export.distance_histogram.len());
+//! ```
+
+use crate::delivery_system::FoodDeliverySystem;
+use num_traits::{PrimInt, Unsigned};
+use rand::Rng;
+
+/// L1 sensitivity of the whole export to adding or removing one customer.
+const SENSITIVITY: f64 = 2.0;
+
+/// Noisy aggregate statistics of customers assigned to their closest farms.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrivateExport<T> {
+    /// Noisy number of customers per farm, ordered by farm.
+    pub farm_counts: Vec<(T, f64)>,
+    /// Noisy number of customers per closest farm distance bucket. Bucket `0` counts zero
+    /// distances and bucket `i` distances from `[2^(i-1), 2^i)`.
+    pub distance_histogram: Vec<f64>,
+    /// Privacy budget the noise was calibrated for.
+    pub epsilon: f64,
+}
+
+/// Return per-farm counts and a distance histogram of customers assigned to their closest farm,
+/// with Laplace noise calibrated to `epsilon`-differential privacy.
+///
+/// Lower `epsilon` gives stronger privacy and noisier statistics. Farms themselves are public,
+/// so every farm is exported, with or without customers.
+///
+/// # Panics
+///
+/// Panics if `epsilon` is not positive.
+pub fn export_private<T, R>(
+    delivery_system: &FoodDeliverySystem<T>,
+    customers: &[T],
+    epsilon: f64,
+    rng: &mut R,
+) -> PrivateExport<T>
+where
+    T: PrimInt + Unsigned + 'static,
+    R: Rng + ?Sized,
+{
+    assert!(epsilon > 0.0, "Privacy budget must be positive!");
+
+    let xor_distance = delivery_system.xor_distance();
+    let bit_size = T::zero().count_zeros() as usize;
+
+    let mut farms: Vec<T> = xor_distance.points().copied().collect();
+    farms.sort();
+    farms.dedup();
+
+    let mut farm_counts = vec![0.0; farms.len()];
+    let mut distance_histogram = vec![0.0; bit_size + 1];
+
+    for position in customers {
+        if let Some(farm) = xor_distance.nth_closest(*position, 0) {
+            // The farm comes from the deduplicated farms.
+            let index = farms.binary_search(&farm).unwrap();
+            farm_counts[index] += 1.0;
+
+            let distance = farm ^ *position;
+            distance_histogram[bit_size - distance.leading_zeros() as usize] += 1.0;
+        }
+    }
+
+    let scale = SENSITIVITY / epsilon;
+
+    PrivateExport {
+        farm_counts: farms
+            .into_iter()
+            .zip(farm_counts)
+            .map(|(farm, count)| (farm, count + laplace(scale, rng)))
+            .collect(),
+        distance_histogram: distance_histogram
+            .into_iter()
+            .map(|count| count + laplace(scale, rng))
+            .collect(),
+        epsilon,
+    }
+}
+
+/// Sample Laplace distribution centered at zero with the `scale`.
+fn laplace<R: Rng + ?Sized>(scale: f64, rng: &mut R) -> f64 {
+    let uniform: f64 = rng.gen_range(-0.5, 0.5);
+
+    // The logarithm of zero is not finite.
+    if uniform == -0.5 {
+        return 0.0;
+    }
+
+    -scale * uniform.signum() * (1.0 - 2.0 * uniform.abs()).ln()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_private, laplace};
+    use crate::delivery_system::FoodDeliverySystem;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn export_close_to_exact() {
+        let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0, 8, 12, 8]);
+        let customers: Vec<u8> = (0..16).cycle().take(16_000).collect();
+        let mut rng = StdRng::seed_from_u64(7);
+
+        let export = export_private(&delivery_system, &customers, 1.0, &mut rng);
+
+        // Duplicated farm 8 is exported once.
+        let farms: Vec<u8> = export.farm_counts.iter().map(|(farm, _)| *farm).collect();
+        assert_eq!(vec![0, 8, 12], farms);
+
+        // Exact counts are 8000, 4000 and 4000, the noise scale is 2.
+        for ((_, count), exact) in export
+            .farm_counts
+            .iter()
+            .zip([8000.0, 4000.0, 4000.0].iter())
+        {
+            assert!((count - exact).abs() < 50.0);
+        }
+
+        assert_eq!(9, export.distance_histogram.len());
+        assert!((export.distance_histogram[0] - 3000.0).abs() < 50.0);
+        assert!(export.distance_histogram[8].abs() < 50.0);
+    }
+
+    #[test]
+    fn laplace_noise_scale() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let samples: Vec<f64> = (0..20_000).map(|_| laplace(3.0, &mut rng)).collect();
+
+        // Mean is zero and mean absolute deviation equals the scale.
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let deviation = samples.iter().map(|x| x.abs()).sum::<f64>() / samples.len() as f64;
+
+        assert!(mean.abs() < 0.1);
+        assert!((deviation - 3.0).abs() < 0.1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Privacy budget must be positive!")]
+    fn invalid_epsilon() {
+        let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0]);
+
+        export_private(&delivery_system, &[1], 0.0, &mut rand::thread_rng());
+    }
+}
@@ -0,0 +1,217 @@
+//! Batching of orders assigned to the same farm into combined dispatches.
+//!
+//! Orders assigned to a farm within a time window since the first of them are delivered
+//! together, so a dispatch is handed over to couriers as one unit instead of one per order.
+//! Time is any monotonic tick count chosen by the caller (seconds, simulation steps, ...).
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::batching::OrderBatcher;
+//! use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+//!
+//! let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+//! let mut batcher = OrderBatcher::new(10);
+//!
+//! for (position, time) in [(9, 0), (10, 3), (1, 4)].iter() {
+//!     let assignment = delivery_system.assign_order(*position).unwrap();
+//!
+//!     assert!(batcher.add_order(assignment, *time).is_empty());
+//! }
+//!
+//! // Orders 9 and 10 assigned to farm 8 are dispatched together once the window elapsed.
+//! let dispatches = batcher.flush(10);
+//!
+//! assert_eq!(1, dispatches.len());
+//! assert_eq!(8, dispatches[0].farm);
+//! assert_eq!(vec![9, 10], dispatches[0].orders);
+//! ```
+
+use crate::bitops::BitOps;
+use crate::hooks::Assignment;
+use num_traits::{PrimInt, Unsigned};
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+
+/// Orders delivered together from one farm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dispatch<T> {
+    /// Farm the orders are delivered from.
+    pub farm: T,
+    /// Customers' positions, in the order of arrival.
+    pub orders: Vec<T>,
+    /// Time the first order of the dispatch arrived.
+    pub opened_at: u64,
+}
+
+impl<T> Dispatch<T> {
+    /// Return number of orders in the dispatch.
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Return whether the dispatch has no orders.
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+}
+
+/// Hash stable across platforms and crate versions, the hashed data is the farm, number of orders
+/// as `u64`, the orders and the opening time, all as little-endian bytes.
+impl<T: PrimInt> Hash for Dispatch<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.farm.hash_stable(state);
+        (self.orders.len() as u64).hash_stable(state);
+
+        for order in self.orders.iter() {
+            order.hash_stable(state);
+        }
+
+        self.opened_at.hash_stable(state);
+    }
+}
+
+/// Groups assigned orders per farm into dispatches closed after a time window.
+pub struct OrderBatcher<T> {
+    window: u64,
+    open: BTreeMap<T, Dispatch<T>>,
+}
+
+impl<T: PrimInt + Unsigned> OrderBatcher<T> {
+    /// Create a new batcher closing dispatches `window` ticks after their first order arrived.
+    ///
+    /// Zero `window` disables batching, every order is dispatched on its own.
+    pub fn new(window: u64) -> Self {
+        OrderBatcher {
+            window,
+            open: BTreeMap::new(),
+        }
+    }
+
+    /// Return the batching window.
+    pub fn window(&self) -> u64 {
+        self.window
+    }
+
+    /// Return number of open dispatches.
+    pub fn open_dispatches(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Add an order arrived at `time` and return dispatches closed by then, ordered by farm.
+    ///
+    /// Time must not go backwards between calls.
+    pub fn add_order(&mut self, assignment: Assignment<T>, time: u64) -> Vec<Dispatch<T>> {
+        let mut dispatches = self.flush(time);
+
+        if self.window == 0 {
+            dispatches.push(Dispatch {
+                farm: assignment.farm,
+                orders: vec![assignment.position],
+                opened_at: time,
+            });
+
+            return dispatches;
+        }
+
+        self.open
+            .entry(assignment.farm)
+            .or_insert_with(|| Dispatch {
+                farm: assignment.farm,
+                orders: Vec::new(),
+                opened_at: time,
+            })
+            .orders
+            .push(assignment.position);
+
+        dispatches
+    }
+
+    /// Close and return dispatches whose window elapsed by `time`, ordered by farm.
+    pub fn flush(&mut self, time: u64) -> Vec<Dispatch<T>> {
+        let window = self.window;
+        let expired: Vec<T> = self
+            .open
+            .values()
+            .filter(|dispatch| time >= dispatch.opened_at.saturating_add(window))
+            .map(|dispatch| dispatch.farm)
+            .collect();
+
+        expired
+            .iter()
+            .filter_map(|farm| self.open.remove(farm))
+            .collect()
+    }
+
+    /// Close and return all open dispatches, ordered by farm.
+    pub fn flush_all(&mut self) -> Vec<Dispatch<T>> {
+        let open = std::mem::take(&mut self.open);
+
+        open.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Dispatch, OrderBatcher};
+    use crate::hooks::Assignment;
+
+    fn assignment(position: u64, farm: u64) -> Assignment<u64> {
+        Assignment {
+            position,
+            farm,
+            tier: None,
+        }
+    }
+
+    #[test]
+    fn batch_within_window() {
+        let mut batcher = OrderBatcher::new(5);
+
+        assert!(batcher.add_order(assignment(1, 0), 0).is_empty());
+        assert!(batcher.add_order(assignment(9, 8), 2).is_empty());
+        assert!(batcher.add_order(assignment(3, 0), 4).is_empty());
+        assert_eq!(2, batcher.open_dispatches());
+
+        // The window of farm 0 elapsed before the order arrived, so it opens a new dispatch.
+        assert_eq!(
+            vec![Dispatch {
+                farm: 0,
+                orders: vec![1, 3],
+                opened_at: 0,
+            }],
+            batcher.add_order(assignment(2, 0), 5)
+        );
+
+        assert_eq!(
+            vec![
+                Dispatch {
+                    farm: 0,
+                    orders: vec![2],
+                    opened_at: 5,
+                },
+                Dispatch {
+                    farm: 8,
+                    orders: vec![9],
+                    opened_at: 2,
+                },
+            ],
+            batcher.flush_all()
+        );
+        assert_eq!(0, batcher.open_dispatches());
+    }
+
+    #[test]
+    fn zero_window() {
+        let mut batcher = OrderBatcher::new(0);
+
+        let dispatches = batcher.add_order(assignment(1, 0), 0);
+        assert_eq!(1, dispatches.len());
+        assert_eq!(vec![1], dispatches[0].orders);
+
+        assert_eq!(1, batcher.add_order(assignment(2, 0), 0).len());
+        assert!(batcher.flush_all().is_empty());
+    }
+}
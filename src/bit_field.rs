@@ -0,0 +1,129 @@
+//! Declarative descriptors of bit fields packed into keys.
+//!
+//! Key layouts are defined once as constants, rather than as scattered shift-and-mask
+//! expressions.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::bit_field::BitField;
+//!
+//! // Key layout: region in the highest 16 bits, tenant in the next 16 bits, serial in the rest.
+//! const REGION: BitField = BitField::new(48, 16);
+//! const TENANT: BitField = BitField::new(32, 16);
+//! const SERIAL: BitField = BitField::new(0, 32);
+//!
+//! let key = SERIAL.insert(TENANT.insert(REGION.insert(0u64, 0xAB), 7), 1234);
+//!
+//! assert_eq!(0x00AB_0007_0000_04D2, key);
+//! assert_eq!(0xAB, REGION.extract(key));
+//! assert_eq!(7, TENANT.extract(key));
+//! assert_eq!(1234, SERIAL.extract(key));
+//! ```
+
+use crate::bitops::BitOps;
+
+/// Field of `width` bits starting at the bit index `offset` of a key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct BitField {
+    /// Index of the least significant bit of the field.
+    pub offset: usize,
+    /// Number of bits of the field.
+    pub width: usize,
+}
+
+impl BitField {
+    /// Create a new bit field descriptor.
+    pub const fn new(offset: usize, width: usize) -> Self {
+        BitField { offset, width }
+    }
+
+    /// Return the bit index one past the most significant bit of the field.
+    pub const fn end(&self) -> usize {
+        self.offset + self.width
+    }
+
+    /// Return mask of the field bits within a key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field doesn't fit into the key type.
+    pub fn mask<T: BitOps>(&self) -> T {
+        let mut mask = T::zero();
+        mask.copy_bits(!T::zero(), 0..self.width, self.offset);
+
+        mask
+    }
+
+    /// Extract value of the field from the key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field doesn't fit into the key type.
+    pub fn extract<T: BitOps>(&self, key: T) -> T {
+        let mut value = T::zero();
+        value.copy_bits(key, self.offset..self.end(), 0);
+
+        value
+    }
+
+    /// Return the key with the field set to the value, bits of the value beyond the field width
+    /// are ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the field doesn't fit into the key type.
+    pub fn insert<T: BitOps>(&self, key: T, value: T) -> T {
+        let mut key = key;
+        key.copy_bits(value, 0..self.width, self.offset);
+
+        key
+    }
+
+    /// Return whether the value fits into the field width.
+    pub fn fits<T: BitOps>(&self, value: T) -> bool {
+        self.width >= T::zero().count_zeros() as usize || value >> self.width == T::zero()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitField;
+
+    #[test]
+    fn extract_and_insert() {
+        let field = BitField::new(4, 4);
+
+        assert_eq!(0b1010, field.extract(0b1010_0101u8));
+        assert_eq!(0b0011_0101, field.insert(0b1010_0101u8, 0b0011));
+
+        // Bits above the width are ignored.
+        assert_eq!(0b1111_0101, field.insert(0b0000_0101u8, 0b1_1111));
+        assert!(!field.fits(0b1_1111u8));
+        assert!(field.fits(0b1111u8));
+    }
+
+    #[test]
+    fn full_width() {
+        let field = BitField::new(0, 64);
+
+        assert_eq!(u64::MAX, field.mask());
+        assert_eq!(42, field.extract(42u64));
+        assert_eq!(7, field.insert(42u64, 7));
+        assert!(field.fits(u64::MAX));
+    }
+
+    #[test]
+    fn mask() {
+        assert_eq!(0b0111_0000u8, BitField::new(4, 3).mask());
+        assert_eq!(0u32, BitField::new(5, 0).mask());
+    }
+
+    #[test]
+    #[should_panic(expected = "Source bits range is out of range!")]
+    fn field_out_of_key() {
+        BitField::new(60, 8).extract(0u64);
+    }
+}
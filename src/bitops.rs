@@ -23,7 +23,12 @@
 //! assert_eq!(0b1000_0000_1001_1011, y);
 //! ```
 
-use num_traits::PrimInt;
+use crate::error::BitsError;
+use core::cmp::Ordering;
+use core::hash::Hasher;
+use core::mem::size_of;
+use core::ops::Range;
+use num_traits::{PrimInt, Unsigned};
 
 /// Bit operations trait for any `Integer` type.
 pub trait BitOps: PrimInt {
@@ -68,7 +73,8 @@ pub trait BitOps: PrimInt {
     #[inline]
     fn is_flag_set(&self, flag: Self) -> bool {
         // The self has the "1" bit set on the same position as the flag.
-        *self & flag > Self::zero()
+        // Compared with zero by inequality, the sign bit flag of signed integers is negative.
+        *self & flag != Self::zero()
     }
 
     /// Set flag.
@@ -94,6 +100,29 @@ pub trait BitOps: PrimInt {
         *self = *self | flag;
     }
 
+    /// Clear flag.
+    ///
+    /// It doesn't check if the provided flag is really flag.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// let mut x = 0b1101;
+    /// let flag = 0b0100;
+    ///
+    /// x.clear_flag(flag);
+    ///
+    /// assert_eq!(0b1001, x);
+    /// ```
+    #[inline]
+    fn clear_flag(&mut self, flag: Self) {
+        *self = *self & !flag;
+    }
+
     /// Returns whether the bit on specified bit index is set to "1".
     ///
     /// # Panics
@@ -145,14 +174,542 @@ pub trait BitOps: PrimInt {
         // Check out if the prepared flag is set.
         self.set_flag(flag);
     }
+
+    /// Clear bit to "0" for specified bit index. Indexed from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit` is greater than the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// let mut x = 0b1010;
+    /// x.clear_bit(1);
+    ///
+    /// assert_eq!(0b1000, x);
+    /// ```
+    #[inline]
+    fn clear_bit(&mut self, bit_index: usize) {
+        self.clear_flag(Self::one() << bit_index);
+    }
+
+    /// Flip the bit on specified bit index, "0" to "1" and vice versa. Indexed from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit` is greater than the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// let mut x = 0b1010;
+    /// x.toggle_bit(0);
+    /// x.toggle_bit(3);
+    ///
+    /// assert_eq!(0b0011, x);
+    /// ```
+    #[inline]
+    fn toggle_bit(&mut self, bit_index: usize) {
+        *self = *self ^ (Self::one() << bit_index);
+    }
+
+    /// Set bit on specified bit index to "1" if `value` is `true` and to "0" otherwise. Indexed
+    /// from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit` is greater than the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// let mut x = 0b1010;
+    /// x.assign_bit(0, true);
+    /// x.assign_bit(1, false);
+    ///
+    /// assert_eq!(0b1001, x);
+    /// ```
+    #[inline]
+    fn assign_bit(&mut self, bit_index: usize, value: bool) {
+        if value {
+            self.set_bit(bit_index);
+        } else {
+            self.clear_bit(bit_index);
+        }
+    }
+
+    /// Return flag with the bit on specified bit index set to "1". Indexed from zero.
+    ///
+    /// Returns `Err(BitsError)` instead of overflowing the shift if `bit_index` is not less than
+    /// the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// assert_eq!(Ok(0b1000u8), u8::checked_shl_flag(3));
+    /// assert!(u8::checked_shl_flag(8).is_err());
+    /// ```
+    #[inline]
+    fn checked_shl_flag(bit_index: usize) -> Result<Self, BitsError> {
+        let size = Self::zero().count_zeros() as usize;
+
+        if bit_index >= size {
+            return Err(BitsError::IndexOutOfRange {
+                index: bit_index,
+                size,
+            });
+        }
+
+        Ok(Self::one() << bit_index)
+    }
+
+    /// Returns whether the bit on specified bit index is set to "1", `Err(BitsError)` if
+    /// `bit_index` is not less than the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// assert_eq!(Ok(true), 0b1000u8.checked_is_bit_set(3));
+    /// assert!(0b1000u8.checked_is_bit_set(8).is_err());
+    /// ```
+    #[inline]
+    fn checked_is_bit_set(&self, bit_index: usize) -> Result<bool, BitsError> {
+        Ok(self.is_flag_set(Self::checked_shl_flag(bit_index)?))
+    }
+
+    /// Set bit to "1" for specified bit index, `Err(BitsError)` if `bit_index` is not less than
+    /// the number of bits in this Integer. The number is left unchanged then. Indexed from zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// let mut x = 0b1000u8;
+    ///
+    /// assert!(x.checked_set_bit(1).is_ok());
+    /// assert!(x.checked_set_bit(8).is_err());
+    /// assert_eq!(0b1010, x);
+    /// ```
+    #[inline]
+    fn checked_set_bit(&mut self, bit_index: usize) -> Result<(), BitsError> {
+        self.set_flag(Self::checked_shl_flag(bit_index)?);
+
+        Ok(())
+    }
+
+    /// Swap values of two bits on specified bit indexes. Indexed from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the bit indexes is greater than the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// let mut x = 0b1000;
+    /// x.swap_bits(3, 0);
+    ///
+    /// assert_eq!(0b0001, x);
+    /// ```
+    #[inline]
+    fn swap_bits(&mut self, bit_index_a: usize, bit_index_b: usize) {
+        // Swapping differing bits is the same as flipping both of them.
+        if self.is_bit_set(bit_index_a) != self.is_bit_set(bit_index_b) {
+            let flags = (Self::one() << bit_index_a) | (Self::one() << bit_index_b);
+            *self = *self ^ flags;
+        }
+    }
+
+    /// Move value of the bit on `src` bit index to `dst` bit index and clear the `src` bit to "0".
+    /// Indexed from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the bit indexes is greater than the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// let mut x = 0b0110;
+    /// x.move_bit(1, 3);
+    ///
+    /// assert_eq!(0b1100, x);
+    /// ```
+    #[inline]
+    fn move_bit(&mut self, src: usize, dst: usize) {
+        let bit = self.is_bit_set(src);
+
+        // Clear both bits and set the destination one only if the source one was set.
+        *self = *self & !(Self::one() << src) & !(Self::one() << dst);
+
+        if bit {
+            self.set_bit(dst);
+        }
+    }
+
+    /// Copy bits of `src` within the `src_range` bit indexes range into this Integer, starting at
+    /// `dst_offset` bit index. Other bits are kept untouched. Indexed from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the source range or the destination range is not within the number of bits in
+    /// this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// let mut key = 0u16;
+    /// let region = 0b1011;
+    /// let serial = 0xFF;
+    ///
+    /// // Compose the key as `region | serial`.
+    /// key.copy_bits(region, 0..4, 8);
+    /// key.copy_bits(serial, 0..8, 0);
+    ///
+    /// assert_eq!(0b1011_1111_1111, key);
+    /// ```
+    #[inline]
+    fn copy_bits(&mut self, src: Self, src_range: Range<usize>, dst_offset: usize) {
+        let bit_size = Self::zero().count_zeros() as usize;
+
+        assert!(
+            src_range.start <= src_range.end && src_range.end <= bit_size,
+            "Source bits range is out of range!"
+        );
+
+        let width = src_range.end - src_range.start;
+
+        assert!(
+            dst_offset + width <= bit_size,
+            "Destination bits range is out of range!"
+        );
+
+        if width == 0 {
+            return;
+        }
+
+        let mask = Self::mask_low(width);
+        let field = src.bits_range(src_range.start, width);
+
+        *self = (*self & !(mask << dst_offset)) | (field << dst_offset);
+    }
+
+    /// Return mask with the lowest `len` bits set to "1".
+    ///
+    /// All bits are set if `len` is greater than or equal to the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// assert_eq!(0b0000_0111, u8::mask_low(3));
+    /// assert_eq!(0, u8::mask_low(0));
+    /// assert_eq!(u8::MAX, u8::mask_low(8));
+    /// ```
+    #[inline]
+    fn mask_low(len: usize) -> Self {
+        let bit_size = Self::zero().count_zeros() as usize;
+
+        // Shifting by the whole bit size would overflow.
+        if len >= bit_size {
+            !Self::zero()
+        } else {
+            // Subtracting one from the sign bit flag of signed numbers would overflow.
+            !(!Self::zero() << len)
+        }
+    }
+
+    /// Return mask with the highest `len` bits set to "1".
+    ///
+    /// All bits are set if `len` is greater than or equal to the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// assert_eq!(0b1110_0000, u8::mask_high(3));
+    /// assert_eq!(0, u8::mask_high(0));
+    /// assert_eq!(u8::MAX, u8::mask_high(8));
+    /// ```
+    #[inline]
+    fn mask_high(len: usize) -> Self {
+        let bit_size = Self::zero().count_zeros() as usize;
+
+        !Self::mask_low(bit_size.saturating_sub(len))
+    }
+
+    /// Return `len` bits starting at the `start` bit index, moved to the lowest bits. Indexed from
+    /// zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bits range is not within the number of bits in this Integer.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// let key = 0b1011_0110u8;
+    ///
+    /// // Region formed by the 4 most significant bits.
+    /// assert_eq!(0b1011, key.bits_range(4, 4));
+    /// assert_eq!(0b11, key.bits_range(1, 2));
+    /// assert_eq!(key, key.bits_range(0, 8));
+    /// ```
+    #[inline]
+    fn bits_range(&self, start: usize, len: usize) -> Self {
+        let bit_size = Self::zero().count_zeros() as usize;
+
+        assert!(
+            start.checked_add(len).is_some_and(|end| end <= bit_size),
+            "Bits range is out of range!"
+        );
+
+        if len == 0 {
+            return Self::zero();
+        }
+
+        // Signed numbers are shifted arithmetically, the mask drops the copies of the sign bit.
+        (*self >> start) & Self::mask_low(len)
+    }
+
+    /// Feed the number into the hasher as its little-endian bytes.
+    ///
+    /// Unlike `Hash` of primitive integers, which feeds native-endian bytes, the hashed data is
+    /// the same on every platform. Together with a stable `Hasher` it gives hashes that can be
+    /// persisted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use std::hash::Hasher;
+    /// use xor_distance_exercise::bitops::BitOps;
+    ///
+    /// // Hasher recording the fed bytes.
+    /// #[derive(Default)]
+    /// struct Bytes(Vec<u8>);
+    ///
+    /// impl Hasher for Bytes {
+    ///     fn finish(&self) -> u64 {
+    ///         0
+    ///     }
+    ///
+    ///     fn write(&mut self, bytes: &[u8]) {
+    ///         self.0.extend_from_slice(bytes);
+    ///     }
+    /// }
+    ///
+    /// let mut hasher = Bytes::default();
+    /// 0x0102u16.hash_stable(&mut hasher);
+    ///
+    /// assert_eq!(vec![0x02, 0x01], hasher.0);
+    /// ```
+    fn hash_stable<H: Hasher>(&self, state: &mut H) {
+        // Any primitive integer fits into `u128` or `i128`, whose lowest bytes are the same as the
+        // bytes of the number.
+        let bytes = match self.to_u128() {
+            Some(number) => number.to_le_bytes(),
+            None => self.to_i128().unwrap().to_le_bytes(),
+        };
+
+        state.write(&bytes[..size_of::<Self>()]);
+    }
 }
 
 /// Implements the `BitOps` trait for all 'Integer' types.
 impl<T> BitOps for T where T: PrimInt {}
 
+/// Bit operations over a `(hi, lo)` pair of `Unsigned Integer` types treated as one value of the
+/// double bit size, `hi` holding the most significant bits.
+///
+/// # Examples
+///
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use std::cmp::Ordering;
+/// use xor_distance_exercise::bitops::WideBitOps;
+///
+/// let a: (u64, u64) = (0, u64::MAX);
+/// let mut b: (u64, u64) = (0, 0);
+///
+/// // Bit 64 is the lowest bit of `hi`.
+/// b.wide_set_bit(64);
+///
+/// assert_eq!((1, 0), b);
+/// assert_eq!(Ordering::Less, a.wide_cmp(&b));
+/// assert_eq!(63, a.wide_xor(&b).wide_leading_zeros());
+/// ```
+pub trait WideBitOps: Sized {
+    /// Return bitwise xor of both values.
+    fn wide_xor(&self, other: &Self) -> Self;
+
+    /// Compare both values as numbers.
+    fn wide_cmp(&self, other: &Self) -> Ordering;
+
+    /// Return number of leading zeros of the whole value.
+    fn wide_leading_zeros(&self) -> u32;
+
+    /// Returns whether the bit on specified bit index of the whole value is set to "1".
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_index` is greater than or equal to the double bit size.
+    fn wide_is_bit_set(&self, bit_index: usize) -> bool;
+
+    /// Set bit to "1" for specified bit index of the whole value. Indexed from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_index` is greater than or equal to the double bit size.
+    fn wide_set_bit(&mut self, bit_index: usize);
+
+    /// Clear bit to "0" for specified bit index of the whole value. Indexed from zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_index` is greater than or equal to the double bit size.
+    fn wide_clear_bit(&mut self, bit_index: usize);
+}
+
+impl<T: BitOps + Unsigned> WideBitOps for (T, T) {
+    #[inline]
+    fn wide_xor(&self, other: &Self) -> Self {
+        (self.0 ^ other.0, self.1 ^ other.1)
+    }
+
+    #[inline]
+    fn wide_cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+
+    #[inline]
+    fn wide_leading_zeros(&self) -> u32 {
+        if self.0 == T::zero() {
+            T::zero().count_zeros() + self.1.leading_zeros()
+        } else {
+            self.0.leading_zeros()
+        }
+    }
+
+    #[inline]
+    fn wide_is_bit_set(&self, bit_index: usize) -> bool {
+        let (half, bit_index) = wide_bit(self, bit_index);
+
+        half.is_bit_set(bit_index)
+    }
+
+    #[inline]
+    fn wide_set_bit(&mut self, bit_index: usize) {
+        let half_size = T::zero().count_zeros() as usize;
+        check_wide_bit_index::<T>(bit_index);
+
+        if bit_index < half_size {
+            self.1.set_bit(bit_index);
+        } else {
+            self.0.set_bit(bit_index - half_size);
+        }
+    }
+
+    #[inline]
+    fn wide_clear_bit(&mut self, bit_index: usize) {
+        let half_size = T::zero().count_zeros() as usize;
+        check_wide_bit_index::<T>(bit_index);
+
+        if bit_index < half_size {
+            self.1 = self.1 & !(T::one() << bit_index);
+        } else {
+            self.0 = self.0 & !(T::one() << (bit_index - half_size));
+        }
+    }
+}
+
+/// Return the half of the pair holding the bit and the bit index within it.
+fn wide_bit<T: BitOps>(pair: &(T, T), bit_index: usize) -> (T, usize) {
+    let half_size = T::zero().count_zeros() as usize;
+    check_wide_bit_index::<T>(bit_index);
+
+    if bit_index < half_size {
+        (pair.1, bit_index)
+    } else {
+        (pair.0, bit_index - half_size)
+    }
+}
+
+fn check_wide_bit_index<T: BitOps>(bit_index: usize) {
+    assert!(
+        bit_index < 2 * T::zero().count_zeros() as usize,
+        "Bit index is out of range!"
+    );
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::bitops::BitOps;
+    use crate::bitops::{BitOps, WideBitOps};
+    use crate::error::BitsError;
+    use std::cmp::Ordering;
+    use std::hash::Hasher;
+
+    /// Hasher recording the fed bytes.
+    #[derive(Default)]
+    struct Bytes(Vec<u8>);
+
+    impl Hasher for Bytes {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+    }
 
     #[test]
     fn check_is_flag() {
@@ -208,6 +765,59 @@ mod tests {
         assert_eq!(0b1011, x);
     }
 
+    #[test]
+    fn check_clear_flag() {
+        let mut x = 0b1011;
+
+        // Clear flag for second bit.
+        x.clear_flag(0b0010);
+        assert_eq!(0b1001, x);
+
+        // Clearing not set flag does not change the number.
+        x.clear_flag(0b0100);
+        assert_eq!(0b1001, x);
+    }
+
+    #[test]
+    fn check_clear_toggle_assign_bit() {
+        let mut x = 0b1011u8;
+
+        x.clear_bit(0);
+        assert_eq!(0b1010, x);
+
+        // Clearing not set bit does not change the number.
+        x.clear_bit(2);
+        assert_eq!(0b1010, x);
+
+        x.toggle_bit(7);
+        assert_eq!(0b1000_1010, x);
+        x.toggle_bit(7);
+        assert_eq!(0b1010, x);
+
+        x.assign_bit(2, true);
+        assert_eq!(0b1110, x);
+        x.assign_bit(3, false);
+        assert_eq!(0b0110, x);
+        // Assigning the current value does not change the number.
+        x.assign_bit(1, true);
+        assert_eq!(0b0110, x);
+
+        // Test signed numbers.
+        let mut y = -1i8;
+        y.clear_bit(7);
+        assert_eq!(i8::MAX, y);
+        y.toggle_bit(7);
+        assert_eq!(-1, y);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to shift left with overflow")]
+    fn clear_bit_index_out_of_range() {
+        let bit_out_of_range = 64;
+
+        0u64.clear_bit(bit_out_of_range);
+    }
+
     #[test]
     fn check_is_bit_set() {
         let x = 0b1011;
@@ -261,4 +871,217 @@ mod tests {
         // Bit are indexed from 0 so bit on position 64 has bit index 63.
         0u64.is_bit_set(bit_out_of_range);
     }
+
+    #[test]
+    fn check_swap_bits() {
+        let mut x = 0b0101;
+
+        // Swapping bits of the same value doesn't change anything.
+        x.swap_bits(0, 2);
+        assert_eq!(0b0101, x);
+
+        // Swap differing bits.
+        x.swap_bits(0, 1);
+        assert_eq!(0b0110, x);
+
+        // Swapping bit with itself doesn't change anything.
+        x.swap_bits(1, 1);
+        assert_eq!(0b0110, x);
+
+        // Swap the most significant bit.
+        let mut y = 1u8;
+        y.swap_bits(0, 7);
+        assert_eq!(0b1000_0000, y);
+    }
+
+    #[test]
+    fn check_move_bit() {
+        let mut x = 0b1001;
+
+        // Moving "1" bit overrides "0" bit.
+        x.move_bit(0, 1);
+        assert_eq!(0b1010, x);
+
+        // Moving "0" bit overrides "1" bit.
+        x.move_bit(2, 3);
+        assert_eq!(0b0010, x);
+
+        // Moving bit onto itself doesn't change anything.
+        x.move_bit(1, 1);
+        assert_eq!(0b0010, x);
+    }
+
+    #[test]
+    #[should_panic(expected = "attempt to shift left with overflow")]
+    fn swap_bits_index_out_of_range() {
+        let mut x = 1u64;
+
+        x.swap_bits(0, 64);
+    }
+
+    #[test]
+    fn check_copy_bits() {
+        let mut x = 0b1111_0000u8;
+
+        // Copy into the middle, untouched bits are kept.
+        x.copy_bits(0b0000_0010, 1..3, 3);
+        assert_eq!(0b1110_1000, x);
+
+        // Empty range doesn't change anything.
+        x.copy_bits(0xFF, 4..4, 0);
+        assert_eq!(0b1110_1000, x);
+
+        // Copy the whole width.
+        x.copy_bits(0b1010_1010, 0..8, 0);
+        assert_eq!(0b1010_1010, x);
+
+        // Copy the most significant bits into the lowest ones.
+        let mut y = 0u64;
+        y.copy_bits(u64::MAX, 60..64, 0);
+        assert_eq!(0b1111, y);
+
+        // Signed numbers are copied by their bits representation.
+        let mut z = 0i8;
+        z.copy_bits(-1, 0..8, 0);
+        assert_eq!(-1, z);
+    }
+
+    #[test]
+    #[should_panic(expected = "Destination bits range is out of range!")]
+    fn copy_bits_destination_out_of_range() {
+        let mut x = 0u8;
+
+        x.copy_bits(0xFF, 0..4, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "Source bits range is out of range!")]
+    fn copy_bits_source_out_of_range() {
+        let mut x = 0u8;
+
+        x.copy_bits(0xFF, 4..9, 0);
+    }
+
+    #[test]
+    fn masks() {
+        assert_eq!(0, u64::mask_low(0));
+        assert_eq!(0xFFFF, u64::mask_low(16));
+        assert_eq!(u64::MAX, u64::mask_low(64));
+        assert_eq!(u64::MAX, u64::mask_low(100));
+
+        assert_eq!(0, u64::mask_high(0));
+        assert_eq!(0xFFFF_0000_0000_0000, u64::mask_high(16));
+        assert_eq!(u64::MAX, u64::mask_high(64));
+        assert_eq!(u64::MAX, u64::mask_high(100));
+
+        // Masks complement each other.
+        for len in 0..=128 {
+            assert_eq!(u128::MAX, u128::mask_low(len) ^ u128::mask_high(128 - len));
+        }
+
+        // Test signed numbers.
+        assert_eq!(i8::MAX, i8::mask_low(7));
+        assert_eq!(i8::MIN, i8::mask_high(1));
+        assert_eq!(-1, i8::mask_high(8));
+    }
+
+    #[test]
+    fn bits_range() {
+        let x = 0xABCD_u16;
+
+        assert_eq!(0xD, x.bits_range(0, 4));
+        assert_eq!(0xBC, x.bits_range(4, 8));
+        assert_eq!(0xA, x.bits_range(12, 4));
+        assert_eq!(x, x.bits_range(0, 16));
+        assert_eq!(0, x.bits_range(16, 0));
+
+        // Copies of the sign bit of signed numbers are dropped.
+        assert_eq!(0b11, (-1i8).bits_range(6, 2));
+        assert_eq!(0b1, i64::MIN.bits_range(63, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Bits range is out of range!")]
+    fn bits_range_out_of_range() {
+        0u16.bits_range(12, 5);
+    }
+
+    #[test]
+    fn hash_stable() {
+        let mut hasher = Bytes::default();
+
+        0xABu8.hash_stable(&mut hasher);
+        (-2i16).hash_stable(&mut hasher);
+        0x0102_0304u32.hash_stable(&mut hasher);
+
+        assert_eq!(vec![0xAB, 0xFE, 0xFF, 0x04, 0x03, 0x02, 0x01], hasher.0);
+
+        let mut hasher = Bytes::default();
+        (1u128 << 127).hash_stable(&mut hasher);
+        i128::MIN.hash_stable(&mut hasher);
+
+        let mut expected = vec![0; 32];
+        expected[15] = 0x80;
+        expected[31] = 0x80;
+        assert_eq!(expected, hasher.0);
+    }
+
+    #[test]
+    fn wide_bit_ops() {
+        let mut x: (u8, u8) = (0, 0);
+
+        x.wide_set_bit(7);
+        x.wide_set_bit(8);
+        assert_eq!((0b1, 0b1000_0000), x);
+        assert!(x.wide_is_bit_set(8));
+        assert!(!x.wide_is_bit_set(15));
+        assert_eq!(7, x.wide_leading_zeros());
+
+        x.wide_clear_bit(8);
+        assert_eq!((0, 0b1000_0000), x);
+        assert_eq!(8, x.wide_leading_zeros());
+        assert_eq!(16, (0u8, 0u8).wide_leading_zeros());
+
+        assert_eq!((0b11, 0b1), (0b01u8, 0b1u8).wide_xor(&(0b10, 0)));
+    }
+
+    #[test]
+    fn wide_cmp() {
+        assert_eq!(Ordering::Greater, (1u32, 0u32).wide_cmp(&(0, u32::MAX)));
+        assert_eq!(Ordering::Less, (1u32, 1u32).wide_cmp(&(1, 2)));
+        assert_eq!(Ordering::Equal, (7u32, 7u32).wide_cmp(&(7, 7)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Bit index is out of range!")]
+    fn wide_bit_index_out_of_range() {
+        (0u8, 0u8).wide_is_bit_set(16);
+    }
+
+    #[test]
+    fn checked_bits() {
+        assert_eq!(Ok(1u64 << 63), u64::checked_shl_flag(63));
+        assert_eq!(
+            Err(BitsError::IndexOutOfRange {
+                index: 64,
+                size: 64
+            }),
+            u64::checked_shl_flag(64)
+        );
+        assert_eq!(Ok(i8::MIN), i8::checked_shl_flag(7));
+
+        let mut x = 0u16;
+        assert_eq!(Ok(()), x.checked_set_bit(15));
+        assert_eq!(Ok(true), x.checked_is_bit_set(15));
+        assert_eq!(Ok(false), x.checked_is_bit_set(0));
+        assert_eq!(
+            Err(BitsError::IndexOutOfRange {
+                index: 16,
+                size: 16
+            }),
+            x.checked_set_bit(16)
+        );
+        assert!(x.checked_is_bit_set(usize::MAX).is_err());
+        assert_eq!(1 << 15, x);
+    }
 }
@@ -1,8 +1,15 @@
 //! Bits representation for any `Integer`.
 
 use crate::bitops::BitOps;
+use crate::error::BitsError;
+use crate::key::XorKey;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::hash::{Hash, Hasher};
+use core::mem::size_of;
 use num_traits::PrimInt;
-use std::mem::size_of;
 
 /// Bits representation.
 ///
@@ -25,6 +32,7 @@ use std::mem::size_of;
 /// bit_rep.is_bit_decided(4);
 /// let number = bit_rep.form_zero_padded_number::<u64>().unwrap();
 /// ```
+#[derive(Clone)]
 pub struct Bits {
     bits: Vec<Option<bool>>,
     size: usize,
@@ -53,6 +61,75 @@ impl Bits {
         Bits { bits, size }
     }
 
+    /// Create a new representation of Bits of the size, e.g. of a `XorKey` wider than any native
+    /// integer.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// assert_eq!(160, Bits::with_size(160).size());
+    /// ```
+    pub fn with_size(size: usize) -> Self {
+        Bits {
+            bits: vec![None; size],
+            size,
+        }
+    }
+
+    /// Create a new representation of Bits of the number, with all bits decided.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let bit_rep = Bits::from_number(0b0110u8);
+    ///
+    /// assert_eq!(Some(true), bit_rep.get_bit(1));
+    /// assert_eq!(Some(false), bit_rep.get_bit(7));
+    /// assert_eq!(Ok(0b0110u8), bit_rep.to_number());
+    /// ```
+    pub fn from_number<T: PrimInt>(number: T) -> Self {
+        let size = Self::bit_size::<T>();
+
+        Bits {
+            bits: (0..size)
+                .map(|index| Some(number.is_bit_set(index)))
+                .collect(),
+            size,
+        }
+    }
+
+    /// Create a new representation of Bits of the key, with all bits decided.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut id = [0u8; 64];
+    /// id[0] = 0x80;
+    ///
+    /// let bit_rep = Bits::from_key(id);
+    ///
+    /// assert_eq!(512, bit_rep.size());
+    /// assert_eq!(Some(true), bit_rep.get_bit(511));
+    /// assert_eq!(Ok(id), bit_rep.form_zero_padded_key());
+    /// ```
+    pub fn from_key<K: XorKey>(key: K) -> Self {
+        Bits {
+            bits: (0..K::BIT_SIZE)
+                .map(|index| Some(key.is_bit_set(index)))
+                .collect(),
+            size: K::BIT_SIZE,
+        }
+    }
+
     /// Return bit size of the type being represented in bits.
     /// # Examples
     /// ```
@@ -72,7 +149,676 @@ impl Bits {
         byte_size * 8
     }
 
-    /// Get bit value for the index.
+    /// Return number of bits of the representation.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// assert_eq!(16, Bits::new::<u16>().size());
+    /// ```
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Get bit value for the index.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let bit_rep = Bits::new::<u64>();
+    /// let bit = bit_rep.get_bit(4);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn get_bit(&self, index: usize) -> Option<bool> {
+        self.bits[index]
+    }
+
+    /// Set new bit value for the index.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::new::<u64>();
+    /// bit_rep.set_bit(4, true);
+    /// bit_rep.set_bit(5, false);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn set_bit(&mut self, index: usize, val: bool) {
+        self.bits[index] = Some(val);
+    }
+
+    /// Return the index if it is within the size of the bits representation, `None` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let bit_rep = Bits::new::<u8>();
+    ///
+    /// assert_eq!(Some(7), bit_rep.checked_bit_index(7));
+    /// assert_eq!(None, bit_rep.checked_bit_index(8));
+    /// ```
+    pub fn checked_bit_index(&self, index: usize) -> Option<usize> {
+        if index < self.size {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    /// Get bit value for the index, `Err(BitsError)` if `index` is out of range.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    /// use xor_distance_exercise::error::BitsError;
+    ///
+    /// let mut bit_rep = Bits::new::<u8>();
+    /// bit_rep.set_bit(4, true);
+    ///
+    /// assert_eq!(Ok(Some(true)), bit_rep.try_get_bit(4));
+    /// assert_eq!(Ok(None), bit_rep.try_get_bit(5));
+    /// assert_eq!(
+    ///     Err(BitsError::IndexOutOfRange { index: 8, size: 8 }),
+    ///     bit_rep.try_get_bit(8)
+    /// );
+    /// ```
+    pub fn try_get_bit(&self, index: usize) -> Result<Option<bool>, BitsError> {
+        let index = self.checked_index(index)?;
+
+        Ok(self.bits[index])
+    }
+
+    /// Set new bit value for the index, `Err(BitsError)` if `index` is out of range. The
+    /// representation is left unchanged then.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::new::<u8>();
+    ///
+    /// assert!(bit_rep.try_set_bit(4, true).is_ok());
+    /// assert!(bit_rep.try_set_bit(8, true).is_err());
+    /// assert_eq!(Some(true), bit_rep.get_bit(4));
+    /// ```
+    pub fn try_set_bit(&mut self, index: usize, val: bool) -> Result<(), BitsError> {
+        let index = self.checked_index(index)?;
+
+        self.bits[index] = Some(val);
+
+        Ok(())
+    }
+
+    /// Set new bit value complying with constrains, already decided bit value can not be changed.
+    ///
+    /// Returns `Ok(())` in case constrains were not violated, `Err(BitsError)` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::new::<u64>();
+    /// bit_rep.set_bit_within_constrains(4, true);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn set_bit_within_constrains(&mut self, index: usize, val: bool) -> Result<(), BitsError> {
+        match self.bits[index] {
+            // Existing bit with a different value is a breach of constrains.
+            Some(bit) if bit != val => {
+                return Err(BitsError::ConstraintViolation {
+                    index,
+                    existing: bit,
+                    requested: val,
+                })
+            }
+            // The value is already present, nothing to do here.
+            Some(_) => {}
+            // No value set as yet so just assign it.
+            None => self.bits[index] = Some(val),
+        }
+
+        Ok(())
+    }
+
+    /// Set all bit values complying with constrains, or none of them.
+    ///
+    /// Assignments are applied in order, the first one violating constrains (of already decided
+    /// bits or of the previous assignments) rolls back all the previous ones.
+    ///
+    /// Returns `Ok(())` in case constrains were not violated, `Err(BitsError)` otherwise. Any index
+    /// out of range is an `Err(BitsError::IndexOutOfRange)`, no assignment is applied then.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::new::<u8>();
+    /// bit_rep.set_bit(4, true);
+    ///
+    /// assert!(bit_rep.set_many(&[(0, true), (4, false)]).is_err());
+    /// assert_eq!(None, bit_rep.get_bit(0));
+    ///
+    /// assert!(bit_rep.set_many(&[(0, true), (4, true)]).is_ok());
+    /// assert_eq!(Some(true), bit_rep.get_bit(0));
+    /// ```
+    pub fn set_many(&mut self, assignments: &[(usize, bool)]) -> Result<(), BitsError> {
+        for (index, _) in assignments.iter() {
+            self.checked_index(*index)?;
+        }
+
+        let mut applied = Vec::with_capacity(assignments.len());
+
+        for (index, val) in assignments.iter() {
+            let decided = self.is_bit_decided(*index);
+
+            if let Err(err) = self.set_bit_within_constrains(*index, *val) {
+                // Undecide only bits decided by this batch.
+                for index in applied {
+                    self.bits[index] = None;
+                }
+
+                return Err(err);
+            }
+
+            if !decided {
+                applied.push(*index);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Is bit decided already?
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let bit_rep = Bits::new::<u64>();
+    /// bit_rep.is_bit_decided(4);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of range.
+    pub fn is_bit_decided(&self, index: usize) -> bool {
+        let bit = self.bits[index];
+
+        bit.is_some()
+    }
+
+    /// Return number of undecided bits.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::new::<u8>();
+    /// bit_rep.set_bit(4, true);
+    ///
+    /// assert_eq!(7, bit_rep.undecided_count());
+    /// ```
+    pub fn undecided_count(&self) -> usize {
+        self.bits
+            .iter()
+            .copied()
+            .filter(|bit| bit.is_none())
+            .count()
+    }
+
+    /// Return an iterator over all bits as `(index, value)` pairs, from the least significant bit,
+    /// where the value is `None` for undecided bits.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::with_size(3);
+    /// bit_rep.set_bit(1, true);
+    ///
+    /// let bits: Vec<(usize, Option<bool>)> = bit_rep.iter().collect();
+    /// assert_eq!(vec![(0, None), (1, Some(true)), (2, None)], bits);
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = (usize, Option<bool>)> + '_ {
+        self.bits.iter().copied().enumerate()
+    }
+
+    /// Return an iterator over indices of the decided bits, in ascending order.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::new::<u8>();
+    /// bit_rep.set_bit(1, true);
+    /// bit_rep.set_bit(6, false);
+    ///
+    /// assert_eq!(vec![1, 6], bit_rep.decided_indices().collect::<Vec<usize>>());
+    /// ```
+    pub fn decided_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter()
+            .filter(|(_, bit)| bit.is_some())
+            .map(|(index, _)| index)
+    }
+
+    /// Return an iterator over indices of the undecided bits, in ascending order.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::with_size(4);
+    /// bit_rep.set_bit(1, true);
+    ///
+    /// assert_eq!(vec![0, 2, 3], bit_rep.undecided_indices().collect::<Vec<usize>>());
+    /// ```
+    pub fn undecided_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        self.iter()
+            .filter(|(_, bit)| bit.is_none())
+            .map(|(index, _)| index)
+    }
+
+    /// Form and return a number based on bits representation, pad/fill undecided bits by zeros.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let bit_rep = Bits::new::<u64>();
+    /// let number = bit_rep.form_zero_padded_number::<u64>().unwrap();
+    /// ```
+    pub fn form_zero_padded_number<T: PrimInt>(&self) -> Result<T, BitsError> {
+        self.check_bit_width::<T>()?;
+
+        // Initialize the number with "0".
+        let mut number: T = T::zero();
+
+        // Construct the number by incorporating in all bits.
+        for index in 0..self.size {
+            self.incorporate_bit(index, false, &mut number)?;
+        }
+
+        Ok(number)
+    }
+
+    /// Form and return a number based on bits representation, pad/fill undecided bits by ones.
+    ///
+    /// Bits of the number beyond the representation size are zeros, as by
+    /// `form_zero_padded_number`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::new::<u8>();
+    /// bit_rep.set_bit(0, false);
+    ///
+    /// assert_eq!(Ok(0b1111_1110u8), bit_rep.form_one_padded_number());
+    /// assert_eq!(Ok(0b1111_1110u16), bit_rep.form_one_padded_number());
+    /// ```
+    pub fn form_one_padded_number<T: PrimInt>(&self) -> Result<T, BitsError> {
+        self.check_bit_width::<T>()?;
+
+        let mut number: T = T::zero();
+
+        for index in 0..self.size {
+            self.incorporate_bit(index, true, &mut number)?;
+        }
+
+        Ok(number)
+    }
+
+    /// Return the number of a representation with all bits decided.
+    ///
+    /// Returns `Err(BitsError)` if any bit is undecided or the number type has not enough bits.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// assert_eq!(Ok(-2i16), Bits::from_number(-2i16).to_number());
+    /// assert!(Bits::new::<u8>().to_number::<u8>().is_err());
+    /// ```
+    pub fn to_number<T: PrimInt>(&self) -> Result<T, BitsError> {
+        if let Some(index) = self.bits.iter().copied().position(|bit| bit.is_none()) {
+            return Err(BitsError::Undecided { index });
+        }
+
+        self.form_zero_padded_number()
+    }
+
+    /// Form and return a key based on bits representation, pad/fill undecided bits by zeros.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::with_size(160);
+    /// bit_rep.set_bit(159, true);
+    ///
+    /// let key = bit_rep.form_zero_padded_key::<[u8; 20]>().unwrap();
+    /// assert_eq!(0x80, key[0]);
+    /// ```
+    pub fn form_zero_padded_key<K: XorKey>(&self) -> Result<K, BitsError> {
+        if K::BIT_SIZE < self.size {
+            return Err(BitsError::InsufficientBitWidth {
+                required: self.size,
+                available: K::BIT_SIZE,
+            });
+        }
+
+        let mut key = K::zero();
+
+        // The key has enough bits for all bits of the representation.
+        for (index, bit) in self.bits.iter().enumerate() {
+            if *bit == Some(true) {
+                key.set_bit(index);
+            }
+        }
+
+        Ok(key)
+    }
+
+    /// Form and return big-endian bytes based on bits representation, pad/fill undecided bits by
+    /// zeros, of any size independent of a Rust type.
+    ///
+    /// The first byte holds the most significant bits, the size is rounded up to whole bytes by
+    /// leading zeros.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut bit_rep = Bits::with_size(12);
+    /// bit_rep.set_bit(11, true);
+    /// bit_rep.set_bit(0, true);
+    ///
+    /// assert_eq!(Ok(vec![0b1000, 0b1]), bit_rep.form_zero_padded_bytes());
+    /// ```
+    pub fn form_zero_padded_bytes(&self) -> Result<Vec<u8>, BitsError> {
+        let mut bytes = vec![0u8; self.size.div_ceil(8)];
+        let len = bytes.len();
+
+        for (index, bit) in self.bits.iter().enumerate() {
+            if *bit == Some(true) {
+                bytes[len - 1 - index / 8] |= 1 << (index % 8);
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    /// Return big-endian bytes of the fully decided bits representation, the first byte holds the
+    /// most significant bits.
+    ///
+    /// Returns `Err(BitsError)` if any bit is undecided.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let bit_rep = Bits::from_number(0x0102u16);
+    ///
+    /// assert_eq!(Ok(vec![0x01, 0x02]), bit_rep.to_bytes_be());
+    /// assert!(Bits::with_size(16).to_bytes_be().is_err());
+    /// ```
+    pub fn to_bytes_be(&self) -> Result<Vec<u8>, BitsError> {
+        if let Some(index) = self.bits.iter().copied().position(|bit| bit.is_none()) {
+            return Err(BitsError::Undecided { index });
+        }
+
+        self.form_zero_padded_bytes()
+    }
+
+    /// Return little-endian bytes of the fully decided bits representation, the first byte holds
+    /// the least significant bits.
+    ///
+    /// Returns `Err(BitsError)` if any bit is undecided.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let bit_rep = Bits::from_number(0x0102u16);
+    ///
+    /// assert_eq!(Ok(vec![0x02, 0x01]), bit_rep.to_bytes_le());
+    /// ```
+    pub fn to_bytes_le(&self) -> Result<Vec<u8>, BitsError> {
+        let mut bytes = self.to_bytes_be()?;
+        bytes.reverse();
+
+        Ok(bytes)
+    }
+
+    /// Create a new representation of Bits of the big-endian bytes, with all bits decided.
+    ///
+    /// The inverse of `to_bytes_be`, the size is the number of bits of the bytes.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let bit_rep = Bits::from_bytes(&[0x01, 0x02]);
+    ///
+    /// assert_eq!(16, bit_rep.size());
+    /// assert_eq!(Ok(0x0102u16), bit_rep.to_number());
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let size = 8 * bytes.len();
+
+        Bits {
+            bits: (0..size)
+                .map(|index| Some(bytes[bytes.len() - 1 - index / 8] & (1 << (index % 8)) != 0))
+                .collect(),
+            size,
+        }
+    }
+
+    /// Return hexadecimal digits of the bits representation from the most significant ones, a
+    /// digit with any undecided bit is rendered as `?`.
+    ///
+    /// The size is rounded up to whole digits by leading zeros.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let bit_rep = Bits::from_number(0xA5u8);
+    /// assert_eq!("a5", bit_rep.to_hex());
+    ///
+    /// let mut bit_rep = Bits::with_size(10);
+    /// bit_rep
+    ///     .set_many(&[(9, true), (8, false), (0, true), (1, true), (2, false), (3, true)])
+    ///     .unwrap();
+    /// assert_eq!("2?b", bit_rep.to_hex());
+    /// ```
+    pub fn to_hex(&self) -> String {
+        self.bits
+            .chunks(4)
+            .rev()
+            .map(|nibble| {
+                nibble
+                    .iter()
+                    .rev()
+                    .try_fold(0, |digit, bit| bit.map(|bit| 2 * digit + bit as u32))
+                    .and_then(|digit| char::from_digit(digit, 16))
+                    .unwrap_or('?')
+            })
+            .collect()
+    }
+
+    /// Intersect two bits representations, the result has every bit decided in any of them.
+    ///
+    /// Returns `Ok(Bits)` if no bit is decided differently in the two representations,
+    /// `Err(BitsError)` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut a = Bits::new::<u8>();
+    /// a.set_bit(0, true);
+    ///
+    /// let mut b = Bits::new::<u8>();
+    /// b.set_bit(1, false);
+    ///
+    /// let intersection = a.intersect(&b).unwrap();
+    ///
+    /// assert_eq!(Some(true), intersection.get_bit(0));
+    /// assert_eq!(Some(false), intersection.get_bit(1));
+    /// ```
+    pub fn intersect(&self, other: &Bits) -> Result<Bits, BitsError> {
+        self.check_same_size(other)?;
+
+        let mut intersection = Bits {
+            bits: self.bits.clone(),
+            size: self.size,
+        };
+
+        for (index, bit) in other.bits.iter().enumerate() {
+            if let Some(val) = bit {
+                intersection.set_bit_within_constrains(index, *val)?;
+            }
+        }
+
+        Ok(intersection)
+    }
+
+    /// Relax two bits representations, the result keeps decided only bits decided identically in
+    /// both of them.
+    ///
+    /// Returns `Err(BitsError)` if the representations have different sizes.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut a = Bits::new::<u8>();
+    /// a.set_bit(0, true);
+    /// a.set_bit(1, true);
+    ///
+    /// let mut b = Bits::new::<u8>();
+    /// b.set_bit(0, true);
+    /// b.set_bit(1, false);
+    ///
+    /// let envelope = a.union_envelope(&b).unwrap();
+    ///
+    /// assert_eq!(Some(true), envelope.get_bit(0));
+    /// assert_eq!(None, envelope.get_bit(1));
+    /// ```
+    pub fn union_envelope(&self, other: &Bits) -> Result<Bits, BitsError> {
+        self.check_same_size(other)?;
+
+        let bits = self
+            .bits
+            .iter()
+            .zip(other.bits.iter())
+            .map(|(a, b)| if a == b { *a } else { None })
+            .collect();
+
+        Ok(Bits {
+            bits,
+            size: self.size,
+        })
+    }
+
+    /// Bitwise AND of two bits representations, a bit is decided if its value follows from the
+    /// decided bits alone: "0" if any of them is "0", "1" if both of them are "1".
+    ///
+    /// Returns `Err(BitsError)` if the representations have different sizes.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::bits::Bits;
+    ///
+    /// let mut a = Bits::new::<u8>();
+    /// a.set_bit(0, false);
+    /// a.set_bit(1, true);
+    ///
+    /// let mut b = Bits::new::<u8>();
+    /// b.set_bit(1, true);
+    ///
+    /// let and = a.and(&b).unwrap();
+    ///
+    /// assert_eq!(Some(false), and.get_bit(0));
+    /// assert_eq!(Some(true), and.get_bit(1));
+    /// assert_eq!(None, and.get_bit(2));
+    /// ```
+    pub fn and(&self, other: &Bits) -> Result<Bits, BitsError> {
+        self.combine(other, |a, b| match (a, b) {
+            (Some(false), _) | (_, Some(false)) => Some(false),
+            (Some(true), Some(true)) => Some(true),
+            _ => None,
+        })
+    }
+
+    /// Bitwise OR of two bits representations, a bit is decided if its value follows from the
+    /// decided bits alone: "1" if any of them is "1", "0" if both of them are "0".
+    ///
+    /// Returns `Err(BitsError)` if the representations have different sizes.
     ///
     /// # Examples
     /// ```
@@ -80,18 +826,30 @@ impl Bits {
     ///
     /// use xor_distance_exercise::bits::Bits;
     ///
-    /// let bit_rep = Bits::new::<u64>();
-    /// let bit = bit_rep.get_bit(4);
-    /// ```
+    /// let mut a = Bits::new::<u8>();
+    /// a.set_bit(0, true);
+    /// a.set_bit(1, false);
     ///
-    /// # Panics
+    /// let or = a.or(&Bits::new::<u8>()).unwrap();
     ///
-    /// Panics if `index` is out of range.
-    pub fn get_bit(&self, index: usize) -> Option<bool> {
-        self.bits[index]
+    /// assert_eq!(Some(true), or.get_bit(0));
+    /// assert_eq!(None, or.get_bit(1));
+    /// ```
+    pub fn or(&self, other: &Bits) -> Result<Bits, BitsError> {
+        self.combine(other, |a, b| match (a, b) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        })
     }
 
-    /// Set new bit value for the index.
+    /// Bitwise XOR of two bits representations, a bit is decided if it is decided in both of
+    /// them.
+    ///
+    /// XOR of two position restrictions restricts their XOR distance, e.g. the bits shared by all
+    /// positions of both.
+    ///
+    /// Returns `Err(BitsError)` if the representations have different sizes.
     ///
     /// # Examples
     /// ```
@@ -99,21 +857,27 @@ impl Bits {
     ///
     /// use xor_distance_exercise::bits::Bits;
     ///
-    /// let mut bit_rep = Bits::new::<u64>();
-    /// bit_rep.set_bit(4, true);
-    /// bit_rep.set_bit(5, false);
-    /// ```
+    /// let mut a = Bits::new::<u8>();
+    /// a.set_bit(0, true);
+    /// a.set_bit(1, true);
     ///
-    /// # Panics
+    /// let mut b = Bits::new::<u8>();
+    /// b.set_bit(0, true);
     ///
-    /// Panics if `index` is out of range.
-    pub fn set_bit(&mut self, index: usize, val: bool) {
-        self.bits[index] = Some(val);
+    /// let xor = a.xor(&b).unwrap();
+    ///
+    /// assert_eq!(Some(false), xor.get_bit(0));
+    /// assert_eq!(None, xor.get_bit(1));
+    /// ```
+    pub fn xor(&self, other: &Bits) -> Result<Bits, BitsError> {
+        self.combine(other, |a, b| match (a, b) {
+            (Some(a), Some(b)) => Some(a != b),
+            _ => None,
+        })
     }
 
-    /// Set new bit value complying with constrains, already decided bit value can not be changed.
-    ///
-    /// Returns `Ok(())` in case constrains were not violated, `Err(&str)` otherwise.
+    /// Bitwise NOT of the bits representation, decided bits are flipped and undecided ones stay
+    /// undecided.
     ///
     /// # Examples
     /// ```
@@ -121,31 +885,30 @@ impl Bits {
     ///
     /// use xor_distance_exercise::bits::Bits;
     ///
-    /// let mut bit_rep = Bits::new::<u64>();
-    /// bit_rep.set_bit_within_constrains(4, true);
-    /// ```
+    /// let mut bit_rep = Bits::new::<u8>();
+    /// bit_rep.set_bit(0, true);
     ///
-    /// # Panics
+    /// let negated = bit_rep.negate();
     ///
-    /// Panics if `index` is out of range.
-    pub fn set_bit_within_constrains(
-        &mut self,
-        index: usize,
-        val: bool,
-    ) -> Result<(), &'static str> {
-        match self.bits[index] {
-            // Existing bit with a different value is a breach of constrains.
-            Some(bit) if bit != val => return Err("Already decided bit value can not be changed!"),
-            // The value is already present, nothing to do here.
-            Some(_) => {}
-            // No value set as yet so just assign it.
-            None => self.bits[index] = Some(val),
+    /// assert_eq!(Some(false), negated.get_bit(0));
+    /// assert_eq!(None, negated.get_bit(1));
+    /// ```
+    pub fn negate(&self) -> Bits {
+        Bits {
+            bits: self
+                .bits
+                .iter()
+                .copied()
+                .map(|bit| bit.map(|bit| !bit))
+                .collect(),
+            size: self.size,
         }
-
-        Ok(())
     }
 
-    /// Is bit decided already?
+    /// Return whether every bit decided in the `other` representation is decided identically in
+    /// this one, so this representation adds no freedom on top of the `other` one.
+    ///
+    /// Representations of different sizes are never refinements of each other.
     ///
     /// # Examples
     /// ```
@@ -153,20 +916,31 @@ impl Bits {
     ///
     /// use xor_distance_exercise::bits::Bits;
     ///
-    /// let bit_rep = Bits::new::<u64>();
-    /// bit_rep.is_bit_decided(4);
-    /// ```
+    /// let mut coarse = Bits::new::<u8>();
+    /// coarse.set_bit(0, true);
     ///
-    /// # Panics
+    /// let mut fine = Bits::new::<u8>();
+    /// fine.set_bit(0, true);
+    /// fine.set_bit(1, false);
     ///
-    /// Panics if `index` is out of range.
-    pub fn is_bit_decided(&self, index: usize) -> bool {
-        let bit = self.bits[index];
-
-        bit.is_some()
+    /// assert!(fine.is_refinement_of(&coarse));
+    /// assert!(!coarse.is_refinement_of(&fine));
+    /// ```
+    pub fn is_refinement_of(&self, other: &Bits) -> bool {
+        self.size == other.size
+            && self
+                .bits
+                .iter()
+                .copied()
+                .zip(other.bits.iter().copied())
+                .all(|(bit, other_bit)| other_bit.is_none() || bit == other_bit)
     }
 
-    /// Form and return a number based on bits representation, pad/fill undecided bits by zeros.
+    /// Normalize into the canonical representation, holding exactly one entry per bit of the
+    /// declared size and no spare capacity.
+    ///
+    /// Every constructor and operation keeps the representation canonical, so normalizing only
+    /// releases the spare capacity.
     ///
     /// # Examples
     /// ```
@@ -174,46 +948,179 @@ impl Bits {
     ///
     /// use xor_distance_exercise::bits::Bits;
     ///
-    /// let bit_rep = Bits::new::<u64>();
-    /// let number = bit_rep.form_zero_padded_number::<u64>().unwrap();
+    /// let mut bit_rep = Bits::new::<u64>();
+    /// bit_rep.normalize();
+    ///
+    /// assert!(bit_rep.is_canonical());
     /// ```
-    pub fn form_zero_padded_number<T: PrimInt>(&self) -> Result<T, &str> {
+    pub fn normalize(&mut self) {
+        self.bits.shrink_to_fit();
+    }
+
+    /// Return whether the representation is canonical, holding exactly one entry per bit of the
+    /// declared size, which every representation is.
+    pub fn is_canonical(&self) -> bool {
+        self.bits.len() == self.size
+    }
+
+    /// Return the index, `Err(BitsError)` if it is not within the size of the representation.
+    fn checked_index(&self, index: usize) -> Result<usize, BitsError> {
+        self.checked_bit_index(index)
+            .ok_or(BitsError::IndexOutOfRange {
+                index,
+                size: self.size,
+            })
+    }
+
+    /// Combine bits of the same index of both bits representations.
+    fn combine<F>(&self, other: &Bits, f: F) -> Result<Bits, BitsError>
+    where
+        F: Fn(Option<bool>, Option<bool>) -> Option<bool>,
+    {
+        self.check_same_size(other)?;
+
+        Ok(Bits {
+            bits: self
+                .bits
+                .iter()
+                .copied()
+                .zip(other.bits.iter().copied())
+                .map(|(a, b)| f(a, b))
+                .collect(),
+            size: self.size,
+        })
+    }
+
+    /// Check the number type has enough bits for the representation.
+    fn check_bit_width<T: PrimInt>(&self) -> Result<(), BitsError> {
         if Self::bit_size::<T>() < self.size {
-            return Err("Requested number type has not enough bits to represent the whole number!");
+            return Err(BitsError::InsufficientBitWidth {
+                required: self.size,
+                available: Self::bit_size::<T>(),
+            });
         }
 
-        // Initialize the number with "0".
-        let mut number: T = T::zero();
+        Ok(())
+    }
 
-        // Construct the number by incorporating in all bits.
-        for (index, _) in self.bits.iter().enumerate() {
-            self.incorporate_bit(index, &mut number);
+    /// Check both bits representations have the same size.
+    fn check_same_size(&self, other: &Bits) -> Result<(), BitsError> {
+        if self.size != other.size {
+            return Err(BitsError::SizeMismatch {
+                left: self.size,
+                right: other.size,
+            });
         }
 
-        Ok(number)
+        Ok(())
     }
 
-    /// Incorporate bit into the provided number.
-    ///
-    /// # Panics
+    /// Incorporate bit into the provided number, an undecided bit as the `padding` value.
     ///
-    /// Panics if `index` is out of range.
-    fn incorporate_bit<T: PrimInt + BitOps>(&self, index: usize, number: &mut T) {
-        let bit = self.bits[index];
+    /// Returns `Err(BitsError)` if the bit is set and the number has not enough bits for it.
+    fn incorporate_bit<T: PrimInt + BitOps>(
+        &self,
+        index: usize,
+        padding: bool,
+        number: &mut T,
+    ) -> Result<(), BitsError> {
+        let bit = self.bits[index].unwrap_or(padding);
 
         // Set only `1` bit as `0` bits are present by default.
-        match bit {
-            Some(bit) if bit => {
-                number.set_bit(index);
-            }
-            _ => {}
+        if bit {
+            number.checked_set_bit(index)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Binary digits from the most significant bit, undecided bits are rendered as `?`.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::bits::Bits;
+///
+/// let mut bit_rep = Bits::new::<u8>();
+/// bit_rep.set_bit(7, true);
+/// bit_rep.set_bit(0, false);
+///
+/// assert_eq!("1??????0", bit_rep.to_string());
+/// ```
+impl fmt::Display for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for bit in self.bits.iter().rev() {
+            f.write_char(match bit {
+                None => '?',
+                Some(false) => '0',
+                Some(true) => '1',
+            })?;
         }
+
+        Ok(())
+    }
+}
+
+/// Bits from the most significant one as by `Display`, e.g. `Bits(1??????0)`.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::bits::Bits;
+///
+/// let mut bit_rep = Bits::new::<u8>();
+/// bit_rep.set_bit(1, true);
+///
+/// assert_eq!("Bits(??????1?)", format!("{:?}", bit_rep));
+/// ```
+impl fmt::Debug for Bits {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Bits")
+            .field(&format_args!("{}", self))
+            .finish()
+    }
+}
+
+/// Bits representations are equal if they have the same size and the same decided bits.
+impl PartialEq for Bits {
+    fn eq(&self, other: &Bits) -> bool {
+        self.size == other.size && self.bits.iter().copied().eq(other.bits.iter().copied())
+    }
+}
+
+impl Eq for Bits {}
+
+/// Hash of the decided form, stable across platforms and crate versions.
+///
+/// The hashed data is the size as a little-endian `u64`, followed by one byte per bit from the
+/// least significant one: `0` for undecided, `1` for `0` and `2` for `1` bit. Together with a
+/// stable `Hasher` it gives hashes that can be persisted.
+impl Hash for Bits {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.size as u64).hash_stable(state);
+
+        let bits: Vec<u8> = self
+            .bits
+            .iter()
+            .copied()
+            .map(|bit| match bit {
+                None => 0,
+                Some(false) => 1,
+                Some(true) => 2,
+            })
+            .collect();
+
+        state.write(&bits);
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::bits::Bits;
+    use crate::error::BitsError;
 
     #[test]
     fn bit_size() {
@@ -301,7 +1208,11 @@ mod tests {
         assert_eq!(Ok(()), bit_rep.set_bit_within_constrains(index, true));
         // Setting the bit value with a different value then in previous step violates constrains.
         assert_eq!(
-            Err("Already decided bit value can not be changed!"),
+            Err(BitsError::ConstraintViolation {
+                index,
+                existing: true,
+                requested: false
+            }),
             bit_rep.set_bit_within_constrains(index, false)
         );
     }
@@ -349,11 +1260,50 @@ mod tests {
 
         // Error is expected.
         assert_eq!(
-            Err("Requested number type has not enough bits to represent the whole number!"),
+            Err(BitsError::InsufficientBitWidth {
+                required: 64,
+                available: 32
+            }),
             bit_rep.form_zero_padded_number::<u32>()
         );
     }
 
+    #[test]
+    fn number_round_trip() {
+        for number in [0u64, 1, 70, 1 << 63, u64::MAX].iter() {
+            let bit_rep = Bits::from_number(*number);
+
+            assert!(bit_rep.is_canonical());
+            assert_eq!(Ok(*number), bit_rep.to_number());
+            assert_eq!(Ok(*number), bit_rep.form_zero_padded_number());
+            assert_eq!(Ok(*number), bit_rep.form_one_padded_number());
+        }
+
+        // Sign bits of signed numbers survive the round trip too.
+        for number in [i8::MIN, -1, 0, i8::MAX].iter() {
+            assert_eq!(Ok(*number), Bits::from_number(*number).to_number());
+        }
+
+        // Padding brackets all numbers the representation stands for.
+        let mut bit_rep = Bits::new::<u8>();
+        bit_rep.set_bit(2, true);
+        bit_rep.set_bit(7, false);
+
+        assert_eq!(Ok(0b0000_0100u8), bit_rep.form_zero_padded_number());
+        assert_eq!(Ok(0b0111_1111u8), bit_rep.form_one_padded_number());
+        assert_eq!(
+            Err(BitsError::Undecided { index: 0 }),
+            bit_rep.to_number::<u8>()
+        );
+        assert_eq!(
+            Err(BitsError::InsufficientBitWidth {
+                required: 64,
+                available: 32
+            }),
+            Bits::from_number(1u64).to_number::<u32>()
+        );
+    }
+
     #[test]
     fn incorporate_bit() {
         let mut bit_rep = Bits::new::<u64>();
@@ -363,18 +1313,384 @@ mod tests {
         let mut number: u64 = 0;
 
         // Incorporating `1` bit with index 1 adds value 2.
-        bit_rep.incorporate_bit(1, &mut number);
+        bit_rep.incorporate_bit(1, false, &mut number).unwrap();
 
         assert_eq!(2, number);
 
         // Incorporating `1` bit with index 2 adds value 4.
-        bit_rep.incorporate_bit(2, &mut number);
+        bit_rep.incorporate_bit(2, false, &mut number).unwrap();
 
         assert_eq!(6, number);
 
-        // Incorporating `0` bit does not change number's value.
-        bit_rep.incorporate_bit(3, &mut number);
+        // Incorporating zero padded bit does not change number's value.
+        bit_rep.incorporate_bit(3, false, &mut number).unwrap();
 
         assert_eq!(6, number);
+
+        // Incorporating one padded bit with index 3 adds value 8.
+        bit_rep.incorporate_bit(3, true, &mut number).unwrap();
+
+        assert_eq!(14, number);
+    }
+
+    #[test]
+    fn intersect() {
+        let mut a = Bits::new::<u8>();
+        a.set_bit(0, true);
+        a.set_bit(2, false);
+
+        let mut b = Bits::new::<u8>();
+        b.set_bit(0, true);
+        b.set_bit(5, true);
+
+        let intersection = a.intersect(&b).unwrap();
+
+        assert_eq!(Some(true), intersection.get_bit(0));
+        assert_eq!(Some(false), intersection.get_bit(2));
+        assert_eq!(Some(true), intersection.get_bit(5));
+        assert_eq!(None, intersection.get_bit(7));
+
+        // Contradicting representations can not be intersected.
+        b.set_bit(2, true);
+        assert_eq!(
+            Err(BitsError::ConstraintViolation {
+                index: 2,
+                existing: false,
+                requested: true
+            }),
+            a.intersect(&b).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn union_envelope() {
+        let mut a = Bits::new::<u8>();
+        a.set_bit(0, true);
+        a.set_bit(1, false);
+        a.set_bit(2, true);
+
+        let mut b = Bits::new::<u8>();
+        b.set_bit(0, true);
+        b.set_bit(1, true);
+
+        let envelope = a.union_envelope(&b).unwrap();
+
+        assert_eq!(Some(true), envelope.get_bit(0));
+        assert_eq!(None, envelope.get_bit(1));
+        assert_eq!(None, envelope.get_bit(2));
+    }
+
+    #[test]
+    fn logical_operations() {
+        // All nine combinations of undecided, "0" and "1" bits.
+        let values = [None, Some(false), Some(true)];
+        let mut a = Bits::new::<u16>();
+        let mut b = Bits::new::<u16>();
+
+        for (index, (x, y)) in values
+            .iter()
+            .flat_map(|x| values.iter().map(move |y| (*x, *y)))
+            .enumerate()
+        {
+            a.bits[index] = x;
+            b.bits[index] = y;
+        }
+
+        let bits = |bits: Bits| (0..9).map(|index| bits.get_bit(index)).collect::<Vec<_>>();
+        let (n, f, t) = (None, Some(false), Some(true));
+
+        assert_eq!(vec![n, f, n, f, f, f, n, f, t], bits(a.and(&b).unwrap()));
+        assert_eq!(vec![n, n, t, n, f, t, t, t, t], bits(a.or(&b).unwrap()));
+        assert_eq!(vec![n, n, n, n, f, t, n, t, f], bits(a.xor(&b).unwrap()));
+        assert_eq!(vec![n, n, n, t, t, t, f, f, f], bits(a.negate()));
+        assert!(a.negate().negate() == a);
+    }
+
+    #[test]
+    fn different_sizes() {
+        let a = Bits::new::<u8>();
+        let b = Bits::new::<u16>();
+
+        let mismatch = BitsError::SizeMismatch { left: 8, right: 16 };
+
+        assert_eq!(Err(mismatch), a.intersect(&b).map(|_| ()));
+        assert_eq!(Err(mismatch), a.union_envelope(&b).map(|_| ()));
+        assert_eq!(Err(mismatch), a.and(&b).map(|_| ()));
+        assert_eq!(Err(mismatch), a.or(&b).map(|_| ()));
+        assert_eq!(Err(mismatch), a.xor(&b).map(|_| ()));
+    }
+
+    #[test]
+    fn normalize() {
+        let mut bit_rep = Bits::new::<u8>();
+        bit_rep.set_bit(7, true);
+        assert!(bit_rep.is_canonical());
+
+        // Normalizing keeps the bits.
+        let normalized = {
+            let mut normalized = bit_rep.clone();
+            normalized.normalize();
+            normalized
+        };
+        assert!(normalized.is_canonical());
+        assert_eq!(bit_rep, normalized);
+        assert_eq!(Some(true), normalized.get_bit(7));
+    }
+
+    #[test]
+    fn eq_and_hash() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+
+        fn hash(bits: &Bits) -> u64 {
+            let mut hasher = DefaultHasher::new();
+            bits.hash(&mut hasher);
+            hasher.finish()
+        }
+
+        let mut a = Bits::new::<u8>();
+        a.set_bit(1, true);
+        a.set_bit(2, false);
+
+        // The same decided bits, set in a different order.
+        let mut b = Bits::new::<u8>();
+        b.set_bit(2, false);
+        b.set_bit(1, true);
+
+        assert!(a == b);
+        assert_eq!(hash(&a), hash(&b));
+
+        b.normalize();
+        assert!(a == b);
+
+        b.set_bit(3, false);
+        assert!(a != b);
+
+        // Same decided bits of a different size.
+        let mut c = Bits::new::<u16>();
+        c.set_bit(1, true);
+        c.set_bit(2, false);
+        assert!(a != c);
+    }
+
+    #[test]
+    fn hash_data_is_stable() {
+        use std::hash::{Hash, Hasher};
+
+        #[derive(Default)]
+        struct Bytes(Vec<u8>);
+
+        impl Hasher for Bytes {
+            fn finish(&self) -> u64 {
+                0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                self.0.extend_from_slice(bytes);
+            }
+        }
+
+        let mut bits = Bits::new::<u8>();
+        bits.set_bit(0, true);
+        bits.set_bit(7, false);
+
+        let mut hasher = Bytes::default();
+        bits.hash(&mut hasher);
+
+        assert_eq!(
+            vec![8, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 1],
+            hasher.0
+        );
+    }
+
+    #[test]
+    fn set_many() {
+        let mut bits = Bits::new::<u8>();
+        bits.set_bit(1, false);
+
+        bits.set_many(&[(0, true), (1, false), (2, true)]).unwrap();
+        assert_eq!(Some(true), bits.get_bit(0));
+        assert_eq!(Some(false), bits.get_bit(1));
+        assert_eq!(Some(true), bits.get_bit(2));
+
+        // Conflict within the batch rolls back bits decided by the batch only.
+        assert_eq!(
+            Err(BitsError::ConstraintViolation {
+                index: 3,
+                existing: true,
+                requested: false
+            }),
+            bits.set_many(&[(3, true), (0, true), (4, false), (3, false)])
+        );
+        assert_eq!(None, bits.get_bit(3));
+        assert_eq!(None, bits.get_bit(4));
+        assert_eq!(Some(true), bits.get_bit(0));
+
+        assert_eq!(Ok(()), bits.set_many(&[]));
+    }
+
+    #[test]
+    fn set_many_out_of_range() {
+        let mut bits = Bits::new::<u8>();
+
+        assert_eq!(
+            Err(BitsError::IndexOutOfRange { index: 8, size: 8 }),
+            bits.set_many(&[(0, true), (8, true)])
+        );
+        // Nothing is applied, not even the assignments before the invalid one.
+        assert_eq!(Bits::new::<u8>(), bits);
+    }
+
+    #[test]
+    fn is_refinement_of() {
+        let undecided = Bits::new::<u8>();
+
+        let mut a = Bits::new::<u8>();
+        a.set_bit(2, true);
+
+        let mut b = Bits::new::<u8>();
+        b.set_bit(2, false);
+        b.set_bit(3, true);
+
+        // Every representation refines the undecided one and itself.
+        assert!(a.is_refinement_of(&undecided));
+        assert!(a.is_refinement_of(&a));
+        assert!(!undecided.is_refinement_of(&a));
+
+        // Contradicting bit.
+        assert!(!b.is_refinement_of(&a));
+
+        assert!(!Bits::new::<u16>().is_refinement_of(&undecided));
+    }
+
+    #[test]
+    fn iterators() {
+        let mut bit_rep = Bits::new::<u16>();
+        bit_rep.set_bit(0, false);
+        bit_rep.set_bit(9, true);
+        bit_rep.set_bit(15, true);
+
+        assert_eq!(16, bit_rep.iter().count());
+        assert!(bit_rep
+            .iter()
+            .all(|(index, bit)| bit == bit_rep.get_bit(index)));
+        assert_eq!(
+            vec![0, 9, 15],
+            bit_rep.decided_indices().collect::<Vec<usize>>()
+        );
+        assert_eq!(
+            bit_rep.undecided_count(),
+            bit_rep.undecided_indices().count()
+        );
+        assert!(bit_rep
+            .undecided_indices()
+            .all(|index| !bit_rep.is_bit_decided(index)));
+    }
+
+    #[test]
+    fn checked_bits() {
+        let mut bit_rep = Bits::new::<u8>();
+        let out_of_range = BitsError::IndexOutOfRange { index: 8, size: 8 };
+
+        assert_eq!(Some(0), bit_rep.checked_bit_index(0));
+        assert_eq!(None, bit_rep.checked_bit_index(8));
+        assert_eq!(None, bit_rep.checked_bit_index(usize::MAX));
+
+        assert_eq!(Ok(()), bit_rep.try_set_bit(3, true));
+        assert_eq!(Ok(Some(true)), bit_rep.try_get_bit(3));
+        assert_eq!(Ok(None), bit_rep.try_get_bit(7));
+
+        // Out of range index leaves the representation unchanged.
+        assert_eq!(Err(out_of_range), bit_rep.try_set_bit(8, true));
+        assert_eq!(Err(out_of_range), bit_rep.try_get_bit(8));
+        assert_eq!(8, bit_rep.bits.len());
+        assert_eq!(vec![3], bit_rep.decided_indices().collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn wide_bits() {
+        let mut id = [0u8; 20];
+        id[0] = 0xA0;
+        id[19] = 0x01;
+
+        let bit_rep = Bits::from_key(id);
+
+        assert_eq!(160, bit_rep.size());
+        assert_eq!(0, bit_rep.undecided_count());
+        assert_eq!(Ok(id), bit_rep.form_zero_padded_key::<[u8; 20]>());
+        assert_eq!(Ok(id.to_vec()), bit_rep.form_zero_padded_bytes());
+
+        // Keys of the same width as the bytes round trip.
+        let wide = (1u128 << 100, 7u128);
+        assert_eq!(Ok(wide), Bits::from_key(wide).form_zero_padded_key());
+        assert!(Bits::from_number(70u64) == Bits::from_key(70u64));
+
+        // Undecided bits are padded by zeros.
+        let mut bit_rep = Bits::with_size(512);
+        bit_rep.set_bit(256, true);
+
+        let bytes = bit_rep.form_zero_padded_bytes().unwrap();
+        assert_eq!(64, bytes.len());
+        assert_eq!(
+            vec![31],
+            (0..64).filter(|i| bytes[*i] != 0).collect::<Vec<_>>()
+        );
+        assert_eq!(1, bytes[31]);
+
+        assert_eq!(Ok(Vec::new()), Bits::with_size(0).form_zero_padded_bytes());
+    }
+
+    #[test]
+    fn bytes_and_hex() {
+        let bytes = [0xDE, 0xAD, 0xBE, 0xEF, 0x01];
+        let bit_rep = Bits::from_bytes(&bytes);
+
+        assert_eq!(40, bit_rep.size());
+        assert_eq!(Ok(bytes.to_vec()), bit_rep.to_bytes_be());
+        assert_eq!(
+            Ok(vec![0x01, 0xEF, 0xBE, 0xAD, 0xDE]),
+            bit_rep.to_bytes_le()
+        );
+        assert_eq!("deadbeef01", bit_rep.to_hex());
+        assert!(Bits::from_bytes(&[]).to_bytes_be().unwrap().is_empty());
+
+        // Undecided bits are not exported as bytes.
+        let mut bit_rep = Bits::with_size(12);
+        bit_rep.set_bit(0, true);
+
+        assert_eq!(
+            Err(BitsError::Undecided { index: 1 }),
+            bit_rep.to_bytes_le()
+        );
+        assert_eq!("???", bit_rep.to_hex());
+        assert_eq!("???????????1", bit_rep.to_string());
+
+        // Digits of sizes not divisible by four have leading zeros.
+        let bit_rep = Bits::from_number(0b1_0110u8);
+        assert_eq!("16", bit_rep.to_hex());
+        assert_eq!("00010110", bit_rep.to_string());
+
+        let mut bit_rep = Bits::with_size(6);
+        bit_rep
+            .set_many(&[(4, true), (5, true), (3, false)])
+            .unwrap();
+        assert_eq!("3?", bit_rep.to_hex());
+        assert_eq!("110???", format!("{}", bit_rep));
+    }
+
+    #[test]
+    fn debug_and_clone() {
+        let mut bit_rep = Bits::new::<u8>();
+        bit_rep.set_bit(7, true);
+        bit_rep.set_bit(0, false);
+
+        let mut clone = bit_rep.clone();
+        assert_eq!(bit_rep, clone);
+        assert_eq!("Bits(1??????0)", format!("{:?}", clone));
+
+        clone.set_bit(1, true);
+        assert_ne!(bit_rep, clone);
+        assert_eq!("Bits(1??????0)", format!("{:?}", bit_rep));
+        assert_eq!("Bits(1?????10)", format!("{:?}", clone));
     }
 }
@@ -0,0 +1,254 @@
+//! Kademlia-style k-buckets of points relative to a local ID.
+//!
+//! The bucket `i` holds points whose highest bit differing from the local ID is the bit `i`, so
+//! the bucket covers XOR distances from `2^i` to `2^(i+1) - 1` of the local ID. Buckets of a
+//! limited size keep the points inserted first, as Kademlia prefers long-lived nodes.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::buckets::KBuckets;
+//!
+//! let mut buckets: KBuckets<u8> = KBuckets::with_bucket_size(0b1000_0000, 2);
+//!
+//! for point in [0b1000_0001, 0b1000_0010, 0b1000_0011, 0b0000_0001].iter() {
+//!     buckets.insert(*point);
+//! }
+//!
+//! assert_eq!(Some(1), buckets.bucket_of(0b1000_0011));
+//! assert_eq!(&[0b1000_0010, 0b1000_0011], buckets.bucket_contents(1));
+//! assert_eq!(vec![0b0000_0001, 0b1000_0001], buckets.closest_via_buckets(0, 2));
+//! ```
+
+use crate::bitops::BitOps;
+use crate::bits::Bits;
+use num_traits::{PrimInt, Unsigned};
+
+/// Points partitioned into k-buckets by the highest bit differing from the local ID.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KBuckets<T> {
+    local_id: T,
+    bucket_size: Option<usize>,
+    buckets: Vec<Vec<T>>,
+}
+
+impl<T: PrimInt + Unsigned> KBuckets<T> {
+    /// Create new buckets of an unlimited size relative to the local ID.
+    pub fn new(local_id: T) -> Self {
+        Self::with_optional_bucket_size(local_id, None)
+    }
+
+    /// Create new buckets holding up to `bucket_size` points each, relative to the local ID.
+    pub fn with_bucket_size(local_id: T, bucket_size: usize) -> Self {
+        Self::with_optional_bucket_size(local_id, Some(bucket_size))
+    }
+
+    fn with_optional_bucket_size(local_id: T, bucket_size: Option<usize>) -> Self {
+        KBuckets {
+            local_id,
+            bucket_size,
+            buckets: vec![Vec::new(); Bits::bit_size::<T>()],
+        }
+    }
+
+    /// Return the local ID the buckets are relative to.
+    pub fn local_id(&self) -> T {
+        self.local_id
+    }
+
+    /// Return number of points in all buckets.
+    pub fn len(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.len()).sum()
+    }
+
+    /// Return whether all buckets are empty.
+    pub fn is_empty(&self) -> bool {
+        self.buckets.iter().all(|bucket| bucket.is_empty())
+    }
+
+    /// Return index of the bucket of the point, the highest bit in which it differs from the local
+    /// ID, `None` for the local ID itself.
+    pub fn bucket_of(&self, point: T) -> Option<usize> {
+        let distance = point ^ self.local_id;
+
+        if distance == T::zero() {
+            return None;
+        }
+
+        Some(self.buckets.len() - 1 - distance.leading_zeros() as usize)
+    }
+
+    /// Return points of the bucket, in their insertion order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than the bit size of the points.
+    pub fn bucket_contents(&self, index: usize) -> &[T] {
+        &self.buckets[index]
+    }
+
+    /// Insert the point into its bucket.
+    ///
+    /// Returns `false` if the point is the local ID, is already present or its bucket is full.
+    pub fn insert(&mut self, point: T) -> bool {
+        let index = match self.bucket_of(point) {
+            Some(index) => index,
+            None => return false,
+        };
+        let bucket = &mut self.buckets[index];
+
+        if bucket.contains(&point) || self.bucket_size.is_some_and(|size| bucket.len() >= size) {
+            return false;
+        }
+
+        bucket.push(point);
+
+        true
+    }
+
+    /// Remove the point from its bucket.
+    ///
+    /// Returns `true` if the point was present, `false` otherwise.
+    pub fn remove(&mut self, point: T) -> bool {
+        let bucket = match self.bucket_of(point) {
+            Some(index) => &mut self.buckets[index],
+            None => return false,
+        };
+
+        match bucket.iter().position(|p| *p == point) {
+            Some(position) => {
+                bucket.remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return up to requested count of closest points to the provided `x`, ordered from the closest
+    /// to the n-th closest, where `n` is the count.
+    ///
+    /// Points of a bucket share all bits from the bucket index up, so every bucket covers a range
+    /// of XOR distances from `x` disjoint with the other buckets. Buckets are visited in the order
+    /// of their ranges and only the visited ones are sorted.
+    pub fn closest_via_buckets(&self, x: T, count: usize) -> Vec<T> {
+        let mut order: Vec<(T, usize)> = (0..self.buckets.len())
+            .filter(|index| !self.buckets[*index].is_empty())
+            .map(|index| (self.min_distance(index, x), index))
+            .collect();
+        order.sort_unstable();
+
+        let mut closest = Vec::with_capacity(count.min(self.len()));
+
+        for (_, index) in order {
+            if closest.len() >= count {
+                break;
+            }
+
+            let mut bucket = self.buckets[index].clone();
+            bucket.sort_unstable_by_key(|point| *point ^ x);
+            bucket.truncate(count - closest.len());
+
+            closest.extend(bucket);
+        }
+
+        closest
+    }
+
+    /// Return the smallest possible XOR distance of the bucket points from `x`, the distance of
+    /// the shared bits only.
+    fn min_distance(&self, index: usize, x: T) -> T {
+        // Points of the bucket have the local ID bits above the index and the opposite bit on it.
+        let prefix = self.local_id ^ (T::one() << index);
+
+        (prefix ^ x) & !T::mask_low(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KBuckets;
+    use crate::xor_distance::XorDistance;
+
+    const POINTS: [u64; 19] = [
+        0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    ];
+
+    #[test]
+    fn partition() {
+        let mut buckets: KBuckets<u64> = KBuckets::new(20);
+
+        for point in POINTS.iter() {
+            buckets.insert(*point);
+        }
+
+        // The local ID is not stored, duplicates are rejected.
+        assert_eq!(POINTS.len() - 1, buckets.len());
+        assert!(!buckets.insert(20));
+        assert!(!buckets.insert(21));
+
+        assert_eq!(None, buckets.bucket_of(20));
+        assert_eq!(Some(0), buckets.bucket_of(21));
+        assert_eq!(&[21], buckets.bucket_contents(0));
+        assert_eq!(&[22], buckets.bucket_contents(1));
+        assert_eq!(&[18, 19], buckets.bucket_contents(2));
+        assert_eq!(
+            &[406, 407, 408, 409, 410, 444, 445],
+            buckets.bucket_contents(8)
+        );
+
+        // Bucket `i` covers distances from `2^i` to `2^(i+1) - 1`.
+        for point in POINTS.iter() {
+            if let Some(bucket) = buckets.bucket_of(*point) {
+                assert_eq!(1, (point ^ 20) >> bucket);
+            }
+        }
+
+        assert!(buckets.remove(21));
+        assert!(!buckets.remove(21));
+        assert!(!buckets.remove(20));
+        assert!(buckets.bucket_contents(0).is_empty());
+    }
+
+    #[test]
+    fn bucket_size() {
+        let mut buckets: KBuckets<u16> = KBuckets::with_bucket_size(0, 2);
+
+        assert!(buckets.insert(256));
+        assert!(buckets.insert(257));
+        assert!(!buckets.insert(258));
+        assert!(buckets.insert(1));
+
+        // Removal frees a place in the bucket.
+        assert!(buckets.remove(256));
+        assert!(buckets.insert(258));
+        assert_eq!(&[257, 258], buckets.bucket_contents(8));
+    }
+
+    #[test]
+    fn same_results_as_xor_distance() {
+        let local_id = 409;
+        let mut buckets: KBuckets<u64> = KBuckets::new(local_id);
+        let xor_distance = XorDistance::new(
+            POINTS
+                .iter()
+                .copied()
+                .filter(|point| *point != local_id)
+                .collect(),
+        );
+
+        for point in POINTS.iter() {
+            buckets.insert(*point);
+        }
+
+        for x in [0, 10, 200, 300, 409, 445, u64::MAX].iter() {
+            for count in [0, 1, 5, 18, 30].iter() {
+                assert_eq!(
+                    xor_distance.closest(*x, *count),
+                    buckets.closest_via_buckets(*x, *count)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Differential testing of the closest points algorithms.
+//!
+//! The same randomized query stream is run through the sort, heap, radix and trie backends and
+//! their results compared, including the order of duplicated points, so new backends ship with a
+//! correctness net users can run against their own point sets.
+//!
+//! Available with the `rand` feature only.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate rand;
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::conformance::check_backends;
+//!
+//! let points: Vec<u32> = (0..500).map(|i| i * 7919).collect();
+//!
+//! assert_eq!(Ok(100), check_backends(&points, 100, &mut rand::thread_rng()));
+//! ```
+
+use crate::xor_distance::{Algorithm, IndexKind, XorDistance};
+use num_traits::{PrimInt, Unsigned};
+use rand::distributions::{Distribution, Standard};
+use rand::Rng;
+
+/// Backends compared with the sort one.
+const BACKENDS: [Algorithm; 3] = [Algorithm::Heap, Algorithm::Radix, Algorithm::Index];
+
+/// Query whose result differs from the result of the sort backend.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch<T> {
+    /// The queried position.
+    pub x: T,
+    /// The requested count of points.
+    pub count: usize,
+    /// The backend returning the different result.
+    pub algorithm: Algorithm,
+    /// Result of the sort backend.
+    pub expected: Vec<T>,
+    /// Result of the mismatching backend.
+    pub actual: Vec<T>,
+}
+
+/// Run `queries` random closest points queries through all backends over the points.
+///
+/// Positions are uniformly random or equal to one of the points, counts range from zero to more
+/// than the number of points. Returns `Ok(queries)` if all backends agree, the first mismatch
+/// otherwise.
+pub fn check_backends<T, R>(points: &[T], queries: usize, rng: &mut R) -> Result<usize, Mismatch<T>>
+where
+    T: PrimInt + Unsigned,
+    Standard: Distribution<T>,
+    R: Rng + ?Sized,
+{
+    let reference = backend(points, Algorithm::Sort);
+    let backends: Vec<XorDistance<T>> = BACKENDS
+        .iter()
+        .map(|algorithm| backend(points, *algorithm))
+        .collect();
+
+    for _ in 0..queries {
+        let x = match points.len() {
+            len if len > 0 && rng.gen::<bool>() => points[rng.gen_range(0, len)],
+            _ => rng.gen(),
+        };
+        let count = rng.gen_range(0, points.len() + 2);
+
+        let expected = reference.closest(x, count);
+
+        for xor_distance in backends.iter() {
+            let actual = xor_distance.closest(x, count);
+
+            if actual != expected {
+                return Err(Mismatch {
+                    x,
+                    count,
+                    algorithm: xor_distance.algorithm(),
+                    expected,
+                    actual,
+                });
+            }
+        }
+    }
+
+    Ok(queries)
+}
+
+fn backend<T: PrimInt + Unsigned>(points: &[T], algorithm: Algorithm) -> XorDistance<T> {
+    let index = match algorithm {
+        Algorithm::Index => IndexKind::Trie,
+        _ => IndexKind::Sort,
+    };
+
+    XorDistance::builder(points.to_vec())
+        .index(index)
+        .algorithm(algorithm)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check_backends;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn backends_agree() {
+        let mut rng = StdRng::seed_from_u64(11);
+
+        // Small keys, so many points are duplicated.
+        let points: Vec<u8> = (0..600).map(|_| rng.gen()).collect();
+        assert_eq!(Ok(200), check_backends(&points, 200, &mut rng));
+
+        let points: Vec<u64> = (0..5000).map(|_| rng.gen()).collect();
+        assert_eq!(Ok(20), check_backends(&points, 20, &mut rng));
+
+        assert_eq!(Ok(5), check_backends::<u16, _>(&[], 5, &mut rng));
+    }
+}
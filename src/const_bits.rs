@@ -0,0 +1,192 @@
+//! Compile time bit helpers for unsigned integer types.
+//!
+//! Generic `BitOps` methods can't be `const fn`, so the mask-generation, prefix-length and
+//! Morton-interleave helpers are provided per type, usable to compute region prefixes and
+//! well-known keys at compile time. The masks equal `BitOps::mask_low` and `BitOps::mask_high` of
+//! the type for every length.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::const_bits::{common_prefix_len_u64, interleave_u32, mask_high_u64};
+//!
+//! // Region prefix formed by the 16 most significant bits.
+//! const REGION_MASK: u64 = mask_high_u64(16);
+//! const REGION: u64 = 0xABCD_0000_0000_0000;
+//!
+//! // Well-known key formed from two coordinates.
+//! const DEPOT: u64 = interleave_u32(3, 5);
+//!
+//! assert_eq!(0xFFFF_0000_0000_0000, REGION_MASK);
+//! assert_eq!(0b10_0111, DEPOT);
+//! assert!(common_prefix_len_u64(REGION, REGION | DEPOT) >= 16);
+//! ```
+
+macro_rules! impl_mask_helpers {
+    ($($t:ident => $mask_low:ident, $mask_high:ident, $prefix:ident, $prefix_len:ident;)*) => {
+        $(
+            #[doc = concat!("Return `", stringify!($t), "` mask with the lowest `len` bits set.")]
+            ///
+            /// All bits are set if `len` is greater than or equal to the bit size of the type, the
+            /// same as by `BitOps::mask_low`.
+            pub const fn $mask_low(len: u32) -> $t {
+                if len >= <$t>::BITS {
+                    <$t>::MAX
+                } else {
+                    (1 << len) - 1
+                }
+            }
+
+            #[doc = concat!("Return `", stringify!($t), "` mask with the highest `len` bits set.")]
+            ///
+            /// All bits are set if `len` is greater than or equal to the bit size of the type, the
+            /// same as by `BitOps::mask_high`.
+            pub const fn $mask_high(len: u32) -> $t {
+                !$mask_low(<$t>::BITS.saturating_sub(len))
+            }
+
+            /// Return the `len` most significant bits of the key, the rest is zeroed.
+            pub const fn $prefix(key: $t, len: u32) -> $t {
+                key & $mask_high(len)
+            }
+
+            /// Return the length of the common prefix of two keys, counted in the most
+            /// significant bits.
+            pub const fn $prefix_len(a: $t, b: $t) -> u32 {
+                (a ^ b).leading_zeros()
+            }
+        )*
+    };
+}
+
+impl_mask_helpers! {
+    u8 => mask_low_u8, mask_high_u8, prefix_u8, common_prefix_len_u8;
+    u16 => mask_low_u16, mask_high_u16, prefix_u16, common_prefix_len_u16;
+    u32 => mask_low_u32, mask_high_u32, prefix_u32, common_prefix_len_u32;
+    u64 => mask_low_u64, mask_high_u64, prefix_u64, common_prefix_len_u64;
+    u128 => mask_low_u128, mask_high_u128, prefix_u128, common_prefix_len_u128;
+    usize => mask_low_usize, mask_high_usize, prefix_usize, common_prefix_len_usize;
+}
+
+macro_rules! impl_morton_helpers {
+    ($($half:ident, $full:ident => $interleave:ident, $deinterleave:ident;)*) => {
+        $(
+            /// Interleave bits of two coordinates into a Morton (Z-order) key, bits of `x` take
+            /// the even and bits of `y` the odd bit indexes.
+            pub const fn $interleave(x: $half, y: $half) -> $full {
+                let mut key: $full = 0;
+                let mut index = 0;
+
+                while index < <$half>::BITS {
+                    key |= ((x as $full >> index) & 1) << (2 * index);
+                    key |= ((y as $full >> index) & 1) << (2 * index + 1);
+                    index += 1;
+                }
+
+                key
+            }
+
+            /// Split a Morton (Z-order) key back into its `(x, y)` coordinates.
+            pub const fn $deinterleave(key: $full) -> ($half, $half) {
+                let mut x: $half = 0;
+                let mut y: $half = 0;
+                let mut index = 0;
+
+                while index < <$half>::BITS {
+                    x |= (((key >> (2 * index)) & 1) as $half) << index;
+                    y |= (((key >> (2 * index + 1)) & 1) as $half) << index;
+                    index += 1;
+                }
+
+                (x, y)
+            }
+        )*
+    };
+}
+
+impl_morton_helpers! {
+    u8, u16 => interleave_u8, deinterleave_u16;
+    u16, u32 => interleave_u16, deinterleave_u32;
+    u32, u64 => interleave_u32, deinterleave_u64;
+    u64, u128 => interleave_u64, deinterleave_u128;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitops::BitOps;
+
+    #[test]
+    fn masks() {
+        assert_eq!(0, mask_low_u8(0));
+        assert_eq!(0b0000_0111, mask_low_u8(3));
+        assert_eq!(u8::MAX, mask_low_u8(8));
+        assert_eq!(u8::MAX, mask_low_u8(9));
+
+        assert_eq!(0, mask_high_u8(0));
+        assert_eq!(0b1110_0000, mask_high_u8(3));
+        assert_eq!(u8::MAX, mask_high_u8(8));
+        assert_eq!(u128::MAX, mask_high_u128(200));
+        assert_eq!(1 << 127, mask_high_u128(1));
+    }
+
+    #[test]
+    fn masks_equal_bitops() {
+        macro_rules! assert_masks_equal {
+            ($($t:ident => $mask_low:ident, $mask_high:ident;)*) => {
+                $(
+                    for len in 0..=<$t>::BITS + 1 {
+                        assert_eq!(<$t>::mask_low(len as usize), $mask_low(len));
+                        assert_eq!(<$t>::mask_high(len as usize), $mask_high(len));
+                    }
+                )*
+            };
+        }
+
+        assert_masks_equal! {
+            u8 => mask_low_u8, mask_high_u8;
+            u16 => mask_low_u16, mask_high_u16;
+            u32 => mask_low_u32, mask_high_u32;
+            u64 => mask_low_u64, mask_high_u64;
+            u128 => mask_low_u128, mask_high_u128;
+            usize => mask_low_usize, mask_high_usize;
+        }
+    }
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(0b1010_0000, prefix_u8(0b1010_1111, 4));
+        assert_eq!(0, prefix_u64(u64::MAX, 0));
+
+        assert_eq!(8, common_prefix_len_u8(3, 3));
+        assert_eq!(4, common_prefix_len_u8(0b1010_0000, 0b1010_1000));
+        assert_eq!(0, common_prefix_len_u32(0, u32::MAX));
+    }
+
+    #[test]
+    fn morton_round_trip() {
+        assert_eq!(0b11, interleave_u8(1, 1));
+        assert_eq!(0b10, interleave_u8(0, 1));
+        assert_eq!(u16::MAX, interleave_u8(u8::MAX, u8::MAX));
+
+        for &(x, y) in [(0, 0), (1, 2), (12345, 54321), (u32::MAX, 7)].iter() {
+            assert_eq!((x, y), deinterleave_u64(interleave_u32(x, y)));
+        }
+
+        assert_eq!(
+            (u64::MAX, 1),
+            deinterleave_u128(interleave_u64(u64::MAX, 1))
+        );
+    }
+
+    #[test]
+    fn const_evaluation() {
+        const KEY: u32 = interleave_u16(0xFF, 0);
+        const MASK: u64 = mask_low_u64(12);
+
+        assert_eq!(0x5555, KEY);
+        assert_eq!(0xFFF, MASK);
+    }
+}
@@ -1,7 +1,18 @@
 //! Food delivery system.
 
-use crate::xor_distance::XorDistance;
+use crate::error::DeliveryError;
+use crate::hooks::{Assignment, DeliveryHook, FarmChange, Hooks};
+#[cfg(feature = "metrics")]
+use crate::metrics::Metrics;
+use crate::solver::ConstraintSet;
+use crate::tiers::ServiceTiers;
+use crate::xor_distance::{Points, XorDistance};
 use num_traits::{PrimInt, Unsigned};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::iter::FromIterator;
+#[cfg(feature = "metrics")]
+use std::time::Instant;
 
 /// Food delivery system of local food from from local farms.
 ///
@@ -22,20 +33,759 @@ use num_traits::{PrimInt, Unsigned};
 /// let closest_farms = delivery_system.closest_farms(position, count);
 /// let position_guess = delivery_system.reverse_closest_farms(&closest_farms).unwrap();
 /// ```
-pub struct FoodDeliverySystem<T: PrimInt + Unsigned> {
+///
+/// Farms can carry data of the type `D`, e.g. names or capacities, with every farm position
+/// mapped to the data of the farm.
+///
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+///
+/// let delivery_system: FoodDeliverySystem<u64, &str> =
+///     FoodDeliverySystem::with_farms(vec![(0, "Oak Farm"), (8, "Green Acres"), (12, "Hillside")]);
+///
+/// assert_eq!(
+///     vec![(8, &"Green Acres"), (12, &"Hillside")],
+///     delivery_system.closest_farms_with_data(10, 2)
+/// );
+/// ```
+pub struct FoodDeliverySystem<T: PrimInt + Unsigned, D = ()> {
     xor_distance: XorDistance<T>,
+    farms: BTreeMap<T, D>,
+    stock: BTreeMap<T, u64>,
+    hooks: Hooks<T>,
+    tiers: Option<ServiceTiers<T>>,
+    #[cfg(feature = "metrics")]
+    metrics: Metrics<T>,
+}
+
+/// Distribution of XOR distances from customers to the farm they are assigned to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DistanceProfile<T> {
+    /// The profiled farm.
+    pub farm: T,
+    /// Distances of the assigned customers, sorted in ascending order.
+    pub distances: Vec<T>,
+}
+
+impl<T: PrimInt + Unsigned> DistanceProfile<T> {
+    /// Return number of assigned customers.
+    pub fn len(&self) -> usize {
+        self.distances.len()
+    }
+
+    /// Return whether no customers are assigned.
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    /// Return the distance below or at which the `q` fraction of assigned customers is, `None`
+    /// if no customers are assigned.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is out of `[0, 1]`.
+    pub fn quantile(&self, q: f64) -> Option<T> {
+        assert!((0.0..=1.0).contains(&q), "Quantile is out of range!");
+
+        if self.distances.is_empty() {
+            return None;
+        }
+
+        // Nearest rank, the rank is at least one.
+        let rank = (q * self.distances.len() as f64).ceil().max(1.0) as usize;
+
+        Some(self.distances[rank - 1])
+    }
+
+    /// Return number of assigned customers per distance bucket. Bucket `0` counts zero distances
+    /// and bucket `i` distances from `[2^(i-1), 2^i)`, there is a bucket per bit of the type.
+    pub fn histogram(&self) -> Vec<usize> {
+        let bit_size = T::zero().count_zeros() as usize;
+        let mut histogram = vec![0; bit_size + 1];
+
+        for distance in self.distances.iter() {
+            histogram[bit_size - distance.leading_zeros() as usize] += 1;
+        }
+
+        histogram
+    }
+}
+
+/// Assignments of one round of orders with a per-farm cap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CappedRound<T> {
+    /// Assignment of every customer in the provided order, `None` if all farms were full.
+    pub assignments: Vec<Option<Assignment<T>>>,
+    /// Number of customers assigned to other than their closest farm.
+    pub spilled: usize,
+    /// Number of customers not assigned at all.
+    pub unassigned: usize,
+    /// Sum of distances the spilled customers have on top of the distance to their closest farm,
+    /// the aggregate cost of the cap.
+    pub extra_distance: f64,
+}
+
+/// Summary of the farms and the number of registered hooks.
+impl<T: PrimInt + Unsigned + fmt::Debug + 'static, D> fmt::Debug for FoodDeliverySystem<T, D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("FoodDeliverySystem")
+            .field("farms", &self.xor_distance)
+            .field("hooks", &self.hooks.len())
+            .finish()
+    }
+}
+
+/// Systems are equal if they have equal farms with equal data, stock and service tiers.
+///
+/// Hooks and metrics are not compared. Systems are not `Clone`, as hooks are boxed closures,
+/// share the system by `Arc` instead.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+///
+/// let mut delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8]);
+/// let mut other: FoodDeliverySystem<u64> = FoodDeliverySystem::default();
+/// other.add_farm(0);
+/// other.add_farm(8);
+///
+/// assert_eq!(delivery_system, other);
+///
+/// delivery_system.set_farm_stock(8, 3);
+/// assert_ne!(delivery_system, other);
+/// ```
+impl<T: PrimInt + Unsigned, D: PartialEq> PartialEq for FoodDeliverySystem<T, D> {
+    fn eq(&self, other: &FoodDeliverySystem<T, D>) -> bool {
+        self.xor_distance == other.xor_distance
+            && self.farms == other.farms
+            && self.stock == other.stock
+            && self.tiers == other.tiers
+    }
+}
+
+/// System of no farms, farms can be added later on.
+impl<T: PrimInt + Unsigned + 'static, D> Default for FoodDeliverySystem<T, D> {
+    fn default() -> Self {
+        Self::with_farms(Vec::new())
+    }
+}
+
+/// System of the collected farms, as by `new`.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+///
+/// let mut delivery_system: FoodDeliverySystem<u64> = vec![0, 8].into_iter().collect();
+/// delivery_system.extend(vec![12]);
+///
+/// let mut farms = Vec::new();
+///
+/// for farm in &delivery_system {
+///     farms.push(*farm);
+/// }
+///
+/// assert_eq!(vec![0, 8, 12], farms);
+/// ```
+impl<T: PrimInt + Unsigned + 'static> FromIterator<T> for FoodDeliverySystem<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(farms: I) -> Self {
+        Self::new(farms.into_iter().collect())
+    }
+}
+
+/// Farms are added one by one as by `add_farm`, notifying the hooks of every farm.
+impl<T: PrimInt + Unsigned + 'static> Extend<T> for FoodDeliverySystem<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, farms: I) {
+        for farm in farms {
+            self.add_farm(farm);
+        }
+    }
+}
+
+/// Farms in the order they were added, as by `farms`.
+impl<'a, T: PrimInt + Unsigned + 'static, D> IntoIterator for &'a FoodDeliverySystem<T, D> {
+    type Item = &'a T;
+    type IntoIter = Points<'a, T>;
+
+    fn into_iter(self) -> Points<'a, T> {
+        self.farms()
+    }
+}
+
+impl<T: PrimInt + Unsigned + 'static> FoodDeliverySystem<T> {
+    pub fn new(points: Vec<T>) -> Self {
+        Self::with_farms(points.into_iter().map(|point| (point, ())).collect())
+    }
+
+    /// Add a new farm.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let mut delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 1]);
+    /// delivery_system.add_farm(10);
+    ///
+    /// assert_eq!(vec![10], delivery_system.closest_farms(10, 1));
+    /// ```
+    pub fn add_farm(&mut self, farm: T) {
+        self.add_farm_with_data(farm, ());
+    }
 }
 
-impl<T: PrimInt + Unsigned> FoodDeliverySystem<T> {
-    pub fn new(points: Vec<T>) -> Self {
-        let xor_distance = XorDistance::new(points);
+impl<T: PrimInt + Unsigned + 'static, D> FoodDeliverySystem<T, D> {
+    /// Create a new Food Delivery System of the farms with their data. Farms at the same position
+    /// share the data provided last.
+    pub fn with_farms(farms: Vec<(T, D)>) -> Self {
+        let xor_distance = XorDistance::new(farms.iter().map(|(farm, _)| *farm).collect());
+
+        Self {
+            xor_distance,
+            farms: farms.into_iter().collect(),
+            stock: BTreeMap::new(),
+            hooks: Hooks::new(),
+            tiers: None,
+            #[cfg(feature = "metrics")]
+            metrics: Metrics::new(),
+        }
+    }
+
+    /// Return the underlying XOR distance structure of farms.
+    pub fn xor_distance(&self) -> &XorDistance<T> {
+        &self.xor_distance
+    }
+
+    /// Configure service tiers returned with every assignment.
+    pub fn set_service_tiers(&mut self, tiers: ServiceTiers<T>) {
+        self.tiers = Some(tiers);
+    }
+
+    /// Return the configured service tiers.
+    pub fn service_tiers(&self) -> Option<&ServiceTiers<T>> {
+        self.tiers.as_ref()
+    }
+
+    /// Register a hook receiving all the food delivery system events.
+    pub fn add_hook<H: DeliveryHook<T> + 'static>(&mut self, hook: H) {
+        self.hooks.add(hook);
+    }
+
+    /// Register a closure invoked after an order was assigned to a farm.
+    pub fn on_assignment<F>(&mut self, hook: F)
+    where
+        F: Fn(&Assignment<T>) + Send + Sync + 'static,
+    {
+        self.hooks.on_assignment(hook);
+    }
+
+    /// Register a closure invoked after an order assignment was cancelled.
+    pub fn on_cancellation<F>(&mut self, hook: F)
+    where
+        F: Fn(&Assignment<T>) + Send + Sync + 'static,
+    {
+        self.hooks.on_cancellation(hook);
+    }
+
+    /// Register a closure invoked after a farm was added or removed.
+    pub fn on_farm_change<F>(&mut self, hook: F)
+    where
+        F: Fn(&FarmChange<T>) + Send + Sync + 'static,
+    {
+        self.hooks.on_farm_change(hook);
+    }
+
+    /// Add a new farm with its data, replacing data of a farm at the same position.
+    pub fn add_farm_with_data(&mut self, farm: T, data: D) {
+        self.xor_distance.add_point(farm);
+        self.farms.insert(farm, data);
+        self.hooks.notify_farm_change(&FarmChange::Added(farm));
+    }
+
+    /// Return all farms in the order they were added, farms at the same position included.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let mut delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![8, 0]);
+    /// delivery_system.add_farm(12);
+    ///
+    /// assert_eq!(vec![8, 0, 12], delivery_system.farms().copied().collect::<Vec<_>>());
+    /// assert!(delivery_system.contains_farm(12));
+    /// ```
+    pub fn farms(&self) -> Points<'_, T> {
+        self.xor_distance.points()
+    }
+
+    /// Return whether there is a farm at the position.
+    pub fn contains_farm(&self, farm: T) -> bool {
+        self.xor_distance.contains(farm)
+    }
+
+    /// Return data of the farm, `None` if there is no farm at the position.
+    pub fn farm_data(&self, farm: T) -> Option<&D> {
+        self.farms.get(&farm)
+    }
+
+    /// Return data of the farm mutably, `None` if there is no farm at the position.
+    pub fn farm_data_mut(&mut self, farm: T) -> Option<&mut D> {
+        self.farms.get_mut(&farm)
+    }
+
+    /// Remove the farm.
+    ///
+    /// Returns `true` if the farm was present, `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let mut delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 1]);
+    ///
+    /// assert!(delivery_system.remove_farm(1));
+    /// assert_eq!(vec![0], delivery_system.closest_farms(1, 2));
+    /// ```
+    pub fn remove_farm(&mut self, farm: T) -> bool {
+        if !self.xor_distance.remove_point(farm) {
+            return false;
+        }
+
+        // Data and stock are shared by all farms at the position, so they go with the last one.
+        if !self.contains_farm(farm) {
+            self.farms.remove(&farm);
+            self.stock.remove(&farm);
+        }
+
+        self.hooks.notify_farm_change(&FarmChange::Removed(farm));
+
+        true
+    }
+
+    /// Assign customer's order to the closest farm.
+    ///
+    /// Returns `None` if there are no farms.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// let assignment = delivery_system.assign_order(10).unwrap();
+    /// assert_eq!(8, assignment.farm);
+    /// ```
+    pub fn assign_order(&self, position: T) -> Option<Assignment<T>> {
+        let farm = self.xor_distance.nth_closest(position, 0)?;
+        let assignment = self.assignment(position, farm);
+
+        self.record_assignment(&assignment);
+
+        Some(assignment)
+    }
+
+    /// Assign one round of customers' orders, in the provided order, with at most `cap` orders per
+    /// farm. Customers overflowing their closest farm spill over to the next-closest farm with a
+    /// free capacity.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// let round = delivery_system.assign_round_capped(&[9, 10, 11], 2);
+    ///
+    /// // Customer 11 spilled over from the farm 8 to the farm 12.
+    /// assert_eq!(1, round.spilled);
+    /// assert_eq!(Some(12), round.assignments[2].map(|assignment| assignment.farm));
+    /// assert_eq!(4.0, round.extra_distance);
+    /// ```
+    pub fn assign_round_capped(&self, positions: &[T], cap: usize) -> CappedRound<T> {
+        let mut loads: BTreeMap<T, usize> = BTreeMap::new();
+        let mut round = CappedRound {
+            assignments: Vec::with_capacity(positions.len()),
+            spilled: 0,
+            unassigned: 0,
+            extra_distance: 0.0,
+        };
+
+        for position in positions.iter() {
+            let closest = self.xor_distance.nth_closest(*position, 0);
+            let farm = (0..self.xor_distance.len())
+                .filter_map(|n| self.xor_distance.nth_closest(*position, n))
+                .find(|farm| loads.get(farm).copied().unwrap_or(0) < cap);
+
+            let assignment = match (closest, farm) {
+                (Some(closest), Some(farm)) => {
+                    *loads.entry(farm).or_insert(0) += 1;
+
+                    if farm != closest {
+                        round.spilled += 1;
+                        // Any primitive integer is representable in `f64`, even if rounded.
+                        round.extra_distance += (farm ^ *position).to_f64().unwrap()
+                            - (closest ^ *position).to_f64().unwrap();
+                    }
+
+                    let assignment = self.assignment(*position, farm);
+                    self.record_assignment(&assignment);
+
+                    Some(assignment)
+                }
+                _ => {
+                    round.unassigned += 1;
+                    None
+                }
+            };
+
+            round.assignments.push(assignment);
+        }
+
+        round
+    }
+
+    /// Set the remaining stock of the farm, the quantity it can still deliver. Farms at the same
+    /// position share the stock.
+    ///
+    /// Returns `false` if there is no farm at the position, the stock is not set then.
+    pub fn set_farm_stock(&mut self, farm: T, stock: u64) -> bool {
+        if !self.contains_farm(farm) {
+            return false;
+        }
+
+        self.stock.insert(farm, stock);
+
+        true
+    }
+
+    /// Return the remaining stock of the farm, `None` if the farm has no stock set and so is not
+    /// limited.
+    pub fn farm_stock(&self, farm: T) -> Option<u64> {
+        self.stock.get(&farm).copied()
+    }
+
+    /// Assign customers' orders of `(position, quantity)`, in the provided order, each to the
+    /// closest farm with enough remaining stock, which is decremented by the quantity. Farms with
+    /// no stock set are not limited.
+    ///
+    /// Farms are visited from the closest one by `nth_closest` and the walk stops at the first
+    /// farm with enough stock, so only the farms closer than the assigned one are ranked.
+    ///
+    /// Returns assignment of every order, `None` if no farm has enough stock for it.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let mut delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    /// delivery_system.set_farm_stock(0, 0);
+    /// delivery_system.set_farm_stock(8, 5);
+    /// delivery_system.set_farm_stock(12, 10);
+    ///
+    /// let assignments = delivery_system.assign_orders(&[(10, 3), (9, 3), (1, 8), (2, 1)]);
+    /// let farms: Vec<Option<u64>> = assignments
+    ///     .iter()
+    ///     .map(|assignment| assignment.map(|assignment| assignment.farm))
+    ///     .collect();
+    ///
+    /// // The second order spilled over from the farm 8 to the farm 12, the third one fits nowhere.
+    /// assert_eq!(vec![Some(8), Some(12), None, Some(8)], farms);
+    /// assert_eq!(Some(1), delivery_system.farm_stock(8));
+    /// assert_eq!(Some(7), delivery_system.farm_stock(12));
+    /// ```
+    pub fn assign_orders(&mut self, orders: &[(T, u64)]) -> Vec<Option<Assignment<T>>> {
+        let mut assignments = Vec::with_capacity(orders.len());
+
+        for (position, quantity) in orders.iter() {
+            let farm = (0..)
+                .map_while(|n| self.xor_distance.nth_closest(*position, n))
+                .find(|farm| self.stock.get(farm).is_none_or(|stock| stock >= quantity));
+
+            let assignment = farm.map(|farm| {
+                if let Some(stock) = self.stock.get_mut(&farm) {
+                    *stock -= quantity;
+                }
+
+                let assignment = self.assignment(*position, farm);
+                self.record_assignment(&assignment);
+
+                assignment
+            });
+
+            assignments.push(assignment);
+        }
+
+        assignments
+    }
+
+    /// Return assignment to the farm, with the service tier of the distance.
+    fn assignment(&self, position: T, farm: T) -> Assignment<T> {
+        Assignment {
+            position,
+            farm,
+            tier: self.tiers.map(|tiers| tiers.tier(position ^ farm)),
+        }
+    }
+
+    /// Record the assignment in metrics and notify hooks about it.
+    fn record_assignment(&self, assignment: &Assignment<T>) {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_order(assignment.farm);
+
+        self.hooks.notify_assignment(assignment);
+    }
+
+    /// Cancel the order assignment.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// let assignment = delivery_system.assign_order(10).unwrap();
+    /// delivery_system.cancel_order(&assignment);
+    /// ```
+    pub fn cancel_order(&self, assignment: &Assignment<T>) {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_cancellation(assignment.farm);
+
+        self.hooks.notify_cancellation(assignment);
+    }
+
+    /// Render the system metrics in Prometheus text exposition format, available with the
+    /// `metrics` feature only.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// let closest_farms = delivery_system.closest_farms(10, 2);
+    /// delivery_system.reverse_closest_farms(&closest_farms);
+    ///
+    /// assert!(delivery_system
+    ///     .render_metrics()
+    ///     .contains("xor_delivery_reverse_duration_seconds_count 1\n"));
+    /// ```
+    #[cfg(feature = "metrics")]
+    pub fn render_metrics(&self) -> String {
+        self.metrics.render()
+    }
+
+    /// Return specified count of closest farms to the provided `position`.
+    ///
+    /// The closest farms are ordered from the closest to the n-th closest, where `n` is the count.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![
+    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    /// ]);
+    ///
+    /// let position = 10;
+    /// let count = 10;
+    ///
+    /// let closest_farms = delivery_system.closest_farms(position, count);
+    /// ```
+    pub fn closest_farms(&self, position: T, count: usize) -> Vec<T> {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_query();
+
+        self.xor_distance.closest(position, count)
+    }
+
+    /// Return closest farms of every `(position, count)` query, each as returned by
+    /// `closest_farms`, see `closest_farms_batch_parallel` for the queries split over threads.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// assert_eq!(
+    ///     vec![vec![8, 12], vec![0]],
+    ///     delivery_system.closest_farms_batch(&[(10, 2), (1, 1)])
+    /// );
+    /// ```
+    pub fn closest_farms_batch(&self, queries: &[(T, usize)]) -> Vec<Vec<T>> {
+        #[cfg(feature = "metrics")]
+        for _ in queries.iter() {
+            self.metrics.record_query();
+        }
+
+        queries
+            .iter()
+            .map(|(position, count)| self.xor_distance.closest(*position, *count))
+            .collect()
+    }
+
+    /// Return specified count of closest farms to the provided `position` with their XOR distances,
+    /// as `(farm, distance)` pairs ordered as by `closest_farms`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// assert_eq!(
+    ///     vec![(8, 2), (12, 6)],
+    ///     delivery_system.closest_farms_with_distances(10, 2)
+    /// );
+    /// ```
+    pub fn closest_farms_with_distances(&self, position: T, count: usize) -> Vec<(T, T)> {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_query();
+
+        self.xor_distance.closest_with_distances(position, count)
+    }
+
+    /// Return specified count of closest farms to the provided `position` with their data, ordered
+    /// as by `closest_farms`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// // Farms with their daily capacity.
+    /// let delivery_system: FoodDeliverySystem<u64, usize> =
+    ///     FoodDeliverySystem::with_farms(vec![(0, 50), (8, 20), (12, 35)]);
+    ///
+    /// let capacity: usize = delivery_system
+    ///     .closest_farms_with_data(10, 2)
+    ///     .iter()
+    ///     .map(|(_, capacity)| **capacity)
+    ///     .sum();
+    ///
+    /// assert_eq!(55, capacity);
+    /// ```
+    pub fn closest_farms_with_data(&self, position: T, count: usize) -> Vec<(T, &D)> {
+        self.closest_farms(position, count)
+            .into_iter()
+            // Every farm position has its data.
+            .map(|farm| (farm, &self.farms[&farm]))
+            .collect()
+    }
+
+    /// Return specified count of farms ordered by the key returned by `key_fn`, called with each
+    /// farm and its XOR distance to the provided `position`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// // Farm 8 is overloaded, so its distance is penalized.
+    /// let farms = delivery_system.closest_farms_by_key(10, 2, |farm, distance| {
+    ///     if farm == 8 {
+    ///         distance + 6
+    ///     } else {
+    ///         distance
+    ///     }
+    /// });
+    ///
+    /// assert_eq!(vec![8, 12], delivery_system.closest_farms(10, 2));
+    /// assert_eq!(vec![12, 8], farms);
+    /// ```
+    pub fn closest_farms_by_key<K, F>(&self, position: T, count: usize, key_fn: F) -> Vec<T>
+    where
+        K: Ord,
+        F: FnMut(T, T) -> K,
+    {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_query();
+
+        self.xor_distance.closest_by_key(position, count, key_fn)
+    }
+
+    /// Return up to specified count of closest farms to the provided `position` within the XOR
+    /// distance `max_distance`, as farms beyond some distance are never acceptable for delivery.
+    ///
+    /// The closest farms are ordered from the closest to the n-th closest, where `n` is the count.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![
+    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    /// ]);
+    ///
+    /// let closest_farms = delivery_system.closest_farms_bounded(10, 10, 8);
+    ///
+    /// assert_eq!(vec![8, 12, 2], closest_farms);
+    /// ```
+    pub fn closest_farms_bounded(&self, position: T, count: usize, max_distance: T) -> Vec<T> {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_query();
+
+        self.xor_distance
+            .closest_bounded(position, count, max_distance)
+    }
 
-        Self { xor_distance }
+    /// Return up to specified count of closest farms to the provided `position` within the XOR
+    /// distance `max_dist`, see `closest_farms_bounded`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> =
+    ///     FoodDeliverySystem::new(vec![0, 1, 2, 4, 6, 8, 12, 444, 445]);
+    ///
+    /// assert_eq!(vec![8, 12], delivery_system.closest_farms_within(10, 2, 255));
+    /// assert_eq!(Vec::<u64>::new(), delivery_system.closest_farms_within(300, 2, 100));
+    /// ```
+    pub fn closest_farms_within(&self, position: T, count: usize, max_dist: T) -> Vec<T> {
+        self.closest_farms_bounded(position, count, max_dist)
     }
 
-    /// Return specified count of closest farms to the provided `position`.
+    /// Return up to specified count of closest farms to the provided `position`, skipping the
+    /// excluded farms, e.g. temporarily closed ones, without rebuilding the system.
     ///
-    /// The closest farms are ordered from the closest to the n-th closest, where `n` is the count.
+    /// Farms are excluded before the count is applied, so `count` farms are returned as long as
+    /// there are enough other farms.
     ///
     /// # Examples
     /// ```
@@ -43,17 +793,63 @@ impl<T: PrimInt + Unsigned> FoodDeliverySystem<T> {
     ///
     /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
     ///
-    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![
-    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
-    /// ]);
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 1, 8, 12]);
     ///
-    /// let position = 10;
-    /// let count = 10;
+    /// assert_eq!(vec![8, 12], delivery_system.closest_farms(10, 2));
+    /// assert_eq!(vec![12, 0], delivery_system.closest_farms_excluding(10, 2, &[8]));
+    /// ```
+    pub fn closest_farms_excluding(&self, position: T, count: usize, excluded: &[T]) -> Vec<T> {
+        #[cfg(feature = "metrics")]
+        self.metrics.record_query();
+
+        // Duplicates of the excluded farms may need further farms, fetched by doubling the count.
+        let mut fetched = count.saturating_add(excluded.len());
+
+        loop {
+            let closest = self.xor_distance.closest(position, fetched);
+            let exhausted = closest.len() < fetched;
+
+            let mut farms: Vec<T> = closest
+                .into_iter()
+                .filter(|farm| !excluded.contains(farm))
+                .collect();
+
+            if farms.len() >= count || exhausted {
+                farms.truncate(count);
+
+                return farms;
+            }
+
+            fetched = fetched.saturating_mul(2);
+        }
+    }
+
+    /// Return distribution of XOR distances from the sampled customers' positions assigned to the
+    /// farm, customers assigned to other farms are skipped.
     ///
-    /// let closest_farms = delivery_system.closest_farms(position, count);
+    /// # Examples
     /// ```
-    pub fn closest_farms(&self, position: T, count: usize) -> Vec<T> {
-        self.xor_distance.closest(position, count)
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// let sample: Vec<u64> = (0..16).collect();
+    /// let profile = delivery_system.farm_distance_profile(8, &sample);
+    ///
+    /// assert_eq!(vec![0, 1, 2, 3], profile.distances);
+    /// assert_eq!(Some(1), profile.quantile(0.5));
+    /// ```
+    pub fn farm_distance_profile(&self, farm: T, sample: &[T]) -> DistanceProfile<T> {
+        let mut distances: Vec<T> = sample
+            .iter()
+            .filter(|position| self.xor_distance.nth_closest(**position, 0) == Some(farm))
+            .map(|position| *position ^ farm)
+            .collect();
+        distances.sort();
+
+        DistanceProfile { farm, distances }
     }
 
     /// Return a `Some(position)` such that `self.closest(position)` equals closest_farms and return
@@ -80,7 +876,203 @@ impl<T: PrimInt + Unsigned> FoodDeliverySystem<T> {
     /// assert_eq!(closest_farms, delivery_system.closest_farms(position_guess, count));
     /// ```
     pub fn reverse_closest_farms(&self, closest_farms: &[T]) -> Option<T> {
-        self.xor_distance.reverse_closest(closest_farms)
+        #[cfg(feature = "metrics")]
+        let start = Instant::now();
+
+        let position = self.xor_distance.reverse_closest(closest_farms);
+
+        #[cfg(feature = "metrics")]
+        self.metrics.record_reverse(start.elapsed());
+
+        position
+    }
+
+    /// Return number of positions with the same closest farms as the `position`, the anonymity set
+    /// size of a customer whose closest farms are observed, saturated at `u128::MAX`.
+    ///
+    /// Every bit of the position left undecided by the closest farms doubles the positions, so a
+    /// response leaks `bit size - log2(anonymity)` bits of the customer's position.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// // The closest farms `[8, 12]` decide the bits 2 and 3 of the position.
+    /// assert_eq!(64, delivery_system.position_anonymity(10, 2));
+    /// // The closest farm `0` decides the bit 3 only.
+    /// assert_eq!(128, delivery_system.position_anonymity(3, 1));
+    /// ```
+    pub fn position_anonymity(&self, position: T, count: usize) -> u128 {
+        let closest_farms = self.xor_distance.closest(position, count);
+
+        self.xor_distance
+            .reverse_closest_detailed(&closest_farms)
+            // The position itself has the closest farms.
+            .expect("Closest farms of a position must be reversible!")
+            .solution_count()
+            .unwrap_or(u128::MAX)
+    }
+
+    /// Return a position different from the `position` with the identical closest farms, which a
+    /// privacy-conscious customer can send instead of the own position.
+    ///
+    /// All bits left undecided by the closest farms are set, so the returned position depends on
+    /// the closest farms only and reveals nothing more about the customer. If the `position`
+    /// itself has all of them set, the undecided bits are cleared instead. The `position` is
+    /// returned only if the closest farms decide all of its bits.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// // The closest farms `[8, 12]` decide the bits 2 and 3 only.
+    /// let obfuscated = delivery_system.obfuscate_position(10, 2);
+    ///
+    /// assert_eq!(0b1111_1011, obfuscated);
+    /// assert_eq!(delivery_system.closest_farms(10, 2), delivery_system.closest_farms(obfuscated, 2));
+    /// assert_eq!(0b0000_1000, delivery_system.obfuscate_position(obfuscated, 2));
+    /// ```
+    pub fn obfuscate_position(&self, position: T, count: usize) -> T {
+        let closest_farms = self.xor_distance.closest(position, count);
+
+        let (smallest, largest) = self
+            .xor_distance
+            .reverse_closest_range(&closest_farms)
+            // The position itself has the closest farms.
+            .expect("Closest farms of a position must be reversible!");
+
+        if largest != position {
+            largest
+        } else {
+            smallest
+        }
+    }
+
+    /// Return constraints on the customer's position implied by the observed assignment to the
+    /// farm under the capped fallback of `assign_round_capped`, with the farm `loads` before the
+    /// assignment.
+    ///
+    /// Fallback makes the plain reverse of the closest farms invalid: full farms might have been
+    /// closer, only the farm being closer than all other farms with a free capacity is implied.
+    ///
+    /// Returns `Err(DeliveryError::UnknownFarm)` if the farm is not a farm of the system and
+    /// `Err(DeliveryError::FarmFull)` if it was full.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use std::collections::BTreeMap;
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    ///
+    /// // The farm 8 was full, so the customer is only known to be closer to 12 than to 0.
+    /// let loads: BTreeMap<u8, usize> = [(8, 2)].iter().copied().collect();
+    /// let constraints = delivery_system.infer_capped_assignment(12, &loads, 2).unwrap();
+    ///
+    /// assert_eq!(Some(true), constraints.bits().get_bit(3));
+    /// assert_eq!(None, constraints.bits().get_bit(2));
+    /// ```
+    pub fn infer_capped_assignment(
+        &self,
+        farm: T,
+        loads: &BTreeMap<T, usize>,
+        cap: usize,
+    ) -> Result<ConstraintSet<T>, DeliveryError> {
+        let is_available = |farm: &T| loads.get(farm).copied().unwrap_or(0) < cap;
+
+        if !self.contains_farm(farm) {
+            return Err(DeliveryError::UnknownFarm);
+        }
+
+        if !is_available(&farm) {
+            return Err(DeliveryError::FarmFull);
+        }
+
+        let mut constraints = ConstraintSet::new();
+        let inequalities = self
+            .xor_distance
+            .points()
+            .filter(|other| **other != farm && is_available(other))
+            .map(|other| (farm, *other));
+
+        // The position equal to the farm satisfies all of them, so they never conflict.
+        let ingested = constraints.ingest(inequalities);
+        debug_assert!(ingested.is_ok());
+
+        Ok(constraints)
+    }
+
+    /// Return constraints on every customer's position implied by the observed assignments of one
+    /// round of `assign_round_capped` with the `cap`, in the order of the round, where `None`
+    /// stands for an unassigned customer.
+    ///
+    /// Loads are replayed from the observed assignments. Unassigned customers imply no
+    /// constraints, all farms were full.
+    ///
+    /// Returns `Err(DeliveryError)` of the first assignment not possible under the cap, see
+    /// `infer_capped_assignment`.
+    pub fn infer_capped_round(
+        &self,
+        assigned_farms: &[Option<T>],
+        cap: usize,
+    ) -> Result<Vec<ConstraintSet<T>>, DeliveryError> {
+        let mut loads: BTreeMap<T, usize> = BTreeMap::new();
+        let mut round = Vec::with_capacity(assigned_farms.len());
+
+        for farm in assigned_farms.iter() {
+            let constraints = match farm {
+                Some(farm) => {
+                    let constraints = self.infer_capped_assignment(*farm, &loads, cap)?;
+                    *loads.entry(*farm).or_insert(0) += 1;
+
+                    constraints
+                }
+                None => ConstraintSet::new(),
+            };
+
+            round.push(constraints);
+        }
+
+        Ok(round)
+    }
+}
+
+#[cfg(feature = "parallel")]
+impl<T: PrimInt + Unsigned + Send + Sync + 'static, D> FoodDeliverySystem<T, D> {
+    /// Return closest farms of every `(position, count)` query as by `closest_farms_batch`, with
+    /// the queries split over all available threads, available with the `parallel` feature only.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+    /// let queries: Vec<(u64, usize)> = (0..1000).map(|position| (position, 2)).collect();
+    ///
+    /// assert_eq!(
+    ///     delivery_system.closest_farms_batch(&queries),
+    ///     delivery_system.closest_farms_batch_parallel(&queries)
+    /// );
+    /// ```
+    pub fn closest_farms_batch_parallel(&self, queries: &[(T, usize)]) -> Vec<Vec<T>> {
+        #[cfg(feature = "metrics")]
+        for _ in queries.iter() {
+            self.metrics.record_query();
+        }
+
+        self.xor_distance.closest_batch_counts(queries)
     }
 }
 
@@ -93,9 +1085,13 @@ mod tests {
     //! random tests.
 
     use super::FoodDeliverySystem;
+    use crate::error::DeliveryError;
+    use crate::hooks::FarmChange;
     use rand::distributions::Standard;
     use rand::prelude::*;
     use rand::{self, Rng};
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
 
     #[test]
     fn closest_farms() {
@@ -176,4 +1172,425 @@ mod tests {
         }
     }
 
+    #[test]
+    fn hooks() {
+        let mut delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let assignment_events = events.clone();
+        delivery_system.on_assignment(move |assignment| {
+            assignment_events.lock().unwrap().push(format!(
+                "assigned {} to {}",
+                assignment.position, assignment.farm
+            ));
+        });
+        let cancellation_events = events.clone();
+        delivery_system.on_cancellation(move |assignment| {
+            cancellation_events
+                .lock()
+                .unwrap()
+                .push(format!("cancelled {}", assignment.position));
+        });
+        let farm_events = events.clone();
+        delivery_system.on_farm_change(move |change| {
+            let event = match change {
+                FarmChange::Added(farm) => format!("added {}", farm),
+                FarmChange::Removed(farm) => format!("removed {}", farm),
+            };
+            farm_events.lock().unwrap().push(event);
+        });
+
+        let assignment = delivery_system.assign_order(10).unwrap();
+        delivery_system.cancel_order(&assignment);
+        delivery_system.add_farm(10);
+        // Removing a missing farm is not a change.
+        assert!(!delivery_system.remove_farm(7));
+        assert!(delivery_system.remove_farm(8));
+        delivery_system.assign_order(9).unwrap();
+
+        assert_eq!(
+            vec![
+                "assigned 10 to 8",
+                "cancelled 10",
+                "added 10",
+                "removed 8",
+                "assigned 9 to 10",
+            ],
+            *events.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn farm_data() {
+        let mut delivery_system: FoodDeliverySystem<u8, String> =
+            FoodDeliverySystem::with_farms(vec![(0, "north".to_string()), (8, "east".to_string())]);
+
+        delivery_system.add_farm_with_data(12, "south".to_string());
+        delivery_system.add_farm_with_data(8, "east hub".to_string());
+        delivery_system.farm_data_mut(0).unwrap().push_str(" hub");
+
+        assert_eq!(
+            vec![(8, &"east hub".to_string()), (8, &"east hub".to_string())],
+            delivery_system.closest_farms_with_data(10, 2)
+        );
+        assert_eq!(Some(&"north hub".to_string()), delivery_system.farm_data(0));
+        assert_eq!(None, delivery_system.farm_data(1));
+
+        // Data stays until the last farm at the position is removed.
+        assert!(delivery_system.remove_farm(8));
+        assert_eq!(Some(&"east hub".to_string()), delivery_system.farm_data(8));
+        assert!(delivery_system.remove_farm(8));
+        assert_eq!(None, delivery_system.farm_data(8));
+        assert_eq!(
+            vec![(12, &"south".to_string()), (0, &"north hub".to_string())],
+            delivery_system.closest_farms_with_data(10, 3)
+        );
+    }
+
+    #[test]
+    fn position_anonymity() {
+        let delivery_system: FoodDeliverySystem<u8> =
+            FoodDeliverySystem::new(vec![3, 17, 18, 96, 200, 201, 201]);
+
+        for position in [0, 17, 100, 201, 255].iter() {
+            for count in 0..9 {
+                let closest_farms = delivery_system.closest_farms(*position, count);
+                let same = (0..=255)
+                    .filter(|other| delivery_system.closest_farms(*other, count) == closest_farms)
+                    .count();
+
+                assert_eq!(
+                    same as u128,
+                    delivery_system.position_anonymity(*position, count)
+                );
+            }
+        }
+
+        let delivery_system: FoodDeliverySystem<u128> = FoodDeliverySystem::new(vec![7]);
+        assert_eq!(u128::MAX, delivery_system.position_anonymity(0, 1));
+    }
+
+    #[test]
+    fn assign_order_without_farms() {
+        let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(Vec::new());
+
+        assert_eq!(None, delivery_system.assign_order(10));
+    }
+
+    #[test]
+    fn debug_summary() {
+        let mut delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0, 8]);
+        delivery_system.on_assignment(|_| {});
+
+        assert_eq!(
+            "FoodDeliverySystem { farms: XorDistance { points: 2, bit_size: 8, index: Trie, \
+             generation: 0, sample: [0, 8] }, hooks: 1 }",
+            format!("{:?}", delivery_system)
+        );
+    }
+
+    #[test]
+    fn farms() {
+        let mut delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![8, 0, 8]);
+
+        assert_eq!(
+            vec![8, 0, 8],
+            delivery_system.farms().copied().collect::<Vec<_>>()
+        );
+        assert!(delivery_system.contains_farm(8));
+        assert!(!delivery_system.contains_farm(12));
+
+        delivery_system.remove_farm(8);
+        assert!(delivery_system.contains_farm(8));
+        assert!(delivery_system.set_farm_stock(8, 1));
+
+        delivery_system.remove_farm(8);
+        assert!(!delivery_system.contains_farm(8));
+        assert!(!delivery_system.set_farm_stock(8, 1));
+        assert_eq!(
+            vec![0],
+            delivery_system.farms().copied().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn collect_extend_iterate() {
+        let mut delivery_system: FoodDeliverySystem<u8> = vec![8, 0].into_iter().collect();
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let hook_changes = changes.clone();
+        delivery_system.on_farm_change(move |change| {
+            hook_changes.lock().unwrap().push(*change);
+        });
+
+        delivery_system.extend(vec![12, 8]);
+        assert_eq!(
+            vec![FarmChange::Added(12), FarmChange::Added(8)],
+            *changes.lock().unwrap()
+        );
+
+        let mut farms = Vec::new();
+        for farm in &delivery_system {
+            farms.push(*farm);
+        }
+        assert_eq!(vec![8, 0, 12, 8], farms);
+    }
+
+    #[test]
+    fn eq_default() {
+        let mut delivery_system: FoodDeliverySystem<u8, &str> =
+            FoodDeliverySystem::with_farms(vec![(0, "Oak Farm"), (8, "Hillside")]);
+        let mut other: FoodDeliverySystem<u8, &str> = FoodDeliverySystem::default();
+        assert!(other.xor_distance().is_empty());
+
+        other.add_farm_with_data(0, "Oak Farm");
+        other.add_farm_with_data(8, "Hillside");
+        other.on_assignment(|_| {});
+        assert_eq!(delivery_system, other);
+
+        *other.farm_data_mut(8).unwrap() = "Green Acres";
+        assert_ne!(delivery_system, other);
+
+        *other.farm_data_mut(8).unwrap() = "Hillside";
+        delivery_system.set_farm_stock(0, 1);
+        assert_ne!(delivery_system, other);
+    }
+
+    #[test]
+    fn farm_distance_profile() {
+        let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0, 8, 12]);
+        let sample: Vec<u8> = (0..=255).collect();
+
+        let profile = delivery_system.farm_distance_profile(0, &sample);
+
+        // Farm 0 serves positions with the bit 3 unset.
+        assert_eq!(128, profile.len());
+        assert_eq!(Some(0), profile.quantile(0.0));
+        assert_eq!(Some(247), profile.quantile(1.0));
+
+        let histogram = profile.histogram();
+        assert_eq!(9, histogram.len());
+        assert_eq!(vec![1, 1, 2, 4, 0, 8, 16, 32, 64], histogram);
+
+        // Unknown farm has no customers.
+        let profile = delivery_system.farm_distance_profile(5, &sample);
+        assert!(profile.is_empty());
+        assert_eq!(None, profile.quantile(0.5));
+    }
+
+    #[test]
+    fn assign_round_capped() {
+        let mut delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8]);
+
+        let assigned = Arc::new(Mutex::new(Vec::new()));
+        let hook_assigned = assigned.clone();
+        delivery_system.on_assignment(move |assignment| {
+            hook_assigned.lock().unwrap().push(assignment.farm);
+        });
+
+        let round = delivery_system.assign_round_capped(&[1, 2, 3, 9, 10], 2);
+
+        let farms: Vec<Option<u64>> = round
+            .assignments
+            .iter()
+            .map(|assignment| assignment.map(|assignment| assignment.farm))
+            .collect();
+
+        // Customer 3 spills over to the farm 8, customer 10 finds both farms full.
+        assert_eq!(vec![Some(0), Some(0), Some(8), Some(8), None], farms);
+        assert_eq!(1, round.spilled);
+        assert_eq!(1, round.unassigned);
+        assert_eq!((11 - 3) as f64, round.extra_distance);
+        assert_eq!(vec![0, 0, 8, 8], *assigned.lock().unwrap());
+
+        // Zero cap assigns nothing.
+        let round = delivery_system.assign_round_capped(&[1], 0);
+        assert_eq!(vec![None], round.assignments);
+    }
+
+    #[test]
+    fn assign_orders() {
+        let mut delivery_system: FoodDeliverySystem<u8> =
+            FoodDeliverySystem::new(vec![0, 8, 8, 12]);
+
+        assert!(delivery_system.set_farm_stock(8, 4));
+        assert!(!delivery_system.set_farm_stock(7, 4));
+        assert_eq!(None, delivery_system.farm_stock(7));
+        assert_eq!(None, delivery_system.farm_stock(12));
+
+        let farms: Vec<Option<u8>> = delivery_system
+            .assign_orders(&[(9, 4), (9, 1), (9, 0)])
+            .iter()
+            .map(|assignment| assignment.map(|assignment| assignment.farm))
+            .collect();
+
+        // Farms at the same position share the stock, the farm 12 is not limited.
+        assert_eq!(vec![Some(8), Some(12), Some(8)], farms);
+        assert_eq!(Some(0), delivery_system.farm_stock(8));
+
+        // The stock goes with the last farm at the position.
+        assert!(delivery_system.remove_farm(8));
+        assert_eq!(Some(0), delivery_system.farm_stock(8));
+        assert!(delivery_system.remove_farm(8));
+        assert_eq!(None, delivery_system.farm_stock(8));
+
+        // No farm has enough stock.
+        delivery_system.set_farm_stock(0, 2);
+        delivery_system.set_farm_stock(12, 2);
+        assert_eq!(vec![None], delivery_system.assign_orders(&[(1, 3)]));
+        assert_eq!(Some(2), delivery_system.farm_stock(0));
+
+        let mut empty: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![]);
+        assert_eq!(vec![None], empty.assign_orders(&[(1, 0)]));
+    }
+
+    #[test]
+    fn closest_farms_batch() {
+        let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ]);
+        let queries: Vec<(u64, usize)> = (0..500).map(|i| (i * 7, i as usize % 20)).collect();
+
+        let batch = delivery_system.closest_farms_batch(&queries);
+
+        assert_eq!(queries.len(), batch.len());
+        for ((position, count), closest) in queries.iter().zip(batch.iter()) {
+            assert_eq!(&delivery_system.closest_farms(*position, *count), closest);
+        }
+
+        #[cfg(feature = "parallel")]
+        assert_eq!(
+            batch,
+            delivery_system.closest_farms_batch_parallel(&queries)
+        );
+
+        assert!(delivery_system.closest_farms_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn closest_farms_excluding() {
+        let farms: Vec<u64> = vec![
+            0, 1, 2, 4, 6, 8, 8, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ];
+        let delivery_system = FoodDeliverySystem::new(farms.clone());
+
+        for excluded in [vec![], vec![8], vec![8, 12, 2, 7], farms.clone()].iter() {
+            for count in 0..farms.len() + 2 {
+                let mut expected: Vec<u64> = delivery_system
+                    .closest_farms(10, farms.len())
+                    .into_iter()
+                    .filter(|farm| !excluded.contains(farm))
+                    .collect();
+                expected.truncate(count);
+
+                assert_eq!(
+                    expected,
+                    delivery_system.closest_farms_excluding(10, count, excluded)
+                );
+            }
+        }
+
+        // Duplicates of an excluded farm are skipped too.
+        assert_eq!(
+            vec![12, 2, 0],
+            delivery_system.closest_farms_excluding(10, 3, &[8])
+        );
+    }
+
+    #[test]
+    fn obfuscate_position() {
+        let delivery_system: FoodDeliverySystem<u16> =
+            FoodDeliverySystem::new(vec![0, 1, 2, 4, 6, 8, 12, 18, 300, 301, 4000, 65535]);
+
+        for position in (0..=u16::MAX).step_by(97) {
+            for count in 0..6 {
+                let closest_farms = delivery_system.closest_farms(position, count);
+                let obfuscated = delivery_system.obfuscate_position(position, count);
+
+                assert_eq!(
+                    closest_farms,
+                    delivery_system.closest_farms(obfuscated, count)
+                );
+                assert_eq!(
+                    delivery_system.position_anonymity(position, count) == 1,
+                    obfuscated == position
+                );
+
+                // Positions with the same closest farms share the obfuscated position.
+                let (_, largest) = delivery_system
+                    .xor_distance()
+                    .reverse_closest_range(&closest_farms)
+                    .unwrap();
+                if position != largest {
+                    assert_eq!(largest, obfuscated);
+                }
+            }
+        }
+
+        // All farms decide every bit of their only position.
+        let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new((0..=255).collect());
+        assert_eq!(7, delivery_system.obfuscate_position(7, 256));
+    }
+
+    #[test]
+    fn infer_capped_round() {
+        let delivery_system: FoodDeliverySystem<u8> =
+            FoodDeliverySystem::new(vec![3, 17, 18, 96, 200, 201]);
+        let positions: Vec<u8> = (0..40).map(|i: u8| i.wrapping_mul(97)).collect();
+
+        let round = delivery_system.assign_round_capped(&positions, 5);
+        let farms: Vec<Option<u8>> = round
+            .assignments
+            .iter()
+            .map(|assignment| assignment.map(|assignment| assignment.farm))
+            .collect();
+
+        let inferred = delivery_system.infer_capped_round(&farms, 5).unwrap();
+
+        for (position, constraints) in positions.iter().zip(inferred.iter()) {
+            // The real position satisfies the constraints.
+            assert!((0..8).all(|index| constraints
+                .bits()
+                .get_bit(index)
+                .is_none_or(|value| value == ((position >> index) & 1 == 1))));
+        }
+
+        // Positions satisfying the constraints would be assigned the same farms.
+        let guessed: Vec<u8> = inferred
+            .iter()
+            .map(|constraints| constraints.position())
+            .collect();
+        let replayed = delivery_system.assign_round_capped(&guessed, 5);
+        assert_eq!(round.assignments.len(), replayed.assignments.len());
+        for (real, guess) in round.assignments.iter().zip(replayed.assignments.iter()) {
+            assert_eq!(
+                real.map(|assignment| assignment.farm),
+                guess.map(|assignment| assignment.farm)
+            );
+        }
+    }
+
+    #[test]
+    fn infer_capped_assignment_errors() {
+        let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0, 8, 12]);
+        let loads: BTreeMap<u8, usize> = [(8, 1)].iter().copied().collect();
+
+        assert_eq!(
+            Some(DeliveryError::FarmFull),
+            delivery_system.infer_capped_assignment(8, &loads, 1).err()
+        );
+        assert_eq!(
+            Some(DeliveryError::UnknownFarm),
+            delivery_system.infer_capped_assignment(5, &loads, 1).err()
+        );
+        assert_eq!(
+            "Assigned farm is not a farm of the system!",
+            DeliveryError::UnknownFarm.to_string()
+        );
+        assert_eq!(
+            Some(DeliveryError::FarmFull),
+            delivery_system
+                .infer_capped_round(&[Some(0), None, Some(0)], 1)
+                .err()
+        );
+    }
 }
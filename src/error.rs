@@ -0,0 +1,285 @@
+//! Error types of the bits representation and of the xor distance structure.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::bits::Bits;
+//! use xor_distance_exercise::error::BitsError;
+//!
+//! let mut bit_rep = Bits::new::<u8>();
+//! bit_rep.set_bit(4, true);
+//!
+//! let error = bit_rep.set_bit_within_constrains(4, false).unwrap_err();
+//!
+//! assert_eq!(
+//!     BitsError::ConstraintViolation {
+//!         index: 4,
+//!         existing: true,
+//!         requested: false,
+//!     },
+//!     error
+//! );
+//! assert_eq!("Already decided bit value can not be changed!", error.to_string());
+//! ```
+
+use core::error::Error;
+use core::fmt;
+
+/// Error of an operation on bits representations or on bits of an `Integer`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitsError {
+    /// The bit is already decided to a different value.
+    ConstraintViolation {
+        /// Index of the bit.
+        index: usize,
+        /// Value the bit is decided to.
+        existing: bool,
+        /// Value requested to be set.
+        requested: bool,
+    },
+    /// The requested type has less bits than the representation.
+    InsufficientBitWidth {
+        /// Number of bits of the representation.
+        required: usize,
+        /// Number of bits of the requested type.
+        available: usize,
+    },
+    /// The bit index is not less than the number of bits.
+    IndexOutOfRange {
+        /// The bit index.
+        index: usize,
+        /// Number of bits.
+        size: usize,
+    },
+    /// The bit is not decided as yet.
+    Undecided {
+        /// Index of the bit.
+        index: usize,
+    },
+    /// The representations have different sizes.
+    SizeMismatch {
+        /// Size of the first representation.
+        left: usize,
+        /// Size of the second representation.
+        right: usize,
+    },
+    /// The values are equal, they have no differing bit.
+    EqualValues,
+}
+
+impl fmt::Display for BitsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            BitsError::ConstraintViolation { .. } => {
+                "Already decided bit value can not be changed!"
+            }
+            BitsError::InsufficientBitWidth { .. } => {
+                "Requested type has not enough bits to represent the whole number!"
+            }
+            BitsError::IndexOutOfRange { .. } => "Bit index is out of range!",
+            BitsError::Undecided { .. } => "Bit value is not decided as yet!",
+            BitsError::SizeMismatch { .. } => "Bits representations have different sizes!",
+            BitsError::EqualValues => "Values have no differing bit!",
+        };
+
+        f.write_str(message)
+    }
+}
+
+impl Error for BitsError {}
+
+/// Error of an operation on the xor distance structure.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum XorDistanceError {
+    /// Points were modified since the scan started.
+    ScanInvalidated {
+        /// Generation of the points the scan started at.
+        scanned: u64,
+        /// Current generation of the points.
+        current: u64,
+    },
+    /// The point is already present and duplicate points are rejected.
+    DuplicatePoint {
+        /// Index of the repeated point in the provided points.
+        index: usize,
+    },
+    /// Bits representation of a position failed.
+    Bits(BitsError),
+    /// The fixed-capacity structure is full.
+    CapacityExhausted {
+        /// The capacity of the structure.
+        capacity: usize,
+    },
+}
+
+impl fmt::Display for XorDistanceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            XorDistanceError::ScanInvalidated { .. } => {
+                f.write_str("Points were modified since the scan started!")
+            }
+            XorDistanceError::DuplicatePoint { .. } => {
+                f.write_str("Duplicate points are not allowed!")
+            }
+            XorDistanceError::Bits(error) => error.fmt(f),
+            XorDistanceError::CapacityExhausted { .. } => {
+                f.write_str("The array capacity is exhausted!")
+            }
+        }
+    }
+}
+
+impl Error for XorDistanceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            XorDistanceError::Bits(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<BitsError> for XorDistanceError {
+    fn from(error: BitsError) -> Self {
+        XorDistanceError::Bits(error)
+    }
+}
+
+/// Error of loading points from a reader or a file, or of parsing a query log, available with the
+/// `std` feature only.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum LoadError {
+    /// Reading failed.
+    Io(std::io::Error),
+    /// The value is not a point of the point type, e.g. not a number or out of its range.
+    InvalidPoint {
+        /// Line of the value, indexed from one.
+        line: usize,
+        /// The value.
+        value: std::string::String,
+    },
+    /// The input is not of the expected format.
+    Syntax {
+        /// Line the input stopped to be of the format at, indexed from one.
+        line: usize,
+        /// Description of what was expected instead.
+        expected: &'static str,
+    },
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "Reading points failed: {}!", error),
+            LoadError::InvalidPoint { line, value } => {
+                write!(f, "Invalid point {:?} on line {}!", value, line)
+            }
+            LoadError::Syntax { line, expected } => {
+                write!(f, "Expected {} on line {}!", expected, line)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for LoadError {
+    fn from(error: std::io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+/// Error of an operation on the food delivery system, available with the `std` feature only.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeliveryError {
+    /// The farm is not a farm of the system.
+    UnknownFarm,
+    /// The farm has no capacity left.
+    FarmFull,
+    /// Territories need more prefix blocks than allowed.
+    TooManyBlocks,
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            DeliveryError::UnknownFarm => "Assigned farm is not a farm of the system!",
+            DeliveryError::FarmFull => "Assigned farm was full!",
+            DeliveryError::TooManyBlocks => "Territories need more prefix blocks than allowed!",
+        };
+
+        f.write_str(message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DeliveryError {}
+
+/// Error of converting geohashes and coordinates to keys, available with the `std` feature only.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeohashError {
+    /// The latitude or the longitude is out of its range.
+    CoordinatesOutOfRange,
+    /// The geohash has more characters than fit into a key.
+    TooLong {
+        /// Number of characters of the geohash.
+        len: usize,
+    },
+    /// The character is not of the geohash alphabet.
+    InvalidCharacter {
+        /// Index of the character.
+        index: usize,
+    },
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for GeohashError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            GeohashError::CoordinatesOutOfRange => "Coordinates are out of range!",
+            GeohashError::TooLong { .. } => "Geohash is too long!",
+            GeohashError::InvalidCharacter { .. } => "Invalid geohash character!",
+        };
+
+        f.write_str(message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for GeohashError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{BitsError, XorDistanceError};
+    use std::error::Error;
+
+    #[test]
+    fn source() {
+        let bits = BitsError::IndexOutOfRange { index: 8, size: 8 };
+        let error = XorDistanceError::from(bits);
+
+        assert_eq!("Bit index is out of range!", error.to_string());
+        assert_eq!(bits.to_string(), error.source().unwrap().to_string());
+
+        let error = XorDistanceError::ScanInvalidated {
+            scanned: 0,
+            current: 1,
+        };
+        assert!(error.source().is_none());
+    }
+}
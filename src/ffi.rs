@@ -0,0 +1,224 @@
+//! C interface of the closest points and their reverse over `u64` points, for embedding into
+//! C and C++ projects, e.g. DHT implementations.
+//!
+//! Points live behind an opaque `XdHandle` created by `xd_new` and released by `xd_free`, all
+//! results are written into caller-provided buffers. The declarations are in
+//! `include/xor_distance.h`, the static library is built by e.g.
+//! `cargo rustc --lib --release --features ffi --crate-type staticlib`.
+//!
+//! Available with the `ffi` feature only.
+//!
+//! # Examples
+//!
+//! ```c
+//! #include "xor_distance.h"
+//!
+//! uint64_t points[] = {0, 1, 2, 4, 6, 8, 12};
+//! XdHandle *handle = xd_new(points, 7);
+//!
+//! uint64_t closest[3];
+//! size_t len = xd_closest(handle, 10, 3, closest, 3);
+//!
+//! uint64_t position;
+//! if (xd_reverse_closest(handle, closest, len, &position) == XD_OK) {
+//!     // `position` has the same closest points.
+//! }
+//!
+//! xd_free(handle);
+//! ```
+
+use crate::xor_distance::XorDistance;
+use alloc::boxed::Box;
+use core::slice;
+
+/// The call succeeded.
+pub const XD_OK: i32 = 0;
+
+/// The closest points are not closest points of any position.
+pub const XD_NOT_FOUND: i32 = 1;
+
+/// A required pointer argument is null.
+pub const XD_NULL_POINTER: i32 = -1;
+
+/// Opaque handle of points.
+pub struct XdHandle {
+    xor_distance: XorDistance<u64>,
+}
+
+/// Return slice of the `len` values at the pointer, empty for zero `len` even if it is null.
+///
+/// # Safety
+///
+/// The pointer must be valid for reads of `len` values unless `len` is zero.
+unsafe fn values<'a>(values: *const u64, len: usize) -> &'a [u64] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(values, len)
+    }
+}
+
+/// Create a handle of the `len` points at `points`, null if `points` is null while `len` is not
+/// zero. The handle must be released by `xd_free`.
+///
+/// # Safety
+///
+/// `points` must be valid for reads of `len` values unless `len` is zero.
+#[no_mangle]
+pub unsafe extern "C" fn xd_new(points: *const u64, len: usize) -> *mut XdHandle {
+    if points.is_null() && len > 0 {
+        return core::ptr::null_mut();
+    }
+
+    let xor_distance = XorDistance::new(values(points, len).to_vec());
+
+    Box::into_raw(Box::new(XdHandle { xor_distance }))
+}
+
+/// Write up to `count` closest points to the position `x` into `out`, ordered from the closest,
+/// and return how many were written.
+///
+/// At most `out_len` points are written, nothing at all if `handle` or `out` is null.
+///
+/// # Safety
+///
+/// `handle` must be a live handle of `xd_new` or null, `out` must be valid for writes of
+/// `out_len` values or null.
+#[no_mangle]
+pub unsafe extern "C" fn xd_closest(
+    handle: *const XdHandle,
+    x: u64,
+    count: usize,
+    out: *mut u64,
+    out_len: usize,
+) -> usize {
+    let handle = match handle.as_ref() {
+        Some(handle) if !out.is_null() => handle,
+        _ => return 0,
+    };
+
+    let closest = handle.xor_distance.closest(x, count.min(out_len));
+    slice::from_raw_parts_mut(out, closest.len()).copy_from_slice(&closest);
+
+    closest.len()
+}
+
+/// Write a position whose closest points are the `len` points at `closest` into `position`.
+///
+/// Returns `XD_OK` if the position was written, `XD_NOT_FOUND` if there is no such a position
+/// and `XD_NULL_POINTER` if `handle` or `position` is null, or `closest` is null while `len` is
+/// not zero.
+///
+/// # Safety
+///
+/// `handle` must be a live handle of `xd_new` or null, `closest` must be valid for reads of
+/// `len` values unless `len` is zero and `position` must be valid for a write or null.
+#[no_mangle]
+pub unsafe extern "C" fn xd_reverse_closest(
+    handle: *const XdHandle,
+    closest: *const u64,
+    len: usize,
+    position: *mut u64,
+) -> i32 {
+    let handle = match handle.as_ref() {
+        Some(handle) if !position.is_null() && (!closest.is_null() || len == 0) => handle,
+        _ => return XD_NULL_POINTER,
+    };
+
+    match handle.xor_distance.reverse_closest(values(closest, len)) {
+        Some(reversed) => {
+            *position = reversed;
+            XD_OK
+        }
+        None => XD_NOT_FOUND,
+    }
+}
+
+/// Release the handle, nothing is done for null.
+///
+/// # Safety
+///
+/// `handle` must be a live handle of `xd_new` or null, it must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn xd_free(handle: *mut XdHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        xd_closest, xd_free, xd_new, xd_reverse_closest, XD_NOT_FOUND, XD_NULL_POINTER, XD_OK,
+    };
+    use core::ptr;
+
+    #[test]
+    fn closest_and_reverse() {
+        let points: Vec<u64> = vec![0, 1, 2, 4, 6, 8, 12];
+
+        unsafe {
+            let handle = xd_new(points.as_ptr(), points.len());
+            assert!(!handle.is_null());
+
+            let mut closest = [0u64; 5];
+            assert_eq!(3, xd_closest(handle, 10, 3, closest.as_mut_ptr(), 5));
+            assert_eq!([8, 12, 2], closest[..3]);
+
+            // The buffer bounds the count.
+            assert_eq!(2, xd_closest(handle, 10, 3, closest.as_mut_ptr(), 2));
+
+            let mut position = 0;
+            assert_eq!(
+                XD_OK,
+                xd_reverse_closest(handle, closest.as_ptr(), 2, &mut position)
+            );
+            assert_eq!(8, position);
+
+            let inconsistent = [8u64, 0, 12];
+            assert_eq!(
+                XD_NOT_FOUND,
+                xd_reverse_closest(handle, inconsistent.as_ptr(), 3, &mut position)
+            );
+
+            xd_free(handle);
+        }
+    }
+
+    #[test]
+    fn null_pointers() {
+        unsafe {
+            assert!(xd_new(ptr::null(), 1).is_null());
+
+            let handle = xd_new(ptr::null(), 0);
+            assert!(!handle.is_null());
+
+            let mut out = [0u64; 1];
+            assert_eq!(0, xd_closest(handle, 0, 1, out.as_mut_ptr(), 1));
+            assert_eq!(0, xd_closest(ptr::null(), 0, 1, out.as_mut_ptr(), 1));
+            assert_eq!(0, xd_closest(handle, 0, 1, ptr::null_mut(), 1));
+
+            let mut position = 7;
+            assert_eq!(
+                XD_OK,
+                xd_reverse_closest(handle, ptr::null(), 0, &mut position)
+            );
+            assert_eq!(0, position);
+            assert_eq!(
+                XD_NULL_POINTER,
+                xd_reverse_closest(handle, ptr::null(), 1, &mut position)
+            );
+            assert_eq!(
+                XD_NULL_POINTER,
+                xd_reverse_closest(handle, out.as_ptr(), 1, ptr::null_mut())
+            );
+            assert_eq!(
+                XD_NULL_POINTER,
+                xd_reverse_closest(ptr::null(), out.as_ptr(), 1, &mut position)
+            );
+
+            xd_free(handle);
+            xd_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,220 @@
+//! Per-farm load forecasting from rolling load statistics.
+//!
+//! Load is observed per period (e.g. an hour) as the number of orders assigned to each farm and
+//! forecasted by an exponentially weighted moving average, optionally corrected by a seasonal
+//! component, so load can be acted on before it actually arrives.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::forecast::LoadForecaster;
+//!
+//! // Daily season of two periods, busy mornings and quiet evenings.
+//! let mut forecaster: LoadForecaster<u64> = LoadForecaster::new(0.5, 2, 64);
+//!
+//! for _ in 0..4 {
+//!     forecaster.record_period(vec![8; 10]);
+//!     forecaster.record_period(vec![8; 2]);
+//! }
+//!
+//! let morning = forecaster.forecast_load(8, 1).unwrap();
+//! let evening = forecaster.forecast_load(8, 2).unwrap();
+//!
+//! assert!((morning - 10.0).abs() < 1e-9);
+//! assert!((evening - 2.0).abs() < 1e-9);
+//! ```
+
+use num_traits::{PrimInt, Unsigned};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Rolling per-farm load history with EWMA and seasonal forecasting.
+pub struct LoadForecaster<T> {
+    alpha: f64,
+    season: usize,
+    max_history: usize,
+    history: BTreeMap<T, VecDeque<f64>>,
+}
+
+impl<T: PrimInt + Unsigned> LoadForecaster<T> {
+    /// Create a new forecaster.
+    ///
+    /// `alpha` is the EWMA smoothing factor from `(0, 1]`, the higher the more weight recent
+    /// periods have. `season` is the number of periods after which load repeats, zero or one
+    /// disables the seasonal component. At most `max_history` periods are kept per farm.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `alpha` is out of `(0, 1]` or `max_history` is zero.
+    pub fn new(alpha: f64, season: usize, max_history: usize) -> Self {
+        assert!(
+            alpha > 0.0 && alpha <= 1.0,
+            "Smoothing factor is out of range!"
+        );
+        assert!(max_history > 0, "History can't be empty!");
+
+        LoadForecaster {
+            alpha,
+            season,
+            max_history,
+            history: BTreeMap::new(),
+        }
+    }
+
+    /// Record one period of load given by farms of the orders assigned during it.
+    ///
+    /// Farms seen before but without orders in the period record zero load.
+    pub fn record_period<I: IntoIterator<Item = T>>(&mut self, assigned_farms: I) {
+        let mut loads: BTreeMap<T, f64> = BTreeMap::new();
+
+        for farm in assigned_farms {
+            *loads.entry(farm).or_insert(0.0) += 1.0;
+        }
+
+        for (farm, history) in self.history.iter_mut() {
+            history.push_back(loads.remove(farm).unwrap_or(0.0));
+        }
+
+        for (farm, load) in loads {
+            self.history.insert(farm, VecDeque::from(vec![load]));
+        }
+
+        let max_history = self.max_history;
+        for history in self.history.values_mut() {
+            while history.len() > max_history {
+                history.pop_front();
+            }
+        }
+    }
+
+    /// Return number of periods recorded for the farm.
+    pub fn periods(&self, farm: T) -> usize {
+        self.history.get(&farm).map_or(0, VecDeque::len)
+    }
+
+    /// Return the forecasted load of the farm `horizon` periods ahead, `1` being the next period.
+    ///
+    /// The seasonal component is applied once at least one full season was recorded. Returns
+    /// `None` if no load of the farm was recorded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `horizon` is zero.
+    pub fn forecast_load(&self, farm: T, horizon: usize) -> Option<f64> {
+        assert!(horizon > 0, "Forecast horizon must be at least one period!");
+
+        let history = self.history.get(&farm)?;
+        let seasonal = self.seasonal_indexes(history);
+
+        // Level of the deseasonalized load.
+        let mut level = None;
+        for (period, load) in history.iter().enumerate() {
+            let load = load - seasonal.as_ref().map_or(0.0, |s| s[period % s.len()]);
+
+            level = Some(match level {
+                None => load,
+                Some(level) => self.alpha * load + (1.0 - self.alpha) * level,
+            });
+        }
+
+        let period = history.len() + horizon - 1;
+        let forecast = level? + seasonal.as_ref().map_or(0.0, |s| s[period % s.len()]);
+
+        Some(forecast.max(0.0))
+    }
+
+    /// Return the additive seasonal index of every season phase, `None` if seasonality is not
+    /// applicable.
+    fn seasonal_indexes(&self, history: &VecDeque<f64>) -> Option<Vec<f64>> {
+        if self.season < 2 || history.len() < self.season {
+            return None;
+        }
+
+        let mean = history.iter().sum::<f64>() / history.len() as f64;
+        let mut sums = vec![0.0; self.season];
+        let mut counts = vec![0usize; self.season];
+
+        for (period, load) in history.iter().enumerate() {
+            sums[period % self.season] += load;
+            counts[period % self.season] += 1;
+        }
+
+        Some(
+            sums.iter()
+                .zip(counts.iter())
+                .map(|(sum, count)| sum / *count as f64 - mean)
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LoadForecaster;
+
+    #[test]
+    fn ewma() {
+        let mut forecaster: LoadForecaster<u64> = LoadForecaster::new(0.5, 0, 16);
+
+        assert_eq!(None, forecaster.forecast_load(1, 1));
+
+        forecaster.record_period(vec![1, 1, 1, 1]);
+        forecaster.record_period(vec![1, 1]);
+
+        // Level 4, then 0.5 * 2 + 0.5 * 4.
+        assert_eq!(Some(3.0), forecaster.forecast_load(1, 1));
+        assert_eq!(Some(3.0), forecaster.forecast_load(1, 5));
+    }
+
+    #[test]
+    fn zero_load_periods() {
+        let mut forecaster: LoadForecaster<u64> = LoadForecaster::new(1.0, 0, 16);
+
+        forecaster.record_period(vec![1, 2]);
+        forecaster.record_period(vec![2]);
+
+        assert_eq!(2, forecaster.periods(1));
+        assert_eq!(Some(0.0), forecaster.forecast_load(1, 1));
+        assert_eq!(Some(1.0), forecaster.forecast_load(2, 1));
+        assert_eq!(0, forecaster.periods(3));
+    }
+
+    #[test]
+    fn rolling_history() {
+        let mut forecaster: LoadForecaster<u64> = LoadForecaster::new(0.5, 0, 2);
+
+        forecaster.record_period(vec![0; 100]);
+        forecaster.record_period(vec![0; 2]);
+        forecaster.record_period(vec![0; 2]);
+
+        // The oldest period dropped out of the history.
+        assert_eq!(2, forecaster.periods(0));
+        assert_eq!(Some(2.0), forecaster.forecast_load(0, 1));
+    }
+
+    #[test]
+    fn seasonal() {
+        let mut forecaster: LoadForecaster<u64> = LoadForecaster::new(0.3, 3, 64);
+
+        for _ in 0..5 {
+            forecaster.record_period(vec![5; 6]);
+            forecaster.record_period(vec![5; 3]);
+            forecaster.record_period(Vec::new());
+        }
+
+        let forecasts: Vec<f64> = (1..=4)
+            .map(|horizon| forecaster.forecast_load(5, horizon).unwrap())
+            .collect();
+
+        for (forecast, expected) in forecasts.iter().zip([6.0, 3.0, 0.0, 6.0].iter()) {
+            assert!((forecast - expected).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Smoothing factor is out of range!")]
+    fn invalid_alpha() {
+        let _: LoadForecaster<u64> = LoadForecaster::new(0.0, 0, 1);
+    }
+}
@@ -0,0 +1,172 @@
+//! Deterministic generators of point sets, e.g. farm distributions of benchmarks and examples.
+//!
+//! Every generator is seeded, so the same seed gives the same points with the same `rand`
+//! version. Uniform points model farms spread evenly, clustered points model farms around a few
+//! centres and prefix clustered points model farms of a single region of a geohash-like space.
+//!
+//! Available with the `rand` feature only.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::generator::{clustered_points, prefix_clustered, random_points};
+//!
+//! let points: Vec<u64> = random_points(7, 1000);
+//! assert_eq!(points, random_points(7, 1000));
+//!
+//! let clustered: Vec<u32> = clustered_points(7, 1000, 3, 0xFF);
+//! assert_eq!(1000, clustered.len());
+//!
+//! let region: Vec<u16> = prefix_clustered(7, 1000, 4);
+//! assert!(region.iter().all(|point| point >> 12 == region[0] >> 12));
+//! ```
+
+use crate::bitops::BitOps;
+use crate::bits::Bits;
+use num_traits::{PrimInt, Unsigned};
+use rand::distributions::{Distribution, Standard};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Return `n` uniformly random points.
+pub fn random_points<T>(seed: u64, n: usize) -> Vec<T>
+where
+    T: PrimInt + Unsigned,
+    Standard: Distribution<T>,
+{
+    StdRng::seed_from_u64(seed)
+        .sample_iter(&Standard)
+        .take(n)
+        .collect()
+}
+
+/// Return `n` points around `clusters` random centres, every point within the XOR distance
+/// `spread` of a random one of the centres.
+///
+/// # Panics
+///
+/// Panics if `clusters` is zero while `n` is not.
+pub fn clustered_points<T>(seed: u64, n: usize, clusters: usize, spread: T) -> Vec<T>
+where
+    T: PrimInt + BitOps + Unsigned,
+    Standard: Distribution<T>,
+{
+    assert!(n == 0 || clusters > 0, "Clusters must not be zero!");
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let centres: Vec<T> = rng.sample_iter(&Standard).take(clusters).collect();
+
+    // Offsets are masked by the bits of the spread and rejected if further.
+    let mask = T::mask_low(Bits::bit_size::<T>() - spread.leading_zeros() as usize);
+
+    (0..n)
+        .map(|_| {
+            let centre = centres[rng.gen_range(0, clusters)];
+
+            loop {
+                let offset = rng.gen::<T>() & mask;
+
+                if offset <= spread {
+                    return centre ^ offset;
+                }
+            }
+        })
+        .collect()
+}
+
+/// Return `n` random points sharing the same random `prefix_bits` most significant bits.
+///
+/// # Panics
+///
+/// Panics if `prefix_bits` is greater than the number of bits of the points.
+pub fn prefix_clustered<T>(seed: u64, n: usize, prefix_bits: usize) -> Vec<T>
+where
+    T: PrimInt + BitOps + Unsigned,
+    Standard: Distribution<T>,
+{
+    assert!(
+        prefix_bits <= Bits::bit_size::<T>(),
+        "Prefix is longer than the points!"
+    );
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mask = T::mask_high(prefix_bits);
+    let prefix = rng.gen::<T>() & mask;
+
+    rng.sample_iter(&Standard)
+        .take(n)
+        .map(|point: T| prefix | (point & !mask))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{clustered_points, prefix_clustered, random_points};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn random() {
+        let points: Vec<u64> = random_points(1548, 500);
+
+        assert_eq!(500, points.len());
+        assert_eq!(points, random_points(1548, 500));
+        assert_ne!(points, random_points(1549, 500));
+        assert_eq!(points[..100].to_vec(), random_points::<u64>(1548, 100));
+        assert!(random_points::<u8>(1548, 0).is_empty());
+    }
+
+    #[test]
+    fn clustered() {
+        let points: Vec<u32> = clustered_points(1548, 2000, 4, 1000);
+        let centres: Vec<u32> = random_points(1548, 4);
+
+        assert_eq!(points, clustered_points(1548, 2000, 4, 1000));
+        assert!(points
+            .iter()
+            .all(|point| centres.iter().any(|centre| point ^ centre <= 1000)));
+
+        // Every cluster gets some points.
+        for centre in centres.iter() {
+            assert!(points.iter().any(|point| point ^ centre <= 1000));
+        }
+
+        // No spread gives the centres only, the full spread any points.
+        let centres: BTreeSet<u8> = clustered_points(1548, 100, 3, 0).into_iter().collect();
+        assert!(centres.len() <= 3);
+        assert_eq!(100, clustered_points::<u8>(1548, 100, 1, u8::MAX).len());
+        assert!(clustered_points::<u8>(1548, 0, 0, 5).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Clusters must not be zero!")]
+    fn clustered_no_clusters() {
+        clustered_points::<u8>(1548, 1, 0, 5);
+    }
+
+    #[test]
+    fn prefixes() {
+        let points: Vec<u64> = prefix_clustered(1548, 1000, 20);
+        let prefix = points[0] >> 44;
+
+        assert_eq!(points, prefix_clustered(1548, 1000, 20));
+        assert!(points.iter().all(|point| point >> 44 == prefix));
+        assert!(points.iter().collect::<BTreeSet<_>>().len() > 990);
+
+        assert_eq!(
+            1,
+            prefix_clustered::<u8>(1548, 10, 8)
+                .iter()
+                .collect::<BTreeSet<_>>()
+                .len()
+        );
+        assert_eq!(10, prefix_clustered::<u8>(1548, 10, 0).len());
+    }
+
+    #[test]
+    #[should_panic(expected = "Prefix is longer than the points!")]
+    fn prefix_too_long() {
+        prefix_clustered::<u8>(1548, 1, 9);
+    }
+}
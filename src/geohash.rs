@@ -0,0 +1,189 @@
+//! Conversion of geohash strings and coordinates to XOR keys.
+//!
+//! A geohash alternates longitude and latitude bisection bits, which is the Morton (Z-order)
+//! interleaving of both coordinates. Keys keep the geohash bits from the most significant one,
+//! so positions sharing a geohash prefix share a key prefix and are close by XOR distance.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+//! use xor_distance_exercise::geohash::{encode, key_to_geohash};
+//!
+//! // Farms in Aarhus and in Prague.
+//! let delivery_system = FoodDeliverySystem::from_geohashes(&["u1zr2", "u2fkb"]).unwrap();
+//!
+//! let customer = encode(56.1572, 10.2107).unwrap();
+//! let farm = delivery_system.assign_order(customer).unwrap().farm;
+//!
+//! assert_eq!("u1zr2", key_to_geohash(farm, 5));
+//! ```
+
+use crate::const_bits::{deinterleave_u64, interleave_u32};
+use crate::delivery_system::FoodDeliverySystem;
+use crate::error::GeohashError;
+
+/// Characters of the geohash base32 alphabet, by their values.
+const ALPHABET: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+/// Number of bits per geohash character.
+const CHARACTER_BITS: usize = 5;
+
+/// Maximal geohash length fitting into a key.
+pub const MAX_PRECISION: usize = 64 / CHARACTER_BITS;
+
+/// Number of cells per coordinate.
+const CELLS: f64 = 4_294_967_296.0;
+
+/// Return key of the coordinates, the Morton interleaving of their cells.
+///
+/// Returns `Err(GeohashError::CoordinatesOutOfRange)` if the latitude is not within `-90..=90` or
+/// the longitude is not within `-180..=180`.
+pub fn encode(latitude: f64, longitude: f64) -> Result<u64, GeohashError> {
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(GeohashError::CoordinatesOutOfRange);
+    }
+
+    let latitude_cell = cell((latitude + 90.0) / 180.0);
+    let longitude_cell = cell((longitude + 180.0) / 360.0);
+
+    // The first geohash bit is a longitude one, so longitude takes the odd bit indexes.
+    Ok(interleave_u32(latitude_cell, longitude_cell))
+}
+
+/// Return `(latitude, longitude)` of the center of the cell the key stands for.
+pub fn decode(key: u64) -> (f64, f64) {
+    let (latitude_cell, longitude_cell) = deinterleave_u64(key);
+
+    let latitude = (f64::from(latitude_cell) + 0.5) / CELLS * 180.0 - 90.0;
+    let longitude = (f64::from(longitude_cell) + 0.5) / CELLS * 360.0 - 180.0;
+
+    (latitude, longitude)
+}
+
+/// Return key of the geohash, the bits beyond the geohash precision are zero.
+///
+/// Returns `Err(GeohashError::TooLong)` if the geohash is longer than `MAX_PRECISION` and
+/// `Err(GeohashError::InvalidCharacter)` if it contains a character outside of the geohash
+/// alphabet.
+pub fn geohash_to_key(geohash: &str) -> Result<u64, GeohashError> {
+    if geohash.len() > MAX_PRECISION {
+        return Err(GeohashError::TooLong { len: geohash.len() });
+    }
+
+    let mut key: u64 = 0;
+
+    for (index, character) in geohash.bytes().enumerate() {
+        let value = ALPHABET
+            .iter()
+            .position(|c| *c == character.to_ascii_lowercase())
+            .ok_or(GeohashError::InvalidCharacter { index })?;
+
+        key |= (value as u64) << (64 - CHARACTER_BITS * (index + 1));
+    }
+
+    Ok(key)
+}
+
+/// Return geohash of the `precision` characters bounding the key.
+///
+/// # Panics
+///
+/// Panics if `precision` is greater than `MAX_PRECISION`.
+pub fn key_to_geohash(key: u64, precision: usize) -> String {
+    assert!(
+        precision <= MAX_PRECISION,
+        "Geohash precision is out of range!"
+    );
+
+    (0..precision)
+        .map(|index| {
+            let value = (key >> (64 - CHARACTER_BITS * (index + 1))) & 0b1_1111;
+
+            char::from(ALPHABET[value as usize])
+        })
+        .collect()
+}
+
+impl FoodDeliverySystem<u64> {
+    /// Create a new Food Delivery System with farms at the geohash positions.
+    ///
+    /// Returns `Err(GeohashError)` of the first invalid geohash, see `geohash_to_key`.
+    pub fn from_geohashes(geohashes: &[&str]) -> Result<Self, GeohashError> {
+        let farms = geohashes
+            .iter()
+            .map(|geohash| geohash_to_key(geohash))
+            .collect::<Result<Vec<u64>, GeohashError>>()?;
+
+        Ok(FoodDeliverySystem::new(farms))
+    }
+}
+
+/// Return cell of the coordinate scaled to `0.0..=1.0`.
+fn cell(scaled: f64) -> u32 {
+    // The upper bound belongs to the last cell.
+    (scaled * CELLS).min(CELLS - 1.0) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode, geohash_to_key, key_to_geohash, MAX_PRECISION};
+    use crate::delivery_system::FoodDeliverySystem;
+    use crate::error::GeohashError;
+
+    #[test]
+    fn known_geohash() {
+        let key = encode(57.64911, 10.40744).unwrap();
+
+        assert_eq!("u4pruydqqvj", key_to_geohash(key, 11));
+        // Keys share all the geohash bits.
+        assert_eq!(key >> 9, geohash_to_key("u4pruydqqvj").unwrap() >> 9);
+    }
+
+    #[test]
+    fn round_trip() {
+        let key = geohash_to_key("ezs42").unwrap();
+
+        assert_eq!("ezs42", key_to_geohash(key, 5));
+        assert_eq!("ezs4200", key_to_geohash(key, 7));
+        assert_eq!(key, geohash_to_key("EZS42").unwrap());
+
+        let (latitude, longitude) = decode(encode(-33.8688, 151.2093).unwrap());
+        assert!((latitude + 33.8688).abs() < 1e-6);
+        assert!((longitude - 151.2093).abs() < 1e-6);
+
+        assert_eq!(u64::MAX, encode(90.0, 180.0).unwrap());
+        assert_eq!(0, encode(-90.0, -180.0).unwrap());
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(
+            Err(GeohashError::InvalidCharacter { index: 3 }),
+            geohash_to_key("ezsa2")
+        );
+        assert_eq!(
+            Err(GeohashError::TooLong { len: 13 }),
+            geohash_to_key("0123456789bcd")
+        );
+        assert_eq!(Err(GeohashError::CoordinatesOutOfRange), encode(90.5, 0.0));
+        assert_eq!(
+            Err(GeohashError::CoordinatesOutOfRange),
+            encode(0.0, f64::NAN)
+        );
+        assert_eq!(
+            "Invalid geohash character!",
+            FoodDeliverySystem::from_geohashes(&["u1zr2", "a"])
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Geohash precision is out of range!")]
+    fn precision_out_of_range() {
+        key_to_geohash(0, MAX_PRECISION + 1);
+    }
+}
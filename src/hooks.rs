@@ -0,0 +1,302 @@
+//! Event hooks of the food delivery system.
+//!
+//! Hooks are invoked synchronously, in registration order, right after the event happens, so
+//! integrations (notifications, metrics, webhooks) can react to events without polling.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use std::sync::atomic::{AtomicUsize, Ordering};
+//! use std::sync::Arc;
+//! use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+//!
+//! let mut delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+//!
+//! let assignments = Arc::new(AtomicUsize::new(0));
+//! let counter = assignments.clone();
+//! delivery_system.on_assignment(move |_| {
+//!     counter.fetch_add(1, Ordering::SeqCst);
+//! });
+//!
+//! delivery_system.assign_order(10);
+//! delivery_system.assign_order(3);
+//!
+//! assert_eq!(2, assignments.load(Ordering::SeqCst));
+//! ```
+
+use crate::bitops::BitOps;
+use crate::tiers::ServiceTier;
+use num_traits::PrimInt;
+use std::hash::{Hash, Hasher};
+
+/// Assignment of a customer's order to a farm.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Assignment<T> {
+    /// Customer's position.
+    pub position: T,
+    /// Farm the order is delivered from.
+    pub farm: T,
+    /// Service tier of the delivery distance, `None` if the system has no service tiers.
+    pub tier: Option<ServiceTier>,
+}
+
+/// Change of the farms set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FarmChange<T> {
+    /// Farm was added to the system.
+    Added(T),
+    /// Farm was removed from the system.
+    Removed(T),
+}
+
+/// Hash stable across platforms and crate versions, the hashed data is the position followed by
+/// the farm, both as little-endian bytes, and a byte of the tier: `0` for none, `1` for express,
+/// `2` for standard and `3` for extended.
+impl<T: PrimInt> Hash for Assignment<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.position.hash_stable(state);
+        self.farm.hash_stable(state);
+        state.write_u8(match self.tier {
+            None => 0,
+            Some(ServiceTier::Express) => 1,
+            Some(ServiceTier::Standard) => 2,
+            Some(ServiceTier::Extended) => 3,
+        });
+    }
+}
+
+/// Hash stable across platforms and crate versions, the hashed data is a byte `0` for added or
+/// `1` for removed farm, followed by the farm as little-endian bytes.
+impl<T: PrimInt> Hash for FarmChange<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let (tag, farm) = match self {
+            FarmChange::Added(farm) => (0u8, farm),
+            FarmChange::Removed(farm) => (1u8, farm),
+        };
+
+        state.write_u8(tag);
+        farm.hash_stable(state);
+    }
+}
+
+/// Hook receiving the food delivery system events, all methods do nothing by default.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use std::sync::Mutex;
+/// use xor_distance_exercise::hooks::{DeliveryHook, FarmChange};
+///
+/// struct FarmLog(Mutex<Vec<FarmChange<u64>>>);
+///
+/// impl DeliveryHook<u64> for FarmLog {
+///     fn on_farm_change(&self, change: &FarmChange<u64>) {
+///         self.0.lock().unwrap().push(*change);
+///     }
+/// }
+/// ```
+pub trait DeliveryHook<T>: Send + Sync {
+    /// Invoked after an order was assigned to a farm.
+    fn on_assignment(&self, _assignment: &Assignment<T>) {}
+
+    /// Invoked after an order assignment was cancelled.
+    fn on_cancellation(&self, _assignment: &Assignment<T>) {}
+
+    /// Invoked after a farm was added or removed.
+    fn on_farm_change(&self, _change: &FarmChange<T>) {}
+}
+
+/// Registry of hooks, invoked in their registration order.
+pub struct Hooks<T> {
+    hooks: Vec<Box<dyn DeliveryHook<T>>>,
+}
+
+impl<T: 'static> Hooks<T> {
+    /// Create a new empty registry.
+    pub fn new() -> Self {
+        Hooks { hooks: Vec::new() }
+    }
+
+    /// Return number of registered hooks.
+    pub fn len(&self) -> usize {
+        self.hooks.len()
+    }
+
+    /// Return whether there are no hooks registered.
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Register a hook.
+    pub fn add<H: DeliveryHook<T> + 'static>(&mut self, hook: H) {
+        self.hooks.push(Box::new(hook));
+    }
+
+    /// Register a closure invoked after an order was assigned to a farm.
+    pub fn on_assignment<F>(&mut self, hook: F)
+    where
+        F: Fn(&Assignment<T>) + Send + Sync + 'static,
+    {
+        self.add(AssignmentHook(hook));
+    }
+
+    /// Register a closure invoked after an order assignment was cancelled.
+    pub fn on_cancellation<F>(&mut self, hook: F)
+    where
+        F: Fn(&Assignment<T>) + Send + Sync + 'static,
+    {
+        self.add(CancellationHook(hook));
+    }
+
+    /// Register a closure invoked after a farm was added or removed.
+    pub fn on_farm_change<F>(&mut self, hook: F)
+    where
+        F: Fn(&FarmChange<T>) + Send + Sync + 'static,
+    {
+        self.add(FarmChangeHook(hook));
+    }
+
+    /// Notify all hooks about the assignment.
+    pub fn notify_assignment(&self, assignment: &Assignment<T>) {
+        for hook in self.hooks.iter() {
+            hook.on_assignment(assignment);
+        }
+    }
+
+    /// Notify all hooks about the cancellation.
+    pub fn notify_cancellation(&self, assignment: &Assignment<T>) {
+        for hook in self.hooks.iter() {
+            hook.on_cancellation(assignment);
+        }
+    }
+
+    /// Notify all hooks about the farm change.
+    pub fn notify_farm_change(&self, change: &FarmChange<T>) {
+        for hook in self.hooks.iter() {
+            hook.on_farm_change(change);
+        }
+    }
+}
+
+impl<T: 'static> Default for Hooks<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Adapter of an assignment closure to the `DeliveryHook` trait.
+struct AssignmentHook<F>(F);
+
+impl<T, F: Fn(&Assignment<T>) + Send + Sync> DeliveryHook<T> for AssignmentHook<F> {
+    fn on_assignment(&self, assignment: &Assignment<T>) {
+        (self.0)(assignment);
+    }
+}
+
+/// Adapter of a cancellation closure to the `DeliveryHook` trait.
+struct CancellationHook<F>(F);
+
+impl<T, F: Fn(&Assignment<T>) + Send + Sync> DeliveryHook<T> for CancellationHook<F> {
+    fn on_cancellation(&self, assignment: &Assignment<T>) {
+        (self.0)(assignment);
+    }
+}
+
+/// Adapter of a farm change closure to the `DeliveryHook` trait.
+struct FarmChangeHook<F>(F);
+
+impl<T, F: Fn(&FarmChange<T>) + Send + Sync> DeliveryHook<T> for FarmChangeHook<F> {
+    fn on_farm_change(&self, change: &FarmChange<T>) {
+        (self.0)(change);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Assignment, DeliveryHook, FarmChange, Hooks};
+    use crate::tiers::ServiceTier;
+    use std::sync::{Arc, Mutex};
+
+    /// Hook recording all events as strings.
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl DeliveryHook<u64> for Recorder {
+        fn on_assignment(&self, assignment: &Assignment<u64>) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("assigned {:?}", assignment));
+        }
+
+        fn on_farm_change(&self, change: &FarmChange<u64>) {
+            self.0.lock().unwrap().push(format!("changed {:?}", change));
+        }
+    }
+
+    #[test]
+    fn notify_in_registration_order() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut hooks = Hooks::new();
+
+        let closure_events = events.clone();
+        hooks.on_cancellation(move |assignment: &Assignment<u64>| {
+            closure_events
+                .lock()
+                .unwrap()
+                .push(format!("cancelled {}", assignment.farm));
+        });
+        hooks.add(Recorder(events.clone()));
+
+        assert_eq!(2, hooks.len());
+
+        let assignment = Assignment {
+            position: 3,
+            farm: 2,
+            tier: None,
+        };
+        hooks.notify_assignment(&assignment);
+        hooks.notify_cancellation(&assignment);
+        hooks.notify_farm_change(&FarmChange::Removed(2));
+
+        assert_eq!(
+            vec![
+                "assigned Assignment { position: 3, farm: 2, tier: None }".to_string(),
+                "cancelled 2".to_string(),
+                "changed Removed(2)".to_string(),
+            ],
+            *events.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn stable_hash() {
+        use std::hash::{Hash, Hasher};
+
+        #[derive(Default)]
+        struct Bytes(Vec<u8>);
+
+        impl Hasher for Bytes {
+            fn finish(&self) -> u64 {
+                0
+            }
+
+            fn write(&mut self, bytes: &[u8]) {
+                self.0.extend_from_slice(bytes);
+            }
+        }
+
+        let mut hasher = Bytes::default();
+        Assignment {
+            position: 0x0102u16,
+            farm: 3,
+            tier: Some(ServiceTier::Standard),
+        }
+        .hash(&mut hasher);
+        FarmChange::Removed(4u16).hash(&mut hasher);
+
+        assert_eq!(vec![2, 1, 3, 0, 2, 1, 4, 0], hasher.0);
+    }
+}
@@ -0,0 +1,407 @@
+//! Xor distance calculations for keys of arbitrary width.
+//!
+//! DHT node IDs are typically 160-bit or 256-bit byte arrays, wider than any native integer. The
+//! `XorKey` trait covers the few operations the closest points and the reverse solver need, and
+//! is implemented for the unsigned integers, for big-endian byte arrays `[u8; N]` and for wide
+//! `(hi, lo)` pairs of unsigned integers (e.g. `(u128, u128)` as a 256-bit key).
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::key::KeyDistance;
+//!
+//! let mut ids = [[0u8; 20]; 4];
+//! ids[1][0] = 0x80;
+//! ids[2][19] = 0x01;
+//! ids[3][10] = 0x10;
+//!
+//! let key_distance = KeyDistance::new(ids.to_vec());
+//!
+//! let closest = key_distance.closest(ids[2], 2);
+//! assert_eq!(vec![ids[2], ids[0]], closest);
+//!
+//! let position = key_distance.reverse_closest(&closest).unwrap();
+//! assert_eq!(closest, key_distance.closest(position, 2));
+//! ```
+
+use crate::bitops::{BitOps, WideBitOps};
+use crate::solver::ConstraintSet;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use num_traits::Unsigned;
+
+/// Key of a fixed bit width supporting XOR distance calculations.
+///
+/// The `Ord` implementation must order keys as unsigned numbers, so XOR distances of keys compare
+/// as keys themselves.
+pub trait XorKey: Copy + Ord {
+    /// Number of bits of the key.
+    const BIT_SIZE: usize;
+
+    /// Return the key with all bits set to "0".
+    fn zero() -> Self;
+
+    /// Return bitwise XOR of the keys, their XOR distance.
+    fn xor(&self, other: &Self) -> Self;
+
+    /// Return number of leading "0" bits, starting at the most significant one.
+    fn leading_zeros(&self) -> usize;
+
+    /// Return whether the bit is set, indexed from zero at the least significant bit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_index` is not less than `BIT_SIZE`.
+    fn is_bit_set(&self, bit_index: usize) -> bool;
+
+    /// Set the bit to "1", indexed from zero at the least significant bit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_index` is not less than `BIT_SIZE`.
+    fn set_bit(&mut self, bit_index: usize);
+}
+
+macro_rules! xor_key {
+    ($($unsigned:ty),*) => {
+        $(
+            impl XorKey for $unsigned {
+                const BIT_SIZE: usize = <$unsigned>::BITS as usize;
+
+                #[inline]
+                fn zero() -> Self {
+                    0
+                }
+
+                #[inline]
+                fn xor(&self, other: &Self) -> Self {
+                    self ^ other
+                }
+
+                #[inline]
+                fn leading_zeros(&self) -> usize {
+                    <$unsigned>::leading_zeros(*self) as usize
+                }
+
+                #[inline]
+                fn is_bit_set(&self, bit_index: usize) -> bool {
+                    check_bit_index::<Self>(bit_index);
+                    BitOps::is_bit_set(self, bit_index)
+                }
+
+                #[inline]
+                fn set_bit(&mut self, bit_index: usize) {
+                    check_bit_index::<Self>(bit_index);
+                    BitOps::set_bit(self, bit_index)
+                }
+            }
+        )*
+    };
+}
+
+xor_key!(u8, u16, u32, u64, u128, usize);
+
+/// Big-endian bytes, the first byte holds the most significant bits.
+impl<const N: usize> XorKey for [u8; N] {
+    const BIT_SIZE: usize = 8 * N;
+
+    #[inline]
+    fn zero() -> Self {
+        [0; N]
+    }
+
+    #[inline]
+    fn xor(&self, other: &Self) -> Self {
+        let mut xor = *self;
+
+        for (byte, other) in xor.iter_mut().zip(other.iter()) {
+            *byte ^= other;
+        }
+
+        xor
+    }
+
+    #[inline]
+    fn leading_zeros(&self) -> usize {
+        match self.iter().position(|byte| *byte != 0) {
+            Some(position) => 8 * position + self[position].leading_zeros() as usize,
+            None => Self::BIT_SIZE,
+        }
+    }
+
+    #[inline]
+    fn is_bit_set(&self, bit_index: usize) -> bool {
+        check_bit_index::<Self>(bit_index);
+
+        self[N - 1 - bit_index / 8] & (1 << (bit_index % 8)) != 0
+    }
+
+    #[inline]
+    fn set_bit(&mut self, bit_index: usize) {
+        check_bit_index::<Self>(bit_index);
+
+        self[N - 1 - bit_index / 8] |= 1 << (bit_index % 8);
+    }
+}
+
+/// Wide `(hi, lo)` pair, `hi` holds the most significant bits.
+impl<T: BitOps + Unsigned> XorKey for (T, T) {
+    const BIT_SIZE: usize = 16 * core::mem::size_of::<T>();
+
+    #[inline]
+    fn zero() -> Self {
+        (T::zero(), T::zero())
+    }
+
+    #[inline]
+    fn xor(&self, other: &Self) -> Self {
+        self.wide_xor(other)
+    }
+
+    #[inline]
+    fn leading_zeros(&self) -> usize {
+        self.wide_leading_zeros() as usize
+    }
+
+    #[inline]
+    fn is_bit_set(&self, bit_index: usize) -> bool {
+        self.wide_is_bit_set(bit_index)
+    }
+
+    #[inline]
+    fn set_bit(&mut self, bit_index: usize) {
+        self.wide_set_bit(bit_index)
+    }
+}
+
+fn check_bit_index<K: XorKey>(bit_index: usize) {
+    assert!(bit_index < K::BIT_SIZE, "Bit index is out of range!");
+}
+
+/// Xor distance structure holding set of `XorKey` points.
+///
+/// It keeps no index, queries scan all points. The reverse queries share the validation and the
+/// `ConstraintSet` solver with `XorDistance` keeping duplicate points.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyDistance<K> {
+    points: Vec<K>,
+}
+
+impl<K: XorKey> KeyDistance<K> {
+    /// Create a new structure holding the points.
+    pub fn new(points: Vec<K>) -> Self {
+        KeyDistance { points }
+    }
+
+    /// Return all points in their insertion order, duplicates included.
+    pub fn points(&self) -> &[K] {
+        &self.points
+    }
+
+    /// Return number of points, duplicates included.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Return whether there are no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Add a new point, duplicates are allowed.
+    pub fn add_point(&mut self, point: K) {
+        self.points.push(point);
+    }
+
+    /// Remove one occurrence of the point.
+    ///
+    /// Returns `true` if the point was present, `false` otherwise.
+    pub fn remove_point(&mut self, point: K) -> bool {
+        match self.points.iter().position(|p| *p == point) {
+            Some(position) => {
+                self.points.remove(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return up to requested count of closest points to the provided `x`, ordered from the closest
+    /// to the n-th closest, where `n` is the count.
+    pub fn closest(&self, x: K, count: usize) -> Vec<K> {
+        let mut closest = self.points.clone();
+
+        // Only the selected points are sorted.
+        if count < closest.len() {
+            if count == 0 {
+                return Vec::new();
+            }
+
+            closest.select_nth_unstable_by_key(count - 1, |point| point.xor(&x));
+            closest.truncate(count);
+        }
+
+        closest.sort_unstable_by_key(|point| point.xor(&x));
+
+        closest
+    }
+
+    /// Return the `n`-th closest point to the provided `x`, indexed from zero, `None` if there are
+    /// not more than `n` points.
+    pub fn nth_closest(&self, x: K, n: usize) -> Option<K> {
+        if n >= self.points.len() {
+            return None;
+        }
+
+        let mut points = self.points.clone();
+        let (_, nth, _) = points.select_nth_unstable_by_key(n, |point| point.xor(&x));
+
+        Some(*nth)
+    }
+
+    /// Return a `Some(x)` such that `self.closest(x)` equals closest_points and return None in case
+    /// such a `x` does not exists.
+    pub fn reverse_closest(&self, closest_points: &[K]) -> Option<K> {
+        if !self.lists_duplicates(closest_points) {
+            return None;
+        }
+
+        let closest_inequalities = closest_points.windows(2).map(|pair| (pair[0], pair[1]));
+        let further_inequalities = closest_points.last().into_iter().flat_map(|a| {
+            self.points
+                .iter()
+                .filter(move |b| !closest_points.contains(b))
+                .map(move |b| (*a, *b))
+        });
+
+        // Equal neighbours in the closest points restrict nothing.
+        let inequalities = closest_inequalities
+            .chain(further_inequalities)
+            .filter(|(a, b)| a != b);
+
+        let mut constraints = ConstraintSet::for_keys();
+        constraints.ingest(inequalities).ok()?;
+
+        // The bits are of the key size.
+        Some(constraints.bits().form_zero_padded_key::<K>().unwrap())
+    }
+
+    /// Return whether every point of the closest points is listed as many times as it is present,
+    /// the last closest point at most as many times, as the count may cut its duplicates.
+    fn lists_duplicates(&self, closest_points: &[K]) -> bool {
+        let mut listed: BTreeMap<K, (usize, usize)> = BTreeMap::new();
+
+        for point in closest_points.iter() {
+            listed.entry(*point).or_insert((0, 0)).0 += 1;
+        }
+
+        for point in self.points.iter() {
+            if let Some((_, present)) = listed.get_mut(point) {
+                *present += 1;
+            }
+        }
+
+        let last = closest_points.last();
+
+        listed.iter().all(|(point, (count, present))| {
+            if Some(point) == last {
+                count <= present
+            } else {
+                count == present
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyDistance, XorKey};
+    use crate::xor_distance::XorDistance;
+
+    /// Return the 160-bit big-endian key of the number.
+    fn id(number: u64) -> [u8; 20] {
+        let mut id = [0; 20];
+        id[12..].copy_from_slice(&number.to_be_bytes());
+        id
+    }
+
+    #[test]
+    fn byte_array_keys() {
+        let mut key = [0u8; 3];
+
+        assert_eq!(24, <[u8; 3]>::BIT_SIZE);
+        assert_eq!(24, key.leading_zeros());
+
+        key.set_bit(0);
+        key.set_bit(17);
+        assert_eq!([0b10, 0, 1], key);
+        assert!(key.is_bit_set(17));
+        assert!(!key.is_bit_set(16));
+        assert_eq!(6, key.leading_zeros());
+        assert_eq!([0b10, 0, 0], key.xor(&[0, 0, 1]));
+
+        let wide = (0u128, 1u128);
+        assert_eq!(256, <(u128, u128)>::BIT_SIZE);
+        assert_eq!(255, wide.leading_zeros());
+    }
+
+    #[test]
+    #[should_panic(expected = "Bit index is out of range!")]
+    fn bit_index_out_of_range() {
+        [0u8; 2].is_bit_set(16);
+    }
+
+    #[test]
+    fn same_results_as_xor_distance() {
+        let numbers: Vec<u64> = vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ];
+        let xor_distance = XorDistance::new(numbers.clone());
+        let ids = KeyDistance::new(numbers.iter().map(|number| id(*number)).collect());
+        let wide = KeyDistance::new(numbers.iter().map(|number| (0u64, *number)).collect());
+
+        for x in [0, 10, 200, 300, 445, u64::MAX].iter() {
+            for count in [1, 5, 19].iter() {
+                let closest = xor_distance.closest(*x, *count);
+                let closest_ids: Vec<[u8; 20]> = closest.iter().map(|number| id(*number)).collect();
+
+                assert_eq!(closest_ids, ids.closest(id(*x), *count));
+                assert_eq!(
+                    closest_ids.get(*count - 1).copied(),
+                    ids.nth_closest(id(*x), *count - 1)
+                );
+
+                let reversed = xor_distance.reverse_closest(&closest).unwrap();
+                assert_eq!(Some(id(reversed)), ids.reverse_closest(&closest_ids));
+                assert_eq!(
+                    Some((0, reversed)),
+                    wide.reverse_closest(&wide.closest((0, *x), *count))
+                );
+            }
+        }
+
+        assert_eq!(None, ids.reverse_closest(&[id(0), id(445), id(1)]));
+    }
+
+    #[test]
+    fn reverse_closest_validated() {
+        let keys = KeyDistance::new(vec![[0u8], [1], [1], [4]]);
+
+        // Missing points are never the closest ones.
+        assert_eq!(
+            None,
+            KeyDistance::new(vec![[0u8], [1]]).reverse_closest(&[[5]])
+        );
+
+        // Duplicates are listed as many times as present, the last one may be cut.
+        assert_eq!(None, keys.reverse_closest(&[[1], [0]]));
+        assert_eq!(None, keys.reverse_closest(&[[1], [1], [1]]));
+
+        for closest in [vec![[1u8]], vec![[1], [1]], vec![[1], [1], [0]]].iter() {
+            let x = keys.reverse_closest(closest).unwrap();
+            assert_eq!(*closest, keys.closest(x, closest.len()));
+        }
+    }
+}
@@ -0,0 +1,259 @@
+//! Comparison of two farm layouts from the customers' point of view.
+//!
+//! Every customer is assigned to the closest farm of each layout, so a proposed farm network
+//! change can be evaluated by the distances to the assigned farms and by the load balance.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+//! use xor_distance_exercise::layout::compare_layouts;
+//!
+//! let current: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 64]);
+//! let proposed: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 16, 64]);
+//!
+//! let comparison = compare_layouts(&current, &proposed, &[1, 17, 18, 65]);
+//!
+//! // Customers 17 and 18 got a closer farm and the load is better balanced.
+//! assert!(comparison.mean_distance_b < comparison.mean_distance_a);
+//! assert!(comparison.load_b.max_load < comparison.load_a.max_load);
+//! ```
+
+use crate::delivery_system::FoodDeliverySystem;
+use num_traits::{PrimInt, Unsigned};
+use std::collections::BTreeMap;
+
+/// Customer's assigned farms and distances to them in both layouts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CustomerDelta<T> {
+    /// Customer's position.
+    pub position: T,
+    /// Farm assigned in the first layout, `None` if it has no farms.
+    pub farm_a: Option<T>,
+    /// Farm assigned in the second layout, `None` if it has no farms.
+    pub farm_b: Option<T>,
+}
+
+impl<T: PrimInt + Unsigned> CustomerDelta<T> {
+    /// Return XOR distance to the farm assigned in the first layout.
+    pub fn distance_a(&self) -> Option<T> {
+        self.farm_a.map(|farm| farm ^ self.position)
+    }
+
+    /// Return XOR distance to the farm assigned in the second layout.
+    pub fn distance_b(&self) -> Option<T> {
+        self.farm_b.map(|farm| farm ^ self.position)
+    }
+
+    /// Return the distance change from the first to the second layout, negative if the second
+    /// layout brings the customer closer to a farm.
+    ///
+    /// Returns `None` if any of the layouts has no farms.
+    pub fn distance_delta(&self) -> Option<f64> {
+        let distance_a = self.distance_a()?.to_f64()?;
+        let distance_b = self.distance_b()?.to_f64()?;
+
+        Some(distance_b - distance_a)
+    }
+}
+
+/// Load balance statistics of customers assigned to farms of a layout.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LoadBalance {
+    /// Number of farms in the layout.
+    pub farms: usize,
+    /// Number of farms with at least one customer assigned.
+    pub used_farms: usize,
+    /// The highest number of customers assigned to one farm.
+    pub max_load: usize,
+    /// Mean number of customers per farm.
+    pub mean_load: f64,
+    /// Standard deviation of the number of customers per farm, unused farms included.
+    pub std_dev: f64,
+}
+
+impl LoadBalance {
+    fn from_loads(farms: usize, loads: &BTreeMap<u128, usize>) -> Self {
+        let customers: usize = loads.values().sum();
+        let mean_load = if farms == 0 {
+            0.0
+        } else {
+            customers as f64 / farms as f64
+        };
+
+        // Unused farms deviate from the mean by the mean itself.
+        let unused = (farms - loads.len()) as f64;
+        let squares: f64 = loads
+            .values()
+            .map(|load| (*load as f64 - mean_load).powi(2))
+            .sum::<f64>()
+            + unused * mean_load.powi(2);
+        let std_dev = if farms == 0 {
+            0.0
+        } else {
+            (squares / farms as f64).sqrt()
+        };
+
+        LoadBalance {
+            farms,
+            used_farms: loads.len(),
+            max_load: loads.values().copied().max().unwrap_or(0),
+            mean_load,
+            std_dev,
+        }
+    }
+}
+
+/// Per-customer and aggregate comparison of two layouts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LayoutComparison<T> {
+    /// Assigned farms of every customer, in the order of the provided customers.
+    pub customers: Vec<CustomerDelta<T>>,
+    /// Mean distance to the assigned farm in the first layout.
+    pub mean_distance_a: f64,
+    /// Mean distance to the assigned farm in the second layout.
+    pub mean_distance_b: f64,
+    /// Load balance of the first layout.
+    pub load_a: LoadBalance,
+    /// Load balance of the second layout.
+    pub load_b: LoadBalance,
+}
+
+/// Compare two farm layouts by assigning every customer to the closest farm of each of them.
+///
+/// Customers are not counted in the mean distance of a layout without farms.
+pub fn compare_layouts<T: PrimInt + Unsigned + 'static>(
+    a: &FoodDeliverySystem<T>,
+    b: &FoodDeliverySystem<T>,
+    customers: &[T],
+) -> LayoutComparison<T> {
+    let deltas: Vec<CustomerDelta<T>> = customers
+        .iter()
+        .map(|position| CustomerDelta {
+            position: *position,
+            farm_a: a.xor_distance().nth_closest(*position, 0),
+            farm_b: b.xor_distance().nth_closest(*position, 0),
+        })
+        .collect();
+
+    let mean_distance_a = mean(deltas.iter().filter_map(|delta| delta.distance_a()));
+    let mean_distance_b = mean(deltas.iter().filter_map(|delta| delta.distance_b()));
+
+    let load_a = load_balance(a, deltas.iter().filter_map(|delta| delta.farm_a));
+    let load_b = load_balance(b, deltas.iter().filter_map(|delta| delta.farm_b));
+
+    LayoutComparison {
+        customers: deltas,
+        mean_distance_a,
+        mean_distance_b,
+        load_a,
+        load_b,
+    }
+}
+
+/// Return mean of the distances, zero if there are none.
+fn mean<T: PrimInt, I: Iterator<Item = T>>(distances: I) -> f64 {
+    let (sum, count) = distances.fold((0.0, 0), |(sum, count), distance| {
+        // Any primitive integer is representable in `f64`, even if rounded.
+        (sum + distance.to_f64().unwrap(), count + 1)
+    });
+
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f64
+    }
+}
+
+fn load_balance<T, I>(layout: &FoodDeliverySystem<T>, assigned_farms: I) -> LoadBalance
+where
+    T: PrimInt + Unsigned + 'static,
+    I: Iterator<Item = T>,
+{
+    let mut loads = BTreeMap::new();
+
+    for farm in assigned_farms {
+        // Unsigned integer types always fit into `u128`.
+        *loads.entry(farm.to_u128().unwrap()).or_insert(0) += 1;
+    }
+
+    LoadBalance::from_loads(layout.xor_distance().len(), &loads)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{compare_layouts, CustomerDelta};
+    use crate::delivery_system::FoodDeliverySystem;
+
+    #[test]
+    fn compare_customers() {
+        let a: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 64]);
+        let b: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 16, 64]);
+
+        let comparison = compare_layouts(&a, &b, &[1, 17, 65]);
+
+        assert_eq!(
+            vec![
+                CustomerDelta {
+                    position: 1,
+                    farm_a: Some(0),
+                    farm_b: Some(0),
+                },
+                CustomerDelta {
+                    position: 17,
+                    farm_a: Some(0),
+                    farm_b: Some(16),
+                },
+                CustomerDelta {
+                    position: 65,
+                    farm_a: Some(64),
+                    farm_b: Some(64),
+                },
+            ],
+            comparison.customers
+        );
+
+        assert_eq!(Some(0.0), comparison.customers[0].distance_delta());
+        assert_eq!(Some(-16.0), comparison.customers[1].distance_delta());
+        assert_eq!((1.0 + 17.0 + 1.0) / 3.0, comparison.mean_distance_a);
+        assert_eq!(1.0, comparison.mean_distance_b);
+    }
+
+    #[test]
+    fn compare_load_balance() {
+        let a: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 64]);
+        let b: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 16, 64, 128]);
+
+        let comparison = compare_layouts(&a, &b, &[1, 2, 17, 65]);
+
+        assert_eq!(2, comparison.load_a.farms);
+        assert_eq!(2, comparison.load_a.used_farms);
+        assert_eq!(3, comparison.load_a.max_load);
+        assert_eq!(2.0, comparison.load_a.mean_load);
+        assert_eq!(1.0, comparison.load_a.std_dev);
+
+        // Farm 128 is unused.
+        assert_eq!(4, comparison.load_b.farms);
+        assert_eq!(3, comparison.load_b.used_farms);
+        assert_eq!(2, comparison.load_b.max_load);
+        assert_eq!(1.0, comparison.load_b.mean_load);
+        assert_eq!((2.0f64 / 4.0).sqrt(), comparison.load_b.std_dev);
+    }
+
+    #[test]
+    fn compare_empty_layout() {
+        let a: FoodDeliverySystem<u64> = FoodDeliverySystem::new(Vec::new());
+        let b: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![8]);
+
+        let comparison = compare_layouts(&a, &b, &[1, 2]);
+
+        assert_eq!(None, comparison.customers[0].distance_delta());
+        assert_eq!(0.0, comparison.mean_distance_a);
+        assert_eq!(0, comparison.load_a.max_load);
+        assert_eq!(0.0, comparison.load_a.std_dev);
+        assert_eq!(2, comparison.load_b.max_load);
+        assert_eq!(0.0, comparison.load_b.std_dev);
+    }
+}
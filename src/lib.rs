@@ -1,7 +1,72 @@
+//! Xor distances exercise including xor and bit operations.
+//!
+//! The `bitops`, `bits`, `const_bits`, `metric` and `xor_distance` modules, with the modules they
+//! build on, are available without the `std` feature, in `#![no_std]` environments with an
+//! allocator. All other modules require the `std` feature.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 extern crate num_traits;
+#[cfg(feature = "rand")]
 extern crate rand;
 
+#[cfg(feature = "rand")]
+pub mod analytics;
+#[cfg(feature = "std")]
+pub mod batching;
+#[cfg(feature = "std")]
+pub mod bit_field;
 pub mod bitops;
 pub mod bits;
+#[cfg(feature = "std")]
+pub mod buckets;
+#[cfg(feature = "rand")]
+pub mod conformance;
+pub mod const_bits;
+#[cfg(feature = "std")]
 pub mod delivery_system;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+pub mod forecast;
+#[cfg(feature = "rand")]
+pub mod generator;
+#[cfg(feature = "std")]
+pub mod geohash;
+#[cfg(feature = "std")]
+pub mod hooks;
+pub mod key;
+#[cfg(feature = "std")]
+pub mod layout;
+#[cfg(feature = "std")]
+pub mod loader;
+pub mod metric;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod query_log;
+#[cfg(feature = "rand")]
+pub mod reservoir;
+#[cfg(feature = "std")]
+pub mod routing;
+#[cfg(feature = "std")]
+pub mod signed;
+#[cfg(feature = "std")]
+pub mod simulation;
+pub mod solver;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod territory;
+#[cfg(feature = "testing")]
+pub mod testing;
+#[cfg(feature = "std")]
+pub mod tiers;
+pub mod trie;
 pub mod xor_distance;
+#[cfg(feature = "std")]
+pub mod xor_distance_array;
@@ -0,0 +1,394 @@
+//! Loading point sets from CSV, JSON arrays or newline-delimited text.
+//!
+//! Points are decimal numbers of the point type. Errors report the line of the malformed input,
+//! indexed from one, so large farm datasets can be kept in files instead of the source code.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::error::LoadError;
+//! use xor_distance_exercise::loader::{read_points, PointsFormat};
+//! use xor_distance_exercise::xor_distance::XorDistance;
+//!
+//! let csv = "farm,name\n0,Oak Farm\n8,Green Acres\n12,Hillside\n";
+//! let xor_distance: XorDistance<u64> =
+//!     XorDistance::from_reader(csv.as_bytes(), PointsFormat::Csv).unwrap();
+//!
+//! assert_eq!(vec![8, 12], xor_distance.closest(10, 2));
+//!
+//! let json = "[0, 8,\n 300]";
+//!
+//! match read_points::<u8, _>(json.as_bytes(), PointsFormat::Json) {
+//!     Err(LoadError::InvalidPoint { line, value }) => assert_eq!((2, "300"), (line, &*value)),
+//!     _ => unreachable!(),
+//! }
+//! ```
+
+use crate::bitops::BitOps;
+use crate::delivery_system::FoodDeliverySystem;
+use crate::error::LoadError;
+use crate::xor_distance::XorDistance;
+use num_traits::{PrimInt, Unsigned};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// Format of a points input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointsFormat {
+    /// Comma separated values with the point in the first column of every row. The first row is
+    /// a header and skipped if its first column doesn't start with a digit.
+    Csv,
+    /// JSON array of the points, e.g. `[0, 8, 12]`.
+    Json,
+    /// One point per line, empty lines and lines starting with `#` are skipped.
+    Lines,
+}
+
+impl PointsFormat {
+    /// Return the format of the file by its extension, `csv` or `json`, `Lines` for any other
+    /// one.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::loader::PointsFormat;
+    ///
+    /// assert_eq!(PointsFormat::Csv, PointsFormat::from_path("farms.CSV"));
+    /// assert_eq!(PointsFormat::Json, PointsFormat::from_path("data/farms.json"));
+    /// assert_eq!(PointsFormat::Lines, PointsFormat::from_path("farms.txt"));
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        let extension = path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("csv") => PointsFormat::Csv,
+            Some("json") => PointsFormat::Json,
+            _ => PointsFormat::Lines,
+        }
+    }
+}
+
+/// Read points of the format from the reader, in their order.
+///
+/// Returns `Err(LoadError)` if reading fails, the input is not of the format or any point is not
+/// a number of the point type.
+pub fn read_points<T, R>(mut reader: R, format: PointsFormat) -> Result<Vec<T>, LoadError>
+where
+    T: PrimInt + Unsigned,
+    R: BufRead,
+{
+    match format {
+        PointsFormat::Csv => read_csv(reader),
+        PointsFormat::Json => {
+            let mut input = String::new();
+            reader.read_to_string(&mut input)?;
+
+            parse_json(&input)
+        }
+        PointsFormat::Lines => read_lines(reader),
+    }
+}
+
+/// Load points from the file, of the format given by its extension, see
+/// `PointsFormat::from_path`.
+///
+/// Returns `Err(LoadError)` if the file can not be read or its content is malformed.
+pub fn load_points<T, P>(path: P) -> Result<Vec<T>, LoadError>
+where
+    T: PrimInt + Unsigned,
+    P: AsRef<Path>,
+{
+    let format = PointsFormat::from_path(&path);
+
+    read_points(BufReader::new(File::open(path)?), format)
+}
+
+impl<T: PrimInt + BitOps + Unsigned> XorDistance<T> {
+    /// Create a new structure of the points read from the reader, see `read_points`.
+    ///
+    /// Returns `Err(LoadError)` if reading fails or the input is malformed.
+    pub fn from_reader<R: BufRead>(reader: R, format: PointsFormat) -> Result<Self, LoadError> {
+        Ok(XorDistance::new(read_points(reader, format)?))
+    }
+}
+
+impl<T: PrimInt + Unsigned + 'static> FoodDeliverySystem<T> {
+    /// Create a new Food Delivery System of the farms loaded from the file, see `load_points`.
+    ///
+    /// Returns `Err(LoadError)` if the file can not be read or its content is malformed.
+    pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Self, LoadError> {
+        Ok(FoodDeliverySystem::new(load_points(path)?))
+    }
+}
+
+/// Parse the point on the line, indexed from one.
+fn parse_point<T: PrimInt + Unsigned>(line: usize, value: &str) -> Result<T, LoadError> {
+    T::from_str_radix(value, 10).map_err(|_| LoadError::InvalidPoint {
+        line,
+        value: value.to_string(),
+    })
+}
+
+fn read_lines<T: PrimInt + Unsigned, R: BufRead>(reader: R) -> Result<Vec<T>, LoadError> {
+    let mut points = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let value = line.trim();
+
+        if value.is_empty() || value.starts_with('#') {
+            continue;
+        }
+
+        points.push(parse_point(index + 1, value)?);
+    }
+
+    Ok(points)
+}
+
+fn read_csv<T: PrimInt + Unsigned, R: BufRead>(reader: R) -> Result<Vec<T>, LoadError> {
+    let mut points = Vec::new();
+    let mut first_row = true;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        // The first column, optionally quoted.
+        let column = line.split(',').next().unwrap_or("").trim();
+        let value = column
+            .strip_prefix('"')
+            .and_then(|column| column.strip_suffix('"'))
+            .unwrap_or(column);
+
+        // Only the first non-empty row can be a header.
+        let is_header = first_row && !value.starts_with(|c: char| c.is_ascii_digit());
+        first_row = false;
+
+        if !is_header {
+            points.push(parse_point(index + 1, value)?);
+        }
+    }
+
+    Ok(points)
+}
+
+fn parse_json<T: PrimInt + Unsigned>(input: &str) -> Result<Vec<T>, LoadError> {
+    let mut tokens = Tokens { input, line: 1 };
+    let mut points = Vec::new();
+
+    tokens.expect('[', "`[`")?;
+
+    if !tokens.eat(']') {
+        loop {
+            let (line, value) = tokens.value()?;
+            points.push(parse_point(line, value)?);
+
+            if tokens.eat(']') {
+                break;
+            }
+
+            tokens.expect(',', "`,` or `]`")?;
+        }
+    }
+
+    tokens.skip_whitespace();
+
+    if !tokens.input.is_empty() {
+        return Err(LoadError::Syntax {
+            line: tokens.line,
+            expected: "end of the input",
+        });
+    }
+
+    Ok(points)
+}
+
+/// Remaining input of a JSON array and its current line.
+struct Tokens<'a> {
+    input: &'a str,
+    line: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn skip_whitespace(&mut self) {
+        let trimmed = self.input.trim_start();
+
+        self.line += self.input[..self.input.len() - trimmed.len()]
+            .matches('\n')
+            .count();
+        self.input = trimmed;
+    }
+
+    /// Consume the character if it is the next one, return whether it was consumed.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+
+        match self.input.strip_prefix(c) {
+            Some(rest) => {
+                self.input = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<(), LoadError> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(LoadError::Syntax {
+                line: self.line,
+                expected,
+            })
+        }
+    }
+
+    /// Consume the next value and return it with its line.
+    fn value(&mut self) -> Result<(usize, &'a str), LoadError> {
+        self.skip_whitespace();
+
+        let end = self
+            .input
+            .find(|c: char| c.is_whitespace() || c == ',' || c == ']')
+            .unwrap_or(self.input.len());
+
+        if end == 0 {
+            return Err(LoadError::Syntax {
+                line: self.line,
+                expected: "a point",
+            });
+        }
+
+        let (value, rest) = self.input.split_at(end);
+        self.input = rest;
+
+        Ok((self.line, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_points, read_points, PointsFormat};
+    use crate::delivery_system::FoodDeliverySystem;
+    use crate::error::LoadError;
+    use std::fs;
+
+    fn read<T: num_traits::PrimInt + num_traits::Unsigned>(
+        input: &str,
+        format: PointsFormat,
+    ) -> Result<Vec<T>, LoadError> {
+        read_points(input.as_bytes(), format)
+    }
+
+    /// Return the line and the expectation of a syntax error, the line and the value of an
+    /// invalid point error.
+    fn error_line(error: LoadError) -> (usize, String) {
+        match error {
+            LoadError::InvalidPoint { line, value } => (line, value),
+            LoadError::Syntax { line, expected } => (line, expected.to_string()),
+            LoadError::Io(error) => panic!("Unexpected error {}!", error),
+        }
+    }
+
+    #[test]
+    fn lines() {
+        let input = "# Farms\n0\n  8 \n\n12\r\n";
+        assert_eq!(
+            vec![0, 8, 12],
+            read::<u64>(input, PointsFormat::Lines).unwrap()
+        );
+
+        let error = read::<u8>("1\n2\n256\n", PointsFormat::Lines).unwrap_err();
+        assert_eq!("Invalid point \"256\" on line 3!", error.to_string());
+        assert_eq!((3, "256".to_string()), error_line(error));
+
+        let error = read::<u8>("1\nfarm\n", PointsFormat::Lines).unwrap_err();
+        assert_eq!((2, "farm".to_string()), error_line(error));
+
+        assert!(read::<u8>("", PointsFormat::Lines).unwrap().is_empty());
+    }
+
+    #[test]
+    fn csv() {
+        let input = "farm,name\n0,Oak Farm\n\"8\",Green Acres\n\n12\n";
+        assert_eq!(
+            vec![0, 8, 12],
+            read::<u64>(input, PointsFormat::Csv).unwrap()
+        );
+
+        // No header row.
+        assert_eq!(
+            vec![4, 5],
+            read::<u64>("4,a\n5,b", PointsFormat::Csv).unwrap()
+        );
+
+        // Only the first row can be a header.
+        let error = read::<u64>("farm\n1\nfarm\n", PointsFormat::Csv).unwrap_err();
+        assert_eq!((3, "farm".to_string()), error_line(error));
+        let error = read::<u64>("farm\nOak\n", PointsFormat::Csv).unwrap_err();
+        assert_eq!((2, "Oak".to_string()), error_line(error));
+        let error = read::<u64>("\nfarm\n\nOak\n", PointsFormat::Csv).unwrap_err();
+        assert_eq!((4, "Oak".to_string()), error_line(error));
+
+        let error = read::<u64>("1\n,2\n", PointsFormat::Csv).unwrap_err();
+        assert_eq!((2, "".to_string()), error_line(error));
+    }
+
+    #[test]
+    fn json() {
+        let input = " [0,\n8 , 12\n]\n";
+        assert_eq!(
+            vec![0, 8, 12],
+            read::<u64>(input, PointsFormat::Json).unwrap()
+        );
+        assert!(read::<u64>("[ ]", PointsFormat::Json).unwrap().is_empty());
+
+        let cases = [
+            ("", (1, "`[`")),
+            ("0, 1", (1, "`[`")),
+            ("[0,\n1", (2, "`,` or `]`")),
+            ("[0,\n\n]", (3, "a point")),
+            ("[0 1]", (1, "`,` or `]`")),
+            ("[0]\n[1]", (2, "end of the input")),
+        ];
+
+        for (input, (line, expected)) in cases.iter() {
+            let error = read::<u64>(input, PointsFormat::Json).unwrap_err();
+            assert_eq!((*line, expected.to_string()), error_line(error));
+        }
+
+        let error = read::<u64>("[0,\n 1.5]", PointsFormat::Json).unwrap_err();
+        assert_eq!((2, "1.5".to_string()), error_line(error));
+        let error = read::<u64>("[\"1\"]", PointsFormat::Json).unwrap_err();
+        assert_eq!((1, "\"1\"".to_string()), error_line(error));
+    }
+
+    #[test]
+    fn load_from_path() {
+        let path = std::env::temp_dir().join(format!("xor-farms-{}.json", std::process::id()));
+        fs::write(&path, "[0, 8, 12]").unwrap();
+
+        let delivery_system: FoodDeliverySystem<u64> =
+            FoodDeliverySystem::load_from_path(&path).unwrap();
+        assert_eq!(vec![8, 12], delivery_system.closest_farms(10, 2));
+
+        fs::remove_file(&path).unwrap();
+
+        match load_points::<u64, _>(&path) {
+            Err(LoadError::Io(_)) => {}
+            _ => panic!("Missing file must not be loaded!"),
+        }
+    }
+}
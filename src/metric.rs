@@ -0,0 +1,179 @@
+//! Distance metrics of closest points queries.
+//!
+//! The XOR distance is the native metric of `XorDistance`, its closest points can be reversed.
+//! Similarity hashes (e.g. SimHash) are compared by the Hamming distance instead, the number of
+//! differing bits, over the very same integer points. `XorDistance::with_metric` ranks the points
+//! by any `DistanceMetric`.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::metric::{DistanceMetric, Hamming, Xor};
+//!
+//! assert_eq!(0b0110, Xor.distance(0b1010u8, 0b1100));
+//! assert_eq!(2, Hamming.distance(0b1010u8, 0b1100));
+//! ```
+
+use crate::bitops::BitOps;
+use crate::xor_distance::XorDistance;
+use alloc::vec::Vec;
+use num_traits::{PrimInt, Unsigned};
+
+/// Distance of two points, ranking the closest points from the smallest distance.
+pub trait DistanceMetric<T> {
+    /// Distance type, ordered from the closest.
+    type Distance: Ord;
+
+    /// Return distance of the points `a` and `b`.
+    fn distance(&self, a: T, b: T) -> Self::Distance;
+}
+
+/// XOR distance `a ^ b`, the native metric of `XorDistance`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Xor;
+
+impl<T: PrimInt + Unsigned> DistanceMetric<T> for Xor {
+    type Distance = T;
+
+    #[inline]
+    fn distance(&self, a: T, b: T) -> T {
+        a ^ b
+    }
+}
+
+/// Hamming distance, the number of differing bits of the points, i.e. popcount of `a ^ b`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Hamming;
+
+impl<T: PrimInt + Unsigned> DistanceMetric<T> for Hamming {
+    type Distance = u32;
+
+    #[inline]
+    fn distance(&self, a: T, b: T) -> u32 {
+        (a ^ b).count_ones()
+    }
+}
+
+/// Points of `XorDistance` ranked by a distance metric, see `XorDistance::with_metric`.
+pub struct MetricDistance<'a, T: PrimInt + Unsigned, M> {
+    xor_distance: &'a XorDistance<T>,
+    metric: M,
+}
+
+impl<'a, T: PrimInt + Unsigned, M> MetricDistance<'a, T, M> {
+    pub(crate) fn new(xor_distance: &'a XorDistance<T>, metric: M) -> Self {
+        MetricDistance {
+            xor_distance,
+            metric,
+        }
+    }
+
+    /// Return the metric the points are ranked by.
+    pub fn metric(&self) -> &M {
+        &self.metric
+    }
+}
+
+impl<'a, T, M> MetricDistance<'a, T, M>
+where
+    T: PrimInt + BitOps + Unsigned,
+    M: DistanceMetric<T>,
+{
+    /// Return up to requested count of closest points to the provided `x` by the metric, ordered
+    /// from the closest.
+    ///
+    /// Unlike XOR distances, distinct points can be equally distant by the metric, such points
+    /// are ordered by the `TieBreak` of the structure.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::metric::Hamming;
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u8> = XorDistance::new(vec![0b1111_0000, 0b0000_0001, 0b1000_0000]);
+    ///
+    /// // One differing bit of `0b1111_0000`, four of the others.
+    /// assert_eq!(vec![0b0000_0001, 0b1111_0000], xor_distance.closest(0b0111_0000, 2));
+    /// assert_eq!(
+    ///     vec![0b1111_0000, 0b0000_0001],
+    ///     xor_distance.with_metric(Hamming).closest(0b0111_0000, 2)
+    /// );
+    /// ```
+    pub fn closest(&self, x: T, count: usize) -> Vec<T> {
+        self.xor_distance
+            .closest_by_key(x, count, |point, _| self.metric.distance(point, x))
+    }
+
+    /// Return up to requested count of closest points to the provided `x` with their distances by
+    /// the metric, as `(point, distance)` pairs ordered as by `closest`.
+    pub fn closest_with_distances(&self, x: T, count: usize) -> Vec<(T, M::Distance)> {
+        self.closest(x, count)
+            .into_iter()
+            .map(|point| (point, self.metric.distance(point, x)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DistanceMetric, Hamming, Xor};
+    use crate::xor_distance::{TieBreak, XorDistance};
+
+    #[test]
+    fn distances() {
+        assert_eq!(0, Xor.distance(7u32, 7));
+        assert_eq!(u128::MAX, Xor.distance(0u128, u128::MAX));
+
+        assert_eq!(0, Hamming.distance(7u32, 7));
+        assert_eq!(128, Hamming.distance(0u128, u128::MAX));
+        assert_eq!(1, Hamming.distance(0b1000_0000u8, 0));
+    }
+
+    #[test]
+    fn closest_by_metric() {
+        let points: Vec<u64> = vec![
+            0, 1, 2, 4, 6, 8, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ];
+        let xor_distance = XorDistance::new(points.clone());
+
+        // The XOR metric agrees with the native closest points.
+        for x in [0, 10, 200, 407, u64::MAX].iter() {
+            for count in 0..points.len() + 2 {
+                assert_eq!(
+                    xor_distance.closest(*x, count),
+                    xor_distance.with_metric(Xor).closest(*x, count)
+                );
+            }
+        }
+
+        // Hamming distances never decrease, equal ones keep the insertion order.
+        let closest = xor_distance
+            .with_metric(Hamming)
+            .closest_with_distances(10, points.len());
+        assert_eq!(points.len(), closest.len());
+        assert_eq!(vec![(2, 1), (8, 1), (8, 1)], closest[..3].to_vec());
+
+        for pair in closest.windows(2) {
+            assert!(pair[0].1 <= pair[1].1);
+
+            if pair[0].1 == pair[1].1 {
+                let position = |point| points.iter().position(|p| *p == point).unwrap();
+                assert!(position(pair[0].0) <= position(pair[1].0));
+            }
+        }
+
+        // Equal Hamming distances ordered from the smallest point.
+        let xor_distance = XorDistance::builder(vec![4u8, 1, 2])
+            .tie_break(TieBreak::ByValue)
+            .build();
+        assert_eq!(
+            vec![1, 2, 4],
+            xor_distance.with_metric(Hamming).closest(0, 3)
+        );
+        assert_eq!(&Hamming, xor_distance.with_metric(Hamming).metric());
+    }
+}
@@ -0,0 +1,241 @@
+//! Food delivery system metrics rendered in Prometheus text exposition format.
+//!
+//! Available with the `metrics` feature only.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+//!
+//! let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 8, 12]);
+//!
+//! delivery_system.closest_farms(10, 2);
+//! delivery_system.assign_order(10);
+//!
+//! let metrics = delivery_system.render_metrics();
+//!
+//! assert!(metrics.contains("xor_delivery_queries_total 1\n"));
+//! assert!(metrics.contains("xor_delivery_farm_load{farm=\"8\"} 1\n"));
+//! ```
+
+use num_traits::{PrimInt, Unsigned};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds of the reverse solve durations histogram buckets, in seconds.
+const DURATION_BUCKETS: [f64; 7] = [0.00001, 0.0001, 0.001, 0.01, 0.1, 1.0, 10.0];
+
+/// Histogram of durations with fixed buckets.
+struct Histogram {
+    // Non-cumulative counts per bucket, the last one is the `+Inf` bucket.
+    counts: [u64; DURATION_BUCKETS.len() + 1],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Histogram {
+            counts: [0; DURATION_BUCKETS.len() + 1],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = DURATION_BUCKETS
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(DURATION_BUCKETS.len());
+
+        self.counts[bucket] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+/// Counters and histograms of the food delivery system.
+pub struct Metrics<T> {
+    queries: AtomicU64,
+    reverse_queries: AtomicU64,
+    orders: AtomicU64,
+    cancellations: AtomicU64,
+    farm_load: Mutex<BTreeMap<T, u64>>,
+    reverse_durations: Mutex<Histogram>,
+}
+
+impl<T: PrimInt + Unsigned> Metrics<T> {
+    /// Create new metrics with all values set to zero.
+    pub fn new() -> Self {
+        Metrics {
+            queries: AtomicU64::new(0),
+            reverse_queries: AtomicU64::new(0),
+            orders: AtomicU64::new(0),
+            cancellations: AtomicU64::new(0),
+            farm_load: Mutex::new(BTreeMap::new()),
+            reverse_durations: Mutex::new(Histogram::new()),
+        }
+    }
+
+    /// Record a closest farms query.
+    pub fn record_query(&self) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a reverse closest farms query and its solve duration.
+    pub fn record_reverse(&self, duration: Duration) {
+        self.reverse_queries.fetch_add(1, Ordering::Relaxed);
+        self.reverse_durations
+            .lock()
+            .unwrap()
+            .observe(duration.as_secs_f64());
+    }
+
+    /// Record an order assigned to the farm.
+    pub fn record_order(&self, farm: T) {
+        self.orders.fetch_add(1, Ordering::Relaxed);
+        *self.farm_load.lock().unwrap().entry(farm).or_insert(0) += 1;
+    }
+
+    /// Record a cancelled order assignment to the farm.
+    pub fn record_cancellation(&self, farm: T) {
+        self.cancellations.fetch_add(1, Ordering::Relaxed);
+
+        if let Some(load) = self.farm_load.lock().unwrap().get_mut(&farm) {
+            *load = load.saturating_sub(1);
+        }
+    }
+
+    /// Render all metrics in Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        let counters = [
+            (
+                "xor_delivery_queries_total",
+                "Number of closest farms queries.",
+                &self.queries,
+            ),
+            (
+                "xor_delivery_reverse_queries_total",
+                "Number of reverse closest farms queries.",
+                &self.reverse_queries,
+            ),
+            (
+                "xor_delivery_orders_total",
+                "Number of orders assigned to farms.",
+                &self.orders,
+            ),
+            (
+                "xor_delivery_cancellations_total",
+                "Number of cancelled order assignments.",
+                &self.cancellations,
+            ),
+        ];
+
+        // Writing into a `String` never fails.
+        for (name, help, counter) in counters.iter() {
+            writeln!(output, "# HELP {} {}", name, help).unwrap();
+            writeln!(output, "# TYPE {} counter", name).unwrap();
+            writeln!(output, "{} {}", name, counter.load(Ordering::Relaxed)).unwrap();
+        }
+
+        let name = "xor_delivery_farm_load";
+        writeln!(
+            output,
+            "# HELP {} Number of active orders assigned per farm.",
+            name
+        )
+        .unwrap();
+        writeln!(output, "# TYPE {} gauge", name).unwrap();
+
+        for (farm, load) in self.farm_load.lock().unwrap().iter() {
+            // Unsigned integer types always fit into `u128`.
+            let farm = farm.to_u128().unwrap();
+            writeln!(output, "{}{{farm=\"{}\"}} {}", name, farm, load).unwrap();
+        }
+
+        let name = "xor_delivery_reverse_duration_seconds";
+        let histogram = self.reverse_durations.lock().unwrap();
+        writeln!(
+            output,
+            "# HELP {} Duration of reverse closest farms solving.",
+            name
+        )
+        .unwrap();
+        writeln!(output, "# TYPE {} histogram", name).unwrap();
+
+        // Prometheus buckets are cumulative.
+        let mut cumulative = 0;
+        for (bound, count) in DURATION_BUCKETS.iter().zip(histogram.counts.iter()) {
+            cumulative += count;
+            writeln!(output, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulative).unwrap();
+        }
+        writeln!(output, "{}_bucket{{le=\"+Inf\"}} {}", name, histogram.count).unwrap();
+        writeln!(output, "{}_sum {}", name, histogram.sum).unwrap();
+        writeln!(output, "{}_count {}", name, histogram.count).unwrap();
+
+        output
+    }
+}
+
+impl<T: PrimInt + Unsigned> Default for Metrics<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metrics;
+    use std::time::Duration;
+
+    #[test]
+    fn render_counters_and_load() {
+        let metrics: Metrics<u64> = Metrics::new();
+
+        metrics.record_query();
+        metrics.record_query();
+        metrics.record_order(8);
+        metrics.record_order(8);
+        metrics.record_order(2);
+        metrics.record_cancellation(8);
+        // Cancelling an unknown farm doesn't create its load.
+        metrics.record_cancellation(5);
+
+        let output = metrics.render();
+
+        assert!(output.contains("# TYPE xor_delivery_queries_total counter\n"));
+        assert!(output.contains("xor_delivery_queries_total 2\n"));
+        assert!(output.contains("xor_delivery_orders_total 3\n"));
+        assert!(output.contains("xor_delivery_cancellations_total 2\n"));
+        assert!(output.contains(
+            "xor_delivery_farm_load{farm=\"2\"} 1\nxor_delivery_farm_load{farm=\"8\"} 1\n"
+        ));
+        assert!(!output.contains("farm=\"5\""));
+    }
+
+    #[test]
+    fn render_histogram() {
+        let metrics: Metrics<u8> = Metrics::new();
+
+        metrics.record_reverse(Duration::from_micros(50));
+        metrics.record_reverse(Duration::from_millis(5));
+        metrics.record_reverse(Duration::from_secs(20));
+
+        let output = metrics.render();
+
+        assert!(output.contains("xor_delivery_reverse_queries_total 3\n"));
+        assert!(output.contains("xor_delivery_reverse_duration_seconds_bucket{le=\"0.00001\"} 0\n"));
+        assert!(output.contains("xor_delivery_reverse_duration_seconds_bucket{le=\"0.0001\"} 1\n"));
+        assert!(output.contains("xor_delivery_reverse_duration_seconds_bucket{le=\"0.01\"} 2\n"));
+        assert!(output.contains("xor_delivery_reverse_duration_seconds_bucket{le=\"10\"} 2\n"));
+        assert!(output.contains("xor_delivery_reverse_duration_seconds_bucket{le=\"+Inf\"} 3\n"));
+        assert!(output.contains("xor_delivery_reverse_duration_seconds_count 3\n"));
+    }
+}
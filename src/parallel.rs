@@ -0,0 +1,201 @@
+//! Closest points queries spread over threads, for large point sets and query batches.
+//!
+//! Every thread selects the closest points of its chunk of the points and the partial results
+//! are merged, batches are split into chunks of queries instead. Threads are scoped standard
+//! library threads, one per available core.
+//!
+//! Available with the `parallel` feature only.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::xor_distance::XorDistance;
+//!
+//! let xor_distance: XorDistance<u32> = XorDistance::new((0..100_000).map(|i| i * 7919).collect());
+//!
+//! assert_eq!(xor_distance.closest(12345, 10), xor_distance.closest_parallel(12345, 10));
+//!
+//! let batch = xor_distance.closest_batch(&[0, 12345, u32::MAX], 3);
+//! assert_eq!(xor_distance.closest(u32::MAX, 3), batch[2]);
+//! ```
+
+use crate::bitops::BitOps;
+use crate::xor_distance::{Algorithm, IndexKind, XorDistance};
+use num_traits::{PrimInt, Unsigned};
+use std::thread;
+
+/// Points are split over threads only from this number of points.
+pub const PARALLEL_MIN_POINTS: usize = 1 << 14;
+
+impl<T: PrimInt + BitOps + Unsigned + Send + Sync> XorDistance<T> {
+    /// Return up to requested count of closest points to the provided `x`, ordered from the closest
+    /// to the n-th closest, where `n` is the count, selected by all available threads.
+    ///
+    /// Point sets smaller than `PARALLEL_MIN_POINTS` are answered by `closest` as the threads
+    /// would cost more than they save. So are queries answered by the trie index, it only walks
+    /// the paths of the returned points.
+    pub fn closest_parallel(&self, x: T, count: usize) -> Vec<T> {
+        let threads = threads();
+        let indexed = self.index_kind() == IndexKind::Trie
+            && self.select_algorithm(count) == Algorithm::Index;
+
+        if indexed || threads == 1 || self.len() < PARALLEL_MIN_POINTS || count == 0 {
+            return self.closest(x, count);
+        }
+
+        let distances: Vec<T> = self.points().map(|point| *point ^ x).collect();
+        let chunk_size = distances.len().div_ceil(threads);
+
+        let mut closest: Vec<T> = thread::scope(|scope| {
+            let partials: Vec<_> = distances
+                .chunks(chunk_size)
+                .map(|chunk| scope.spawn(move || select_smallest(chunk.to_vec(), count)))
+                .collect();
+
+            partials
+                .into_iter()
+                .flat_map(|partial| partial.join().unwrap())
+                .collect()
+        });
+
+        // Equally distant points are equal, so merging keeps the result of the sequential query.
+        closest.sort_unstable();
+        closest.truncate(count);
+
+        closest.into_iter().map(|distance| distance ^ x).collect()
+    }
+
+    /// Return closest points of every query position, each as returned by `closest`, with the
+    /// queries split over all available threads.
+    pub fn closest_batch(&self, xs: &[T], count: usize) -> Vec<Vec<T>> {
+        batch(xs, |x| self.closest(*x, count))
+    }
+
+    /// Return closest points of every `(x, count)` query, each as returned by `closest`, with the
+    /// queries split over all available threads.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 8, 12]);
+    ///
+    /// assert_eq!(
+    ///     vec![vec![8], vec![0, 8]],
+    ///     xor_distance.closest_batch_counts(&[(10, 1), (1, 2)])
+    /// );
+    /// ```
+    pub fn closest_batch_counts(&self, queries: &[(T, usize)]) -> Vec<Vec<T>> {
+        batch(queries, |(x, count)| self.closest(*x, *count))
+    }
+}
+
+/// Return answers of every query, in the order of the queries, split into chunks of queries over
+/// all available threads.
+fn batch<Q, A, F>(queries: &[Q], answer: F) -> Vec<A>
+where
+    Q: Sync,
+    A: Send,
+    F: Fn(&Q) -> A + Sync,
+{
+    let threads = threads();
+
+    if threads == 1 || queries.len() < 2 {
+        return queries.iter().map(answer).collect();
+    }
+
+    let chunk_size = queries.len().div_ceil(threads);
+    let answer = &answer;
+
+    thread::scope(|scope| {
+        let chunks: Vec<_> = queries
+            .chunks(chunk_size)
+            .map(|chunk| scope.spawn(move || chunk.iter().map(answer).collect::<Vec<A>>()))
+            .collect();
+
+        chunks
+            .into_iter()
+            .flat_map(|chunk| chunk.join().unwrap())
+            .collect()
+    })
+}
+
+/// Return number of threads to spread the work over.
+fn threads() -> usize {
+    thread::available_parallelism().map_or(1, |threads| threads.get())
+}
+
+/// Return up to `count` smallest values, in no particular order.
+fn select_smallest<T: Ord>(mut values: Vec<T>, count: usize) -> Vec<T> {
+    if count < values.len() {
+        values.select_nth_unstable(count);
+        values.truncate(count);
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PARALLEL_MIN_POINTS;
+    use crate::xor_distance::{IndexKind, RemovalKind, XorDistance};
+
+    #[test]
+    fn closest_parallel() {
+        let points: Vec<u64> = (0..2 * PARALLEL_MIN_POINTS as u64)
+            .map(|i| (i * 2_654_435_761) % 100_000)
+            .collect();
+
+        // Only the sort path is parallel, the trie index answers the queries itself.
+        for index in [IndexKind::Sort, IndexKind::Trie].iter() {
+            let mut xor_distance = XorDistance::builder(points.clone())
+                .index(*index)
+                .removal(RemovalKind::Tombstone)
+                .build();
+
+            // Tombstoned points are not returned either.
+            assert!(xor_distance.remove_point(0));
+
+            for x in [0, 77, 65_536, u64::MAX].iter() {
+                for count in [0, 1, 10, 5000].iter() {
+                    assert_eq!(
+                        xor_distance.closest(*x, *count),
+                        xor_distance.closest_parallel(*x, *count)
+                    );
+                }
+            }
+        }
+
+        let small: XorDistance<u8> = XorDistance::new(vec![3, 3, 1]);
+        assert_eq!(vec![3, 3], small.closest_parallel(2, 2));
+    }
+
+    #[test]
+    fn closest_batch() {
+        let xor_distance: XorDistance<u32> = XorDistance::new((0..1000).map(|i| i * 37).collect());
+        let xs: Vec<u32> = (0..100).map(|i| i * 991).collect();
+
+        let batch = xor_distance.closest_batch(&xs, 4);
+
+        assert_eq!(xs.len(), batch.len());
+        for (x, closest) in xs.iter().zip(batch.iter()) {
+            assert_eq!(&xor_distance.closest(*x, 4), closest);
+        }
+
+        assert!(xor_distance.closest_batch(&[], 4).is_empty());
+
+        let queries: Vec<(u32, usize)> = xs.iter().map(|x| (*x, *x as usize % 7)).collect();
+
+        for ((x, count), closest) in queries
+            .iter()
+            .zip(xor_distance.closest_batch_counts(&queries))
+        {
+            assert_eq!(xor_distance.closest(*x, *count), closest);
+        }
+    }
+}
@@ -0,0 +1,462 @@
+//! Opt-in query log with a replayable JSON Lines format.
+//!
+//! Queries run through a `QueryLogger` are recorded with their inputs, results and timings. The
+//! log can be written as JSON Lines, one query per line, and replayed against a (possibly
+//! modified) `XorDistance` to compare both results and performance.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::query_log::{replay, QueryLog, QueryLogger};
+//! use xor_distance_exercise::xor_distance::XorDistance;
+//!
+//! let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+//!
+//! let mut logger = QueryLogger::new(&xor_distance);
+//! let closest = logger.closest(10, 3);
+//! logger.reverse_closest(&closest);
+//!
+//! let jsonl = logger.into_log().to_jsonl();
+//! let log: QueryLog<u64> = QueryLog::from_jsonl(&jsonl).unwrap();
+//!
+//! // The points changed since the queries were logged.
+//! let modified: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 9, 12]);
+//! let report = replay(&log, &modified);
+//!
+//! assert_eq!(2, report.queries);
+//! assert_eq!(vec![0, 1], report.mismatches);
+//! ```
+
+use crate::error::LoadError;
+use crate::xor_distance::XorDistance;
+use num_traits::{PrimInt, Unsigned};
+use std::fmt::Write;
+use std::time::Instant;
+
+/// Query along with its inputs and result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Query<T> {
+    /// Closest points query.
+    Closest {
+        /// The queried position.
+        x: T,
+        /// The requested count of points.
+        count: usize,
+        /// The returned closest points.
+        result: Vec<T>,
+    },
+    /// Reverse closest points query.
+    Reverse {
+        /// The closest points to reverse.
+        closest_points: Vec<T>,
+        /// The returned position.
+        result: Option<T>,
+    },
+}
+
+/// Logged query with its duration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LoggedQuery<T> {
+    /// The query.
+    pub query: Query<T>,
+    /// Duration of the query in nanoseconds.
+    pub nanos: u64,
+}
+
+/// Log of queries in their order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct QueryLog<T> {
+    /// The logged queries.
+    pub queries: Vec<LoggedQuery<T>>,
+}
+
+impl<T: PrimInt + Unsigned> QueryLog<T> {
+    /// Create a new empty log.
+    pub fn new() -> Self {
+        QueryLog {
+            queries: Vec::new(),
+        }
+    }
+
+    /// Return the log in the JSON Lines format, one query per line.
+    pub fn to_jsonl(&self) -> String {
+        let mut jsonl = String::new();
+
+        for logged in self.queries.iter() {
+            // Writing into a `String` never fails.
+            match &logged.query {
+                Query::Closest { x, count, result } => writeln!(
+                    jsonl,
+                    r#"{{"query":"closest","x":{},"count":{},"result":{},"nanos":{}}}"#,
+                    key(*x),
+                    count,
+                    keys(result),
+                    logged.nanos
+                ),
+                Query::Reverse {
+                    closest_points,
+                    result,
+                } => writeln!(
+                    jsonl,
+                    r#"{{"query":"reverse","closest":{},"result":{},"nanos":{}}}"#,
+                    keys(closest_points),
+                    result.map_or("null".to_string(), |result| key(result).to_string()),
+                    logged.nanos
+                ),
+            }
+            .unwrap();
+        }
+
+        jsonl
+    }
+
+    /// Parse a log in the JSON Lines format written by `to_jsonl`, empty lines are skipped.
+    ///
+    /// Returns `Err(LoadError::Syntax)` if any line is malformed and `Err(LoadError::InvalidPoint)`
+    /// if any logged point doesn't fit into the point type, lines are indexed from one.
+    pub fn from_jsonl(jsonl: &str) -> Result<Self, LoadError> {
+        let mut log = QueryLog::new();
+
+        for (index, text) in jsonl.lines().enumerate() {
+            if !text.trim().is_empty() {
+                log.queries.push(parse_line(text, index + 1)?);
+            }
+        }
+
+        Ok(log)
+    }
+}
+
+/// Wrapper of `XorDistance` logging all queries run through it.
+pub struct QueryLogger<'a, T: PrimInt + Unsigned> {
+    xor_distance: &'a XorDistance<T>,
+    log: QueryLog<T>,
+}
+
+impl<'a, T: PrimInt + Unsigned> QueryLogger<'a, T> {
+    /// Create a new logger of queries against the `XorDistance`.
+    pub fn new(xor_distance: &'a XorDistance<T>) -> Self {
+        QueryLogger {
+            xor_distance,
+            log: QueryLog::new(),
+        }
+    }
+
+    /// Run and log the `XorDistance::closest` query.
+    pub fn closest(&mut self, x: T, count: usize) -> Vec<T> {
+        let start = Instant::now();
+        let result = self.xor_distance.closest(x, count);
+        let nanos = elapsed_nanos(start);
+
+        self.log.queries.push(LoggedQuery {
+            query: Query::Closest {
+                x,
+                count,
+                result: result.clone(),
+            },
+            nanos,
+        });
+
+        result
+    }
+
+    /// Run and log the `XorDistance::reverse_closest` query.
+    pub fn reverse_closest(&mut self, closest_points: &[T]) -> Option<T> {
+        let start = Instant::now();
+        let result = self.xor_distance.reverse_closest(closest_points);
+        let nanos = elapsed_nanos(start);
+
+        self.log.queries.push(LoggedQuery {
+            query: Query::Reverse {
+                closest_points: closest_points.to_vec(),
+                result,
+            },
+            nanos,
+        });
+
+        result
+    }
+
+    /// Return the queries logged so far.
+    pub fn log(&self) -> &QueryLog<T> {
+        &self.log
+    }
+
+    /// Stop logging and return the log.
+    pub fn into_log(self) -> QueryLog<T> {
+        self.log
+    }
+}
+
+/// Comparison of a replayed log with the logged results and timings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReplayReport {
+    /// Number of replayed queries.
+    pub queries: usize,
+    /// Indexes of the queries whose results differ from the logged ones.
+    pub mismatches: Vec<usize>,
+    /// Sum of the logged durations in nanoseconds.
+    pub logged_nanos: u64,
+    /// Sum of the replayed durations in nanoseconds.
+    pub replayed_nanos: u64,
+}
+
+/// Re-execute all logged queries against the `XorDistance`.
+pub fn replay<T: PrimInt + Unsigned>(
+    log: &QueryLog<T>,
+    xor_distance: &XorDistance<T>,
+) -> ReplayReport {
+    let mut logger = QueryLogger::new(xor_distance);
+    let mut mismatches = Vec::new();
+
+    for (index, logged) in log.queries.iter().enumerate() {
+        let matches = match &logged.query {
+            Query::Closest { x, count, result } => logger.closest(*x, *count) == *result,
+            Query::Reverse {
+                closest_points,
+                result,
+            } => logger.reverse_closest(closest_points) == *result,
+        };
+
+        if !matches {
+            mismatches.push(index);
+        }
+    }
+
+    ReplayReport {
+        queries: log.queries.len(),
+        mismatches,
+        logged_nanos: log.queries.iter().map(|logged| logged.nanos).sum(),
+        replayed_nanos: logger.log.queries.iter().map(|logged| logged.nanos).sum(),
+    }
+}
+
+fn elapsed_nanos(start: Instant) -> u64 {
+    // Queries never take hundreds of years.
+    start.elapsed().as_nanos() as u64
+}
+
+fn key<T: PrimInt + Unsigned>(point: T) -> u128 {
+    // Unsigned integer types always fit into `u128`.
+    point.to_u128().unwrap()
+}
+
+fn keys<T: PrimInt + Unsigned>(points: &[T]) -> String {
+    let keys: Vec<String> = points.iter().map(|point| key(*point).to_string()).collect();
+
+    format!("[{}]", keys.join(","))
+}
+
+/// Value of a logged field.
+enum Value<'a> {
+    Text(&'a str),
+    Number(u128),
+    Numbers(Vec<u128>),
+    Null,
+}
+
+fn parse_line<T: PrimInt + Unsigned>(text: &str, line: usize) -> Result<LoggedQuery<T>, LoadError> {
+    let fields = parse_object(text, line)?;
+    let syntax = |expected: &'static str| LoadError::Syntax { line, expected };
+    let field = |name: &str| {
+        fields
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+            .ok_or_else(|| syntax("all fields of the logged query"))
+    };
+
+    let number = |name: &str| match field(name)? {
+        Value::Number(number) => Ok(*number),
+        _ => Err(syntax("a number field")),
+    };
+    let points = |name: &str| match field(name)? {
+        Value::Numbers(numbers) => numbers.iter().map(|number| point(*number, line)).collect(),
+        _ => Err(syntax("an array field")),
+    };
+
+    let query = match field("query")? {
+        Value::Text("closest") => Query::Closest {
+            x: point(number("x")?, line)?,
+            count: number("count")? as usize,
+            result: points("result")?,
+        },
+        Value::Text("reverse") => Query::Reverse {
+            closest_points: points("closest")?,
+            result: match field("result")? {
+                Value::Null => None,
+                Value::Number(number) => Some(point(*number, line)?),
+                _ => return Err(syntax("a number or null result")),
+            },
+        },
+        _ => return Err(syntax("a closest or reverse query")),
+    };
+
+    Ok(LoggedQuery {
+        query,
+        nanos: number("nanos")? as u64,
+    })
+}
+
+fn point<T: PrimInt + Unsigned>(number: u128, line: usize) -> Result<T, LoadError> {
+    T::from(number).ok_or_else(|| LoadError::InvalidPoint {
+        line,
+        value: number.to_string(),
+    })
+}
+
+/// Parse a flat JSON object of the logged fields.
+fn parse_object(text: &str, line: usize) -> Result<Vec<(&str, Value<'_>)>, LoadError> {
+    let malformed = || LoadError::Syntax {
+        line,
+        expected: "a flat JSON object",
+    };
+
+    let inner = text
+        .trim()
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or_else(malformed)?;
+    let mut rest = inner.trim_start();
+    let mut fields = Vec::new();
+
+    while !rest.is_empty() {
+        let (key, after_key) = parse_text(rest).ok_or_else(malformed)?;
+        let after_colon = after_key
+            .trim_start()
+            .strip_prefix(':')
+            .ok_or_else(malformed)?
+            .trim_start();
+
+        let (value, after_value) = if after_colon.starts_with('"') {
+            let (text, after) = parse_text(after_colon).ok_or_else(malformed)?;
+            (Value::Text(text), after)
+        } else if let Some(after) = after_colon.strip_prefix("null") {
+            (Value::Null, after)
+        } else if let Some(array) = after_colon.strip_prefix('[') {
+            let end = array.find(']').ok_or_else(malformed)?;
+            let numbers = array[..end]
+                .split(',')
+                .map(str::trim)
+                .filter(|number| !number.is_empty())
+                .map(|number| number.parse().map_err(|_| malformed()))
+                .collect::<Result<Vec<u128>, LoadError>>()?;
+            (Value::Numbers(numbers), &array[end + 1..])
+        } else {
+            let end = after_colon
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(after_colon.len());
+            let number = after_colon[..end].parse().map_err(|_| malformed())?;
+            (Value::Number(number), &after_colon[end..])
+        };
+
+        fields.push((key, value));
+
+        let after_value = after_value.trim_start();
+        rest = match after_value.strip_prefix(',') {
+            Some(next) => next.trim_start(),
+            None if after_value.is_empty() => after_value,
+            None => return Err(malformed()),
+        };
+    }
+
+    Ok(fields)
+}
+
+/// Parse a JSON string without escapes, returning it and the rest of the input.
+fn parse_text(input: &str) -> Option<(&str, &str)> {
+    let text = input.strip_prefix('"')?;
+    let end = text.find('"')?;
+
+    Some((&text[..end], &text[end + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replay, LoggedQuery, Query, QueryLog, QueryLogger};
+    use crate::xor_distance::XorDistance;
+
+    #[test]
+    fn jsonl_round_trip() {
+        let log: QueryLog<u8> = QueryLog {
+            queries: vec![
+                LoggedQuery {
+                    query: Query::Closest {
+                        x: 255,
+                        count: 2,
+                        result: vec![200, 3],
+                    },
+                    nanos: 1500,
+                },
+                LoggedQuery {
+                    query: Query::Reverse {
+                        closest_points: Vec::new(),
+                        result: None,
+                    },
+                    nanos: 7,
+                },
+            ],
+        };
+
+        let jsonl = log.to_jsonl();
+
+        assert_eq!(
+            "{\"query\":\"closest\",\"x\":255,\"count\":2,\"result\":[200,3],\"nanos\":1500}\n\
+             {\"query\":\"reverse\",\"closest\":[],\"result\":null,\"nanos\":7}\n",
+            jsonl
+        );
+        assert_eq!(log, QueryLog::from_jsonl(&jsonl).unwrap());
+
+        // Whitespace and field order don't matter.
+        let line = r#" { "nanos" : 1, "result": 4, "closest": [ 1, 2 ], "query": "reverse" } "#;
+        let parsed: QueryLog<u8> = QueryLog::from_jsonl(line).unwrap();
+        assert_eq!(
+            Query::Reverse {
+                closest_points: vec![1, 2],
+                result: Some(4),
+            },
+            parsed.queries[0].query
+        );
+    }
+
+    #[test]
+    fn malformed_jsonl() {
+        let parse = |jsonl: &str| QueryLog::<u8>::from_jsonl(jsonl).unwrap_err().to_string();
+
+        assert_eq!(
+            "Expected a flat JSON object on line 1!",
+            parse("{\"query\":\"closest\"")
+        );
+        assert_eq!(
+            "Expected all fields of the logged query on line 1!",
+            parse("{\"query\":\"closest\"}")
+        );
+        assert_eq!(
+            "Expected a closest or reverse query on line 3!",
+            parse("{\"query\":\"reverse\",\"closest\":[],\"result\":null,\"nanos\":0}\n\n{\"query\":\"range\"}")
+        );
+        assert_eq!(
+            "Invalid point \"256\" on line 1!",
+            parse("{\"query\":\"closest\",\"x\":256,\"count\":1,\"result\":[],\"nanos\":0}")
+        );
+    }
+
+    #[test]
+    fn replay_unchanged() {
+        let xor_distance: XorDistance<u32> = XorDistance::new((0..100).collect());
+        let mut logger = QueryLogger::new(&xor_distance);
+
+        for x in 0..10 {
+            let closest = logger.closest(x * 7, 5);
+            assert!(logger.reverse_closest(&closest).is_some());
+        }
+
+        assert_eq!(20, logger.log().queries.len());
+
+        let report = replay(&logger.into_log(), &xor_distance);
+        assert_eq!(20, report.queries);
+        assert!(report.mismatches.is_empty());
+    }
+}
@@ -0,0 +1,225 @@
+//! Bounded-memory sampling of unbounded point streams.
+//!
+//! Only a reservoir of a fixed capacity is retained, sampled either uniformly or biased toward
+//! points close to a focus key, so approximate queries stay possible on data too large to store.
+//!
+//! Available with the `rand` feature only.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate rand;
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::reservoir::Sampling;
+//! use xor_distance_exercise::xor_distance::XorDistance;
+//!
+//! let stream = 0..1_000_000u64;
+//!
+//! let xor_distance = XorDistance::from_stream_sampled(
+//!     stream,
+//!     100,
+//!     Sampling::Uniform,
+//!     &mut rand::thread_rng(),
+//! );
+//!
+//! assert_eq!(100, xor_distance.len());
+//! ```
+
+use crate::xor_distance::XorDistance;
+use num_traits::{PrimInt, Unsigned};
+use rand::Rng;
+
+/// Sampling strategy of a reservoir.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sampling<T> {
+    /// Every point of the stream has the same chance to be retained.
+    Uniform,
+    /// Points closer to the focus key by XOR distance have a higher chance to be retained, the
+    /// weight of a point grows linearly with the length of its common prefix with the focus.
+    Focused(T),
+}
+
+/// Reservoir of sampled stream points.
+pub struct Reservoir<T> {
+    capacity: usize,
+    sampling: Sampling<T>,
+    // Retained points along with their sampling keys, unused by uniform sampling.
+    points: Vec<(f64, T)>,
+    seen: u64,
+}
+
+impl<T: PrimInt + Unsigned> Reservoir<T> {
+    /// Create a new empty reservoir retaining up to `capacity` points.
+    pub fn new(capacity: usize, sampling: Sampling<T>) -> Self {
+        Reservoir {
+            capacity,
+            sampling,
+            points: Vec::with_capacity(capacity),
+            seen: 0,
+        }
+    }
+
+    /// Return number of points seen in the stream so far.
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    /// Return number of retained points.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Return whether no points are retained.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Return retained points, in no particular order.
+    pub fn points(&self) -> Vec<T> {
+        self.points.iter().map(|(_, point)| *point).collect()
+    }
+
+    /// Offer a stream point to the reservoir.
+    pub fn push<R: Rng + ?Sized>(&mut self, point: T, rng: &mut R) {
+        self.seen += 1;
+
+        if self.capacity == 0 {
+            return;
+        }
+
+        match self.sampling {
+            Sampling::Uniform => {
+                if self.points.len() < self.capacity {
+                    self.points.push((0.0, point));
+                } else {
+                    // Replace a retained point with the probability `capacity / seen`.
+                    let index = rng.gen_range(0, self.seen) as usize;
+
+                    if index < self.capacity {
+                        self.points[index] = (0.0, point);
+                    }
+                }
+            }
+            Sampling::Focused(focus) => {
+                // Weighted sampling keeps points with the highest `u^(1 / weight)` keys, compared
+                // by their logarithms `ln(u) / weight`.
+                let uniform: f64 = rng.gen_range(f64::MIN_POSITIVE, 1.0);
+                let key = uniform.ln() / Self::weight(point, focus);
+
+                if self.points.len() < self.capacity {
+                    self.points.push((key, point));
+                } else {
+                    let (lowest, _) = self
+                        .points
+                        .iter()
+                        .enumerate()
+                        .min_by(|(_, a), (_, b)| a.0.total_cmp(&b.0))
+                        // The reservoir is full and the capacity is not zero.
+                        .unwrap();
+
+                    if key > self.points[lowest].0 {
+                        self.points[lowest] = (key, point);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Offer all points of the stream to the reservoir.
+    pub fn extend<I, R>(&mut self, stream: I, rng: &mut R)
+    where
+        I: IntoIterator<Item = T>,
+        R: Rng + ?Sized,
+    {
+        for point in stream {
+            self.push(point, rng);
+        }
+    }
+
+    /// Return weight of the point, one plus the length of its common prefix with the focus.
+    fn weight(point: T, focus: T) -> f64 {
+        f64::from((point ^ focus).leading_zeros() + 1)
+    }
+}
+
+impl<T: PrimInt + Unsigned> XorDistance<T> {
+    /// Create a new `XorDistance` from an unbounded stream of points, retaining only a sampled
+    /// reservoir of up to `capacity` points.
+    ///
+    /// Queries are approximate then, answered from the retained points only.
+    pub fn from_stream_sampled<I, R>(
+        stream: I,
+        capacity: usize,
+        sampling: Sampling<T>,
+        rng: &mut R,
+    ) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        R: Rng + ?Sized,
+    {
+        let mut reservoir = Reservoir::new(capacity, sampling);
+        reservoir.extend(stream, rng);
+
+        XorDistance::new(reservoir.points())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Reservoir, Sampling};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn uniform_sampling() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut hits = [0u32; 10];
+
+        // Every point is retained with the probability `capacity / seen`.
+        for _ in 0..2000 {
+            let mut reservoir: Reservoir<u8> = Reservoir::new(3, Sampling::Uniform);
+            reservoir.extend(0..10, &mut rng);
+
+            assert_eq!(10, reservoir.seen());
+            assert_eq!(3, reservoir.len());
+
+            for point in reservoir.points() {
+                hits[point as usize] += 1;
+            }
+        }
+
+        for hit in hits.iter() {
+            assert!((*hit as f64 - 600.0).abs() < 100.0);
+        }
+    }
+
+    #[test]
+    fn focused_sampling() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut reservoir: Reservoir<u16> = Reservoir::new(200, Sampling::Focused(0));
+
+        reservoir.extend(0..=u16::MAX, &mut rng);
+
+        // Points close to the focus are over-represented, the half of the stream furthest from
+        // the focus would make up the half of a uniform sample.
+        let far = reservoir
+            .points()
+            .iter()
+            .filter(|point| **point >= 1 << 15)
+            .count();
+
+        assert_eq!(200, reservoir.len());
+        assert!(far < 80);
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut reservoir: Reservoir<u8> = Reservoir::new(0, Sampling::Focused(1));
+
+        reservoir.extend(0..10, &mut rand::thread_rng());
+
+        assert!(reservoir.is_empty());
+        assert_eq!(10, reservoir.seen());
+    }
+}
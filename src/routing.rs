@@ -0,0 +1,195 @@
+//! Route planning of dispatches for multiple couriers.
+//!
+//! Orders of a dispatch are split amongst couriers starting at the farm. Every route is built
+//! greedily by visiting the nearest order next and then improved by 2-opt moves, all over XOR
+//! distance.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::batching::Dispatch;
+//! use xor_distance_exercise::routing::plan_routes;
+//!
+//! let dispatch: Dispatch<u64> = Dispatch {
+//!     farm: 0,
+//!     orders: vec![17, 3, 16, 2],
+//!     opened_at: 0,
+//! };
+//!
+//! let plan = plan_routes(&dispatch, 2);
+//!
+//! assert_eq!(vec![2, 3], plan.routes[0].stops);
+//! assert_eq!(vec![16, 17], plan.routes[1].stops);
+//! assert_eq!(3 + 17, plan.total_distance);
+//! ```
+
+use crate::batching::Dispatch;
+use num_traits::{PrimInt, Unsigned};
+
+/// Sequence of orders delivered by one courier, starting at the farm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Route<T> {
+    /// Customers' positions in the order of delivery.
+    pub stops: Vec<T>,
+    /// Sum of XOR distances from the farm through all the stops.
+    pub distance: u128,
+}
+
+/// Routes of all couriers delivering a dispatch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoutePlan<T> {
+    /// Farm the couriers start at.
+    pub farm: T,
+    /// Route of every courier, couriers without orders are left out.
+    pub routes: Vec<Route<T>>,
+    /// Sum of distances of all routes.
+    pub total_distance: u128,
+}
+
+/// Plan routes of up to `couriers` couriers delivering the dispatch.
+///
+/// Orders are split evenly, every courier takes the orders nearest to its current stop in turn,
+/// and the routes are improved by 2-opt moves afterwards.
+///
+/// # Panics
+///
+/// Panics if `couriers` is zero.
+pub fn plan_routes<T: PrimInt + Unsigned>(dispatch: &Dispatch<T>, couriers: usize) -> RoutePlan<T> {
+    assert!(couriers > 0, "There must be at least one courier!");
+
+    let farm = dispatch.farm;
+    let mut remaining = dispatch.orders.clone();
+    let route_size = remaining.len().div_ceil(couriers);
+    let mut routes = Vec::with_capacity(couriers);
+
+    while !remaining.is_empty() {
+        let mut stops = Vec::with_capacity(route_size);
+        let mut current = farm;
+
+        while stops.len() < route_size && !remaining.is_empty() {
+            let (nearest, _) = remaining
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, order)| **order ^ current)
+                // There are remaining orders.
+                .unwrap();
+
+            current = remaining.swap_remove(nearest);
+            stops.push(current);
+        }
+
+        improve_two_opt(farm, &mut stops);
+
+        routes.push(Route {
+            distance: route_distance(farm, &stops),
+            stops,
+        });
+    }
+
+    RoutePlan {
+        farm,
+        total_distance: routes.iter().map(|route| route.distance).sum(),
+        routes,
+    }
+}
+
+/// Return sum of XOR distances from the farm through all the stops.
+pub fn route_distance<T: PrimInt + Unsigned>(farm: T, stops: &[T]) -> u128 {
+    let mut current = farm;
+
+    stops
+        .iter()
+        .map(|stop| {
+            let distance = distance(current, *stop);
+            current = *stop;
+            distance
+        })
+        .sum()
+}
+
+/// Reverse route segments as long as it shortens the route.
+fn improve_two_opt<T: PrimInt + Unsigned>(farm: T, stops: &mut [T]) {
+    let mut improved = true;
+
+    while improved {
+        improved = false;
+
+        for i in 0..stops.len() {
+            for j in i + 1..stops.len() {
+                // Reversing `stops[i..=j]` only changes the edges entering and leaving it.
+                let before = if i == 0 { farm } else { stops[i - 1] };
+                let mut current = distance(before, stops[i]);
+                let mut reversed = distance(before, stops[j]);
+
+                if let Some(after) = stops.get(j + 1) {
+                    current += distance(stops[j], *after);
+                    reversed += distance(stops[i], *after);
+                }
+
+                if reversed < current {
+                    stops[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+}
+
+fn distance<T: PrimInt + Unsigned>(a: T, b: T) -> u128 {
+    // Unsigned integer types always fit into `u128`.
+    (a ^ b).to_u128().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{improve_two_opt, plan_routes, route_distance};
+    use crate::batching::Dispatch;
+
+    fn dispatch(orders: Vec<u64>) -> Dispatch<u64> {
+        Dispatch {
+            farm: 0,
+            orders,
+            opened_at: 0,
+        }
+    }
+
+    #[test]
+    fn single_courier() {
+        let plan = plan_routes(&dispatch(vec![12, 1, 8, 3]), 1);
+
+        assert_eq!(1, plan.routes.len());
+        assert_eq!(vec![1, 3, 8, 12], plan.routes[0].stops);
+        assert_eq!(1 + 2 + 11 + 4, plan.total_distance);
+    }
+
+    #[test]
+    fn more_couriers_than_orders() {
+        let plan = plan_routes(&dispatch(vec![5, 9]), 4);
+
+        assert_eq!(2, plan.routes.len());
+        assert_eq!(vec![5], plan.routes[0].stops);
+        assert_eq!(vec![9], plan.routes[1].stops);
+        assert_eq!(14, plan.total_distance);
+
+        assert!(plan_routes(&dispatch(Vec::new()), 2).routes.is_empty());
+    }
+
+    #[test]
+    fn two_opt_shortens_route() {
+        let mut stops = vec![8u64, 1, 9, 0];
+        let before = route_distance(0, &stops);
+
+        improve_two_opt(0, &mut stops);
+
+        assert!(route_distance(0, &stops) < before);
+        assert_eq!(vec![0, 1, 9, 8], stops);
+    }
+
+    #[test]
+    #[should_panic(expected = "There must be at least one courier!")]
+    fn no_courier() {
+        plan_routes(&dispatch(vec![1]), 0);
+    }
+}
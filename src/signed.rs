@@ -0,0 +1,315 @@
+//! Signed integer points mapped onto `Unsigned Integer` keys.
+//!
+//! A signed point is mapped to the unsigned key of the same width by flipping its sign bit, which
+//! is the bias offset by `2^(N-1)`. The mapping preserves the order of points, and as both points
+//! of a distance are flipped alike it also preserves the XOR distance of their two's-complement
+//! bit patterns. The solver works on keys, so the reverse-closest logic stays unchanged and only
+//! the results are mapped back.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::signed::SignedXorDistance;
+//!
+//! let xor_distance: SignedXorDistance<i64> = SignedXorDistance::new(vec![-8, -1, 0, 3, 12]);
+//!
+//! let closest = xor_distance.closest(-2, 3);
+//! assert_eq!(vec![-1, -8, 12], closest);
+//!
+//! let position = xor_distance.reverse_closest(&closest).unwrap();
+//! assert_eq!(closest, xor_distance.closest(position, 3));
+//! ```
+
+use crate::bitops::BitOps;
+use crate::delivery_system::FoodDeliverySystem;
+use crate::hooks::Assignment;
+use crate::xor_distance::XorDistance;
+use num_traits::{PrimInt, Signed, Unsigned};
+
+/// Signed integer type with its unsigned key type of the same width.
+pub trait SignedPoint: PrimInt + Signed {
+    /// Unsigned type of the same width.
+    type Key: PrimInt + BitOps + Unsigned + 'static;
+
+    /// Return key of the point, the point with its sign bit flipped.
+    fn to_key(self) -> Self::Key;
+
+    /// Return point of the key, the inverse of `to_key`.
+    fn from_key(key: Self::Key) -> Self;
+
+    /// Return XOR distance of the points, the XOR of their two's-complement bit patterns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::signed::SignedPoint;
+    ///
+    /// assert_eq!(1, (-1i8).distance(-2));
+    /// assert_eq!(255, (-1i8).distance(0));
+    /// ```
+    fn distance(self, other: Self) -> Self::Key {
+        self.to_key() ^ other.to_key()
+    }
+}
+
+macro_rules! signed_point {
+    ($($signed:ty => $unsigned:ty),*) => {
+        $(
+            impl SignedPoint for $signed {
+                type Key = $unsigned;
+
+                #[inline]
+                fn to_key(self) -> $unsigned {
+                    (self as $unsigned) ^ (<$signed>::MIN as $unsigned)
+                }
+
+                #[inline]
+                fn from_key(key: $unsigned) -> $signed {
+                    (key ^ (<$signed>::MIN as $unsigned)) as $signed
+                }
+            }
+        )*
+    };
+}
+
+signed_point!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+/// Map points to their keys.
+fn to_keys<S: SignedPoint>(points: &[S]) -> Vec<S::Key> {
+    points.iter().map(|point| point.to_key()).collect()
+}
+
+/// Map keys back to their points.
+fn from_keys<S: SignedPoint>(keys: Vec<S::Key>) -> Vec<S> {
+    keys.into_iter().map(S::from_key).collect()
+}
+
+/// Xor distance structure holding set of `Signed Integer` points.
+pub struct SignedXorDistance<S: SignedPoint> {
+    xor_distance: XorDistance<S::Key>,
+}
+
+impl<S: SignedPoint> SignedXorDistance<S> {
+    /// Create a new structure holding the points.
+    pub fn new(points: Vec<S>) -> Self {
+        SignedXorDistance {
+            xor_distance: XorDistance::new(to_keys(&points)),
+        }
+    }
+
+    /// Return the underlying structure of the points' keys.
+    pub fn xor_distance(&self) -> &XorDistance<S::Key> {
+        &self.xor_distance
+    }
+
+    /// Return number of points.
+    pub fn len(&self) -> usize {
+        self.xor_distance.len()
+    }
+
+    /// Return whether there are no points.
+    pub fn is_empty(&self) -> bool {
+        self.xor_distance.is_empty()
+    }
+
+    /// Add a new point.
+    pub fn add_point(&mut self, point: S) {
+        self.xor_distance.add_point(point.to_key());
+    }
+
+    /// Remove one occurrence of the point, returning whether it was present.
+    pub fn remove_point(&mut self, point: S) -> bool {
+        self.xor_distance.remove_point(point.to_key())
+    }
+
+    /// Return up to requested count of closest points to the provided `x`, ordered from the closest
+    /// to the n-th closest, where `n` is the count.
+    pub fn closest(&self, x: S, count: usize) -> Vec<S> {
+        from_keys(self.xor_distance.closest(x.to_key(), count))
+    }
+
+    /// Return up to requested count of closest points to the provided `x` within the XOR distance
+    /// `max_distance`, ordered from the closest to the n-th closest, where `n` is the count.
+    pub fn closest_bounded(&self, x: S, count: usize, max_distance: S::Key) -> Vec<S> {
+        from_keys(
+            self.xor_distance
+                .closest_bounded(x.to_key(), count, max_distance),
+        )
+    }
+
+    /// Return the `n`-th closest point to the provided `x`, indexed from zero, `None` if there are
+    /// not more than `n` points.
+    pub fn nth_closest(&self, x: S, n: usize) -> Option<S> {
+        self.xor_distance
+            .nth_closest(x.to_key(), n)
+            .map(S::from_key)
+    }
+
+    /// Return a `Some(x)` such that `self.closest(x)` equals closest_points and return None in case
+    /// such a `x` does not exists.
+    pub fn reverse_closest(&self, closest_points: &[S]) -> Option<S> {
+        self.xor_distance
+            .reverse_closest(&to_keys(closest_points))
+            .map(S::from_key)
+    }
+}
+
+/// Food delivery system of farms at `Signed Integer` positions.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::signed::SignedFoodDeliverySystem;
+///
+/// let delivery_system: SignedFoodDeliverySystem<i32> =
+///     SignedFoodDeliverySystem::new(vec![-100, -3, 5, 40]);
+///
+/// assert_eq!(-3, delivery_system.assign_order(-4).unwrap().farm);
+///
+/// let closest_farms = delivery_system.closest_farms(7, 2);
+/// let position = delivery_system.reverse_closest_farms(&closest_farms).unwrap();
+/// assert_eq!(closest_farms, delivery_system.closest_farms(position, 2));
+/// ```
+pub struct SignedFoodDeliverySystem<S: SignedPoint> {
+    delivery_system: FoodDeliverySystem<S::Key>,
+}
+
+impl<S: SignedPoint> SignedFoodDeliverySystem<S> {
+    /// Create a new Food Delivery System with farms at the positions.
+    pub fn new(farms: Vec<S>) -> Self {
+        SignedFoodDeliverySystem {
+            delivery_system: FoodDeliverySystem::new(to_keys(&farms)),
+        }
+    }
+
+    /// Return the underlying system of the farms' keys, with its hooks, tiers and metrics.
+    pub fn delivery_system(&self) -> &FoodDeliverySystem<S::Key> {
+        &self.delivery_system
+    }
+
+    /// Return the underlying system of the farms' keys mutably.
+    pub fn delivery_system_mut(&mut self) -> &mut FoodDeliverySystem<S::Key> {
+        &mut self.delivery_system
+    }
+
+    /// Add a new farm.
+    pub fn add_farm(&mut self, farm: S) {
+        self.delivery_system.add_farm(farm.to_key());
+    }
+
+    /// Remove the farm.
+    ///
+    /// Returns `true` if the farm was present, `false` otherwise.
+    pub fn remove_farm(&mut self, farm: S) -> bool {
+        self.delivery_system.remove_farm(farm.to_key())
+    }
+
+    /// Assign customer's order to the closest farm.
+    ///
+    /// Returns `None` if there are no farms.
+    pub fn assign_order(&self, position: S) -> Option<Assignment<S>> {
+        self.delivery_system
+            .assign_order(position.to_key())
+            .map(|assignment| Assignment {
+                position,
+                farm: S::from_key(assignment.farm),
+                tier: assignment.tier,
+            })
+    }
+
+    /// Return up to requested count of closest farms to the position, ordered from the closest to
+    /// the n-th closest, where `n` is the count.
+    pub fn closest_farms(&self, position: S, count: usize) -> Vec<S> {
+        from_keys(self.delivery_system.closest_farms(position.to_key(), count))
+    }
+
+    /// Return up to requested count of closest farms to the position within the XOR distance
+    /// `max_distance`, ordered from the closest to the n-th closest, where `n` is the count.
+    pub fn closest_farms_bounded(&self, position: S, count: usize, max_distance: S::Key) -> Vec<S> {
+        from_keys(self.delivery_system.closest_farms_bounded(
+            position.to_key(),
+            count,
+            max_distance,
+        ))
+    }
+
+    /// Return a possible position of a customer with the closest farms, `None` if there is no such
+    /// a position.
+    pub fn reverse_closest_farms(&self, closest_farms: &[S]) -> Option<S> {
+        self.delivery_system
+            .reverse_closest_farms(&to_keys(closest_farms))
+            .map(S::from_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SignedFoodDeliverySystem, SignedPoint, SignedXorDistance};
+
+    #[test]
+    fn keys() {
+        assert_eq!(0u8, i8::MIN.to_key());
+        assert_eq!(128u8, 0i8.to_key());
+        assert_eq!(u64::MAX, i64::MAX.to_key());
+
+        for point in [i16::MIN, -300, -1, 0, 1, 300, i16::MAX].iter() {
+            assert_eq!(*point, i16::from_key(point.to_key()));
+        }
+
+        // Keys keep the order of points.
+        let points = [-5i32, -1, 0, 7, i32::MAX];
+        assert!(points
+            .windows(2)
+            .all(|pair| pair[0].to_key() < pair[1].to_key()));
+
+        // The distance is the XOR of the two's-complement bit patterns.
+        assert_eq!((-5i32 as u32) ^ 7, (-5i32).distance(7));
+    }
+
+    #[test]
+    fn closest_and_reverse() {
+        let points: Vec<i64> = vec![i64::MIN, -406, -20, -2, -1, 0, 1, 4, 19, 445, i64::MAX];
+        let xor_distance = SignedXorDistance::new(points.clone());
+
+        for x in [i64::MIN, -300, -1, 0, 5, 200, i64::MAX].iter() {
+            let mut expected = points.clone();
+            expected.sort_by_key(|point| point.distance(*x));
+
+            for count in 0..points.len() + 1 {
+                let closest = xor_distance.closest(*x, count);
+                assert_eq!(&expected[..count], &closest[..]);
+
+                let position = xor_distance.reverse_closest(&closest).unwrap();
+                assert_eq!(closest, xor_distance.closest(position, count));
+            }
+
+            assert_eq!(Some(expected[3]), xor_distance.nth_closest(*x, 3));
+        }
+
+        assert_eq!(vec![-1, -2], xor_distance.closest_bounded(-1, 5, 1));
+        assert_eq!(None, xor_distance.reverse_closest(&[0, i64::MIN, 1]));
+    }
+
+    #[test]
+    fn delivery_system() {
+        let mut delivery_system: SignedFoodDeliverySystem<i8> =
+            SignedFoodDeliverySystem::new(vec![-128, -1, 1]);
+
+        let assignment = delivery_system.assign_order(-2).unwrap();
+        assert_eq!(-2, assignment.position);
+        assert_eq!(-1, assignment.farm);
+
+        assert!(delivery_system.remove_farm(-1));
+        assert!(!delivery_system.remove_farm(-1));
+        delivery_system.add_farm(-3);
+
+        assert_eq!(vec![-3, -128], delivery_system.closest_farms(-2, 2));
+        assert_eq!(vec![-3], delivery_system.closest_farms_bounded(-2, 2, 3));
+        assert_eq!(3, delivery_system.delivery_system().xor_distance().len());
+    }
+}
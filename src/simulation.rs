@@ -0,0 +1,304 @@
+//! Hour-by-hour simulation of farms with opening hours and time-varying customer arrivals.
+//!
+//! Every simulated hour only the open farms take orders, each up to its hourly capacity, with
+//! customers overflowing their closest farm spilling over as by `assign_round_capped`. The
+//! resulting load and rejection curves can be fed to the `LoadForecaster` or compared between
+//! farm layouts.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::simulation::{ArrivalRates, Schedule, Simulation};
+//!
+//! // A day farm and a night farm, customers arrive mostly during the day.
+//! let mut rates = [1.0; 24];
+//! for rate in rates[8..20].iter_mut() {
+//!     *rate = 4.0;
+//! }
+//!
+//! let simulation: Simulation<u64> = Simulation::new(
+//!     vec![(0, Schedule::hours(8, 20)), (64, Schedule::hours(20, 8))],
+//!     3,
+//!     ArrivalRates::new(rates),
+//! );
+//!
+//! let reports = simulation.run(&[1, 2, 65, 66], 24);
+//!
+//! // Only one farm is open at a time, so a busy hour rejects one of four orders.
+//! assert_eq!(1, reports[12].rejected);
+//! assert_eq!(3, reports[12].loads[&0]);
+//! assert_eq!(0, reports[2].rejected);
+//! ```
+
+use crate::delivery_system::FoodDeliverySystem;
+use num_traits::{PrimInt, Unsigned};
+use std::collections::BTreeMap;
+
+/// Number of hours of a day.
+pub const HOURS_PER_DAY: usize = 24;
+
+/// Opening hours of a farm, repeating every day.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    open: [bool; HOURS_PER_DAY],
+}
+
+impl Schedule {
+    /// Create a schedule of a farm open all day.
+    pub fn always_open() -> Self {
+        Schedule {
+            open: [true; HOURS_PER_DAY],
+        }
+    }
+
+    /// Create a schedule of a farm open from the `open` hour until the `close` hour, wrapping
+    /// over midnight if `close` is not after `open`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the hours is not less than `HOURS_PER_DAY`.
+    pub fn hours(open: usize, close: usize) -> Self {
+        assert!(
+            open < HOURS_PER_DAY && close < HOURS_PER_DAY,
+            "Hour is out of range!"
+        );
+
+        let mut schedule = Schedule {
+            open: [false; HOURS_PER_DAY],
+        };
+        let mut hour = open;
+
+        loop {
+            schedule.open[hour] = true;
+            hour = (hour + 1) % HOURS_PER_DAY;
+
+            if hour == close {
+                break;
+            }
+        }
+
+        schedule
+    }
+
+    /// Return whether the farm is open in the simulated hour, counted from midnight of the first
+    /// day.
+    pub fn is_open(&self, hour: usize) -> bool {
+        self.open[hour % HOURS_PER_DAY]
+    }
+}
+
+/// Expected number of customers' orders per hour of a day.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ArrivalRates {
+    rates: [f64; HOURS_PER_DAY],
+}
+
+impl ArrivalRates {
+    /// Create arrival rates of the hours of a day.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any rate is negative or not finite.
+    pub fn new(rates: [f64; HOURS_PER_DAY]) -> Self {
+        assert!(
+            rates.iter().all(|rate| rate.is_finite() && *rate >= 0.0),
+            "Arrival rate must be a non-negative number!"
+        );
+
+        ArrivalRates { rates }
+    }
+
+    /// Return the arrival rate of the simulated hour, counted from midnight of the first day.
+    pub fn rate(&self, hour: usize) -> f64 {
+        self.rates[hour % HOURS_PER_DAY]
+    }
+}
+
+/// Outcome of one simulated hour.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HourReport<T> {
+    /// The simulated hour, counted from midnight of the first day.
+    pub hour: usize,
+    /// Number of open farms.
+    pub open_farms: usize,
+    /// Number of arrived orders.
+    pub orders: usize,
+    /// Number of orders no open farm had a capacity for.
+    pub rejected: usize,
+    /// Number of orders assigned to every open farm.
+    pub loads: BTreeMap<T, usize>,
+}
+
+impl<T: PrimInt + Unsigned> HourReport<T> {
+    /// Return share of rejected orders, zero if there were no orders.
+    pub fn rejection_rate(&self) -> f64 {
+        if self.orders == 0 {
+            return 0.0;
+        }
+
+        self.rejected as f64 / self.orders as f64
+    }
+
+    /// Return farms of all assigned orders, as recorded by `LoadForecaster::record_period`.
+    pub fn assigned_farms(&self) -> impl Iterator<Item = T> + '_ {
+        self.loads
+            .iter()
+            .flat_map(|(farm, load)| std::iter::repeat_n(*farm, *load))
+    }
+}
+
+/// Simulation of farms with schedules and an hourly capacity.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Simulation<T> {
+    farms: Vec<(T, Schedule)>,
+    capacity: usize,
+    arrivals: ArrivalRates,
+}
+
+impl<T: PrimInt + Unsigned + 'static> Simulation<T> {
+    /// Create a new simulation of the farms with their schedules, each taking up to `capacity`
+    /// orders per hour.
+    pub fn new(farms: Vec<(T, Schedule)>, capacity: usize, arrivals: ArrivalRates) -> Self {
+        Simulation {
+            farms,
+            capacity,
+            arrivals,
+        }
+    }
+
+    /// Simulate the hours, customers' orders are placed in turns from the customers' positions.
+    ///
+    /// Fractional arrival rates are carried over to the next hours, so the number of orders
+    /// follows the rates in the long run.
+    pub fn run(&self, customers: &[T], hours: usize) -> Vec<HourReport<T>> {
+        let mut reports = Vec::with_capacity(hours);
+        let mut next_customer = 0;
+        let mut carried = 0.0;
+
+        for hour in 0..hours {
+            let expected = self.arrivals.rate(hour) + carried;
+            let orders = if customers.is_empty() {
+                0
+            } else {
+                expected.floor() as usize
+            };
+            carried = expected - orders as f64;
+
+            let positions: Vec<T> = (0..orders)
+                .map(|order| customers[(next_customer + order) % customers.len()])
+                .collect();
+            next_customer = (next_customer + orders) % customers.len().max(1);
+
+            reports.push(self.simulate_hour(hour, &positions));
+        }
+
+        reports
+    }
+
+    fn simulate_hour(&self, hour: usize, positions: &[T]) -> HourReport<T> {
+        let open: Vec<T> = self
+            .farms
+            .iter()
+            .filter(|(_, schedule)| schedule.is_open(hour))
+            .map(|(farm, _)| *farm)
+            .collect();
+
+        let mut loads: BTreeMap<T, usize> = open.iter().map(|farm| (*farm, 0)).collect();
+        let round = FoodDeliverySystem::new(open).assign_round_capped(positions, self.capacity);
+
+        for assignment in round.assignments.iter().flatten() {
+            *loads.entry(assignment.farm).or_insert(0) += 1;
+        }
+
+        HourReport {
+            hour,
+            open_farms: loads.len(),
+            orders: positions.len(),
+            rejected: round.unassigned,
+            loads,
+        }
+    }
+}
+
+/// Return load of the farm in every hour, zero while it was closed.
+pub fn load_curve<T: PrimInt + Unsigned>(reports: &[HourReport<T>], farm: T) -> Vec<usize> {
+    reports
+        .iter()
+        .map(|report| report.loads.get(&farm).copied().unwrap_or(0))
+        .collect()
+}
+
+/// Return share of rejected orders in every hour.
+pub fn rejection_curve<T: PrimInt + Unsigned>(reports: &[HourReport<T>]) -> Vec<f64> {
+    reports
+        .iter()
+        .map(|report| report.rejection_rate())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_curve, rejection_curve, ArrivalRates, Schedule, Simulation};
+    use crate::forecast::LoadForecaster;
+
+    #[test]
+    fn schedules() {
+        let night = Schedule::hours(22, 2);
+
+        assert!(night.is_open(23));
+        assert!(night.is_open(1));
+        assert!(!night.is_open(2));
+        assert!(night.is_open(24 + 22));
+        assert!(!night.is_open(12));
+
+        // Opening and closing at the same hour means open all day.
+        assert_eq!(Schedule::always_open(), Schedule::hours(5, 5));
+    }
+
+    #[test]
+    fn fractional_rates() {
+        let simulation: Simulation<u8> = Simulation::new(
+            vec![(0, Schedule::always_open())],
+            10,
+            ArrivalRates::new([0.5; 24]),
+        );
+
+        let reports = simulation.run(&[1, 2, 3], 48);
+        let orders: usize = reports.iter().map(|report| report.orders).sum();
+
+        assert_eq!(24, orders);
+        assert_eq!(vec![0, 1, 0, 1], load_curve(&reports[..4], 0));
+        assert!(rejection_curve(&reports).iter().all(|rate| *rate == 0.0));
+
+        // Without customers there are no orders at all.
+        assert!(simulation
+            .run(&[], 5)
+            .iter()
+            .all(|report| report.orders == 0));
+    }
+
+    #[test]
+    fn closed_farms_reject_orders() {
+        let simulation: Simulation<u8> = Simulation::new(
+            vec![(0, Schedule::hours(9, 17)), (128, Schedule::hours(12, 17))],
+            2,
+            ArrivalRates::new([3.0; 24]),
+        );
+
+        let reports = simulation.run(&[1, 129, 130], 24);
+
+        assert_eq!(0, reports[8].open_farms);
+        assert_eq!(1.0, reports[8].rejection_rate());
+        assert_eq!(1, reports[10].rejected);
+        assert_eq!(0, reports[13].rejected);
+        assert_eq!(2, reports[13].loads[&128]);
+
+        let mut forecaster: LoadForecaster<u8> = LoadForecaster::new(1.0, 0, 24);
+        for report in reports.iter() {
+            forecaster.record_period(report.assigned_farms());
+        }
+        assert_eq!(Some(0.0), forecaster.forecast_load(128, 1));
+    }
+}
@@ -0,0 +1,643 @@
+//! Bit constraints derived from XOR distance inequalities.
+//!
+//! Every inequality `a ^ x < b ^ x`, where `x` is the unknown position, fixes the most significant
+//! bit in which `a` and `b` differ to the bit value of `a`. A `ConstraintSet` collects such
+//! restrictions from any inequality stream, not only from closest points of an `XorDistance`,
+//! and the `Bits` it forms are the solution, every position matching them satisfies all the
+//! inequalities. It is available as `PrefixConstraintSolver` too, for other metrics and
+//! applications. Inequalities of wide `XorKey` keys are collected by a set created by `for_keys`.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::solver::ConstraintSet;
+//!
+//! let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+//!
+//! // Peer `4` ranked closer than `0` and `1` closer than `3`.
+//! constraints.ingest(vec![(4, 0), (1, 3)].into_iter()).unwrap();
+//!
+//! let x = constraints.position();
+//! assert!(4 ^ x < 0 ^ x);
+//! assert!(1 ^ x < 3 ^ x);
+//! ```
+
+use crate::bitops::BitOps;
+use crate::bits::Bits;
+use crate::error::BitsError;
+use crate::key::XorKey;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::error::Error;
+use core::fmt;
+use num_traits::{PrimInt, Unsigned};
+
+/// Inequality `a ^ x < b ^ x` contradicting the bit restriction formed by a previous inequality,
+/// where `x` is the position being searched for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Conflict<T> {
+    /// The contradicting inequality pair `(a, b)`.
+    pub inequality: (T, T),
+    /// Index of the bit the inequality restricts.
+    pub bit_index: usize,
+    /// Bit value required by the contradicting inequality.
+    pub required: bool,
+    /// The inequality pair which decided the bit value first.
+    pub decided_by: (T, T),
+}
+
+/// Error of adding an inequality `a ^ x < b ^ x` to a `ConstraintSet`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InequalityError<T> {
+    /// The inequality contradicts the bit restriction formed by a previous inequality.
+    Conflict(Conflict<T>),
+    /// Both points of the inequality are the point, no position satisfies it.
+    EqualPoints {
+        /// The point compared with itself.
+        point: T,
+    },
+}
+
+impl<T> fmt::Display for InequalityError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InequalityError::Conflict(_) => {
+                f.write_str("Inequality contradicts a previous restriction!")
+            }
+            InequalityError::EqualPoints { .. } => {
+                f.write_str("Points of an inequality must differ!")
+            }
+        }
+    }
+}
+
+impl<T: fmt::Debug> Error for InequalityError<T> {}
+
+/// Solver of "a is closer than b" constraints `a ^ x < b ^ x`, producing the `Bits` of every
+/// position `x` satisfying them, the `ConstraintSet` under the name of its role.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::solver::PrefixConstraintSolver;
+///
+/// let mut solver: PrefixConstraintSolver<u8> = PrefixConstraintSolver::new();
+/// solver.ingest(vec![(4, 0), (1, 3)].into_iter()).unwrap();
+///
+/// assert_eq!("?????10?", solver.into_bits().to_string());
+/// ```
+pub type PrefixConstraintSolver<T> = ConstraintSet<T>;
+
+/// Function returning the bit restricted by an inequality, of the integers or of the keys.
+type RestrictedBit<T> = fn((T, T)) -> Result<(usize, bool), BitsError>;
+
+/// Bit restrictions of a position formed by inequalities `a ^ x < b ^ x`.
+#[derive(Clone, Debug)]
+pub struct ConstraintSet<T> {
+    bits: Bits,
+    // Inequality which decided each bit first.
+    decided_by: Vec<Option<(T, T)>>,
+    // Bit restricted by an inequality, see `restricted_bit`.
+    restricted_bit: RestrictedBit<T>,
+}
+
+impl<T: PrimInt + Unsigned> Default for ConstraintSet<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PrimInt + Unsigned> ConstraintSet<T> {
+    /// Create a new constraint set without any restriction.
+    pub fn new() -> Self {
+        ConstraintSet {
+            bits: Bits::new::<T>(),
+            decided_by: vec![None; Bits::bit_size::<T>()],
+            restricted_bit: restricted_bit::<T>,
+        }
+    }
+
+    /// Return the smallest position satisfying all restrictions, undecided bits are zero.
+    pub fn position(&self) -> T {
+        // Asking for the same number type as we are bit-representing is fine.
+        self.bits.form_zero_padded_number::<T>().unwrap()
+    }
+
+    /// Return the mask of undecided bits, the bits free to take any value.
+    pub fn free_mask(&self) -> T {
+        let mut free = T::zero();
+
+        for bit_index in 0..self.bits.size() {
+            if !self.bits.is_bit_decided(bit_index) {
+                free.set_bit(bit_index);
+            }
+        }
+
+        free
+    }
+
+    /// Return an iterator over all positions satisfying the restrictions, in ascending order.
+    ///
+    /// There are `2^k` of them for `k` undecided bits, so collecting them is only feasible for
+    /// well constrained sets.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::solver::ConstraintSet;
+    ///
+    /// let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+    /// constraints.ingest((0..8).map(|bit| (0, 1 << bit)).skip(2)).unwrap();
+    ///
+    /// assert_eq!(vec![0, 1, 2, 3], constraints.solutions().collect::<Vec<u8>>());
+    /// ```
+    pub fn solutions(&self) -> Solutions<T> {
+        Solutions {
+            position: self.position(),
+            free: self.free_mask(),
+            next: Some(T::zero()),
+        }
+    }
+
+    /// Return the smallest and the largest position satisfying all restrictions, undecided bits
+    /// all zero and all one respectively.
+    ///
+    /// Not every position between them satisfies the restrictions, see `ranges`.
+    pub fn bounds(&self) -> (T, T) {
+        let position = self.position();
+
+        (position, position | self.free_mask())
+    }
+
+    /// Return an iterator over the disjoint ranges `(min, max)` of positions satisfying the
+    /// restrictions, inclusive and in ascending order.
+    ///
+    /// Undecided bits below the least significant decided bit make up each range, the other
+    /// undecided bits double the number of ranges.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::solver::ConstraintSet;
+    ///
+    /// let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+    /// // Bits other than the zeroth and the second one are zero.
+    /// constraints.ingest([1, 3, 4, 5, 6, 7].iter().map(|bit| (0, 1 << bit))).unwrap();
+    ///
+    /// assert_eq!((0, 0b101), constraints.bounds());
+    /// assert_eq!(
+    ///     vec![(0, 0b1), (0b100, 0b101)],
+    ///     constraints.ranges().collect::<Vec<_>>()
+    /// );
+    /// ```
+    pub fn ranges(&self) -> Ranges<T> {
+        let free = self.free_mask();
+
+        // Trailing run of the undecided bits, spanning every range.
+        let span = T::mask_low(free.trailing_ones() as usize);
+
+        Ranges {
+            starts: Solutions {
+                position: self.position(),
+                free: free & !span,
+                next: Some(T::zero()),
+            },
+            span,
+        }
+    }
+}
+
+impl<K: XorKey> ConstraintSet<K> {
+    /// Create a new constraint set of keys without any restriction.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::solver::ConstraintSet;
+    ///
+    /// let mut constraints: ConstraintSet<[u8; 2]> = ConstraintSet::for_keys();
+    /// constraints.ingest(vec![([0x80, 0], [0, 0])].into_iter()).unwrap();
+    ///
+    /// assert_eq!(Some(true), constraints.bits().get_bit(15));
+    /// assert_eq!(Ok([0x80, 0]), constraints.bits().form_zero_padded_key());
+    /// ```
+    pub fn for_keys() -> Self {
+        ConstraintSet {
+            bits: Bits::with_size(K::BIT_SIZE),
+            decided_by: vec![None; K::BIT_SIZE],
+            restricted_bit: restricted_key_bit::<K>,
+        }
+    }
+}
+
+impl<T: Copy> ConstraintSet<T> {
+    /// Return the bit restrictions formed so far.
+    pub fn bits(&self) -> &Bits {
+        &self.bits
+    }
+
+    /// Return the bit restrictions formed, the solution of the inequalities.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::solver::ConstraintSet;
+    ///
+    /// let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+    /// constraints.ingest(vec![(4, 0), (1, 3)].into_iter()).unwrap();
+    ///
+    /// assert_eq!("?????10?", constraints.into_bits().to_string());
+    /// ```
+    pub fn into_bits(self) -> Bits {
+        self.bits
+    }
+
+    /// Return the inequality which decided the bit first, `None` if the bit is undecided.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bit_index` is not less than the bit size of `T`.
+    pub fn decided_by(&self, bit_index: usize) -> Option<(T, T)> {
+        self.decided_by[bit_index]
+    }
+
+    /// Add restriction of the inequality `a ^ x < b ^ x`.
+    ///
+    /// Returns `Err(InequalityError::Conflict)` if it contradicts a restriction added before and
+    /// `Err(InequalityError::EqualPoints)` if `a` equals `b`, as no position satisfies such an
+    /// inequality. The set is left unchanged then.
+    pub fn add(&mut self, inequality: (T, T)) -> Result<(), InequalityError<T>> {
+        let (bit_index, required) =
+            (self.restricted_bit)(inequality).map_err(|_| InequalityError::EqualPoints {
+                point: inequality.0,
+            })?;
+
+        if self
+            .bits
+            .set_bit_within_constrains(bit_index, required)
+            .is_err()
+        {
+            return Err(InequalityError::Conflict(Conflict {
+                inequality,
+                bit_index,
+                required,
+                // A conflict is only possible with an already decided bit.
+                decided_by: self.decided_by[bit_index].unwrap(),
+            }));
+        }
+
+        if self.decided_by[bit_index].is_none() {
+            self.decided_by[bit_index] = Some(inequality);
+        }
+
+        Ok(())
+    }
+
+    /// Add restrictions of all inequalities, stopping at the first error, see `add`.
+    pub fn ingest<I>(&mut self, inequalities: I) -> Result<(), InequalityError<T>>
+    where
+        I: Iterator<Item = (T, T)>,
+    {
+        for inequality in inequalities {
+            self.add(inequality)?;
+        }
+
+        Ok(())
+    }
+
+    /// Add restrictions of all inequalities, skipping and collecting errors of the failing ones,
+    /// see `add`.
+    pub fn ingest_collecting_conflicts<I>(&mut self, inequalities: I) -> Vec<InequalityError<T>>
+    where
+        I: Iterator<Item = (T, T)>,
+    {
+        inequalities
+            .filter_map(|inequality| self.add(inequality).err())
+            .collect()
+    }
+}
+
+/// Iterator over all positions satisfying a `ConstraintSet`, in ascending order.
+pub struct Solutions<T> {
+    // The smallest solution and the mask of its free bits.
+    position: T,
+    free: T,
+    // Free bits of the next solution, `None` once all were returned.
+    next: Option<T>,
+}
+
+impl<T: PrimInt + Unsigned> Solutions<T> {
+    /// Return an iterator over no positions at all.
+    pub fn empty() -> Self {
+        Solutions {
+            position: T::zero(),
+            free: T::zero(),
+            next: None,
+        }
+    }
+}
+
+impl<T: PrimInt + Unsigned> Iterator for Solutions<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let subset = self.next?;
+
+        // Setting all bits but the free ones and incrementing carries over to the next subset of
+        // the free bits, the last subset is the full mask.
+        self.next = if subset == self.free {
+            None
+        } else {
+            Some(((subset | !self.free) + T::one()) & self.free)
+        };
+
+        Some(self.position | subset)
+    }
+}
+
+/// Iterator over disjoint ranges of positions satisfying a `ConstraintSet`, in ascending order.
+pub struct Ranges<T> {
+    starts: Solutions<T>,
+    span: T,
+}
+
+impl<T: PrimInt + Unsigned> Ranges<T> {
+    /// Return an iterator over no ranges at all.
+    pub fn empty() -> Self {
+        Ranges {
+            starts: Solutions::empty(),
+            span: T::zero(),
+        }
+    }
+}
+
+impl<T: PrimInt + Unsigned> Iterator for Ranges<T> {
+    type Item = (T, T);
+
+    fn next(&mut self) -> Option<(T, T)> {
+        self.starts.next().map(|start| (start, start | self.span))
+    }
+}
+
+/// Return index and required value of the bit restricted by the inequality `a ^ x < b ^ x`,
+/// where `x` is the position being searched for.
+///
+/// Returns `Err(BitsError::EqualValues)` if `a` equals `b`, as no bit is restricted then.
+pub fn restricted_bit<T: PrimInt + Unsigned>((a, b): (T, T)) -> Result<(usize, bool), BitsError> {
+    if a == b {
+        return Err(BitsError::EqualValues);
+    }
+
+    let xor_distance: T = a ^ b;
+    let bit_size = T::zero().count_zeros();
+
+    // Index of the first left hand-side bit in which `a` and `b` differ. The index starts by 0.
+    let bit_index = (bit_size - xor_distance.leading_zeros() - 1) as usize;
+
+    // As `a` is closer to the position we are searching for then `b`, we need to restrict
+    // to bit value of `a`.
+    Ok((bit_index, a.is_bit_set(bit_index)))
+}
+
+/// Return index and required value of the bit restricted by the inequality `a ^ x < b ^ x` of
+/// keys, see `restricted_bit`.
+fn restricted_key_bit<K: XorKey>((a, b): (K, K)) -> Result<(usize, bool), BitsError> {
+    if a == b {
+        return Err(BitsError::EqualValues);
+    }
+
+    let bit_index = K::BIT_SIZE - 1 - a.xor(&b).leading_zeros();
+
+    Ok((bit_index, a.is_bit_set(bit_index)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{restricted_bit, Conflict, ConstraintSet, InequalityError, Ranges, Solutions};
+    use crate::error::BitsError;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn solution_bits() {
+        let mut constraints: ConstraintSet<u16> = ConstraintSet::new();
+        constraints
+            .ingest([(0x100, 0), (0x10, 0x30), (2, 3)].iter().copied())
+            .unwrap();
+
+        assert_eq!(Some((0x100, 0)), constraints.decided_by(8));
+        assert_eq!(None, constraints.decided_by(1));
+        assert_eq!(Some((0x10, 0x30)), constraints.decided_by(5));
+        assert_eq!(Some((2, 3)), constraints.decided_by(0));
+
+        // Redundant restrictions keep the first deciding inequality.
+        let mut fork = constraints.clone();
+        fork.add((0x1FF, 0x0FF)).unwrap();
+        assert_eq!(Some((0x100, 0)), fork.decided_by(8));
+
+        let position = constraints.position();
+        let bits = constraints.into_bits();
+
+        assert_eq!(Some(true), bits.get_bit(8));
+        assert_eq!(Some(false), bits.get_bit(0));
+        assert_eq!(None, bits.get_bit(1));
+        assert_eq!(position, bits.form_zero_padded_number::<u16>().unwrap());
+    }
+
+    #[test]
+    fn restricted_bits() {
+        assert_eq!(Ok((2, true)), restricted_bit((0b110u8, 0b010)));
+        assert_eq!(Ok((0, false)), restricted_bit((0u8, 1)));
+        assert_eq!(Ok((63, true)), restricted_bit((u64::MAX, 0)));
+
+        // Bits at the width boundaries of `u128`.
+        assert_eq!(Ok((127, true)), restricted_bit((1u128 << 127, 0)));
+        assert_eq!(
+            Ok((127, false)),
+            restricted_bit(((1u128 << 127) - 1, 1 << 127))
+        );
+        assert_eq!(
+            Ok((64, true)),
+            restricted_bit((1u128 << 64, u64::MAX as u128))
+        );
+        assert_eq!(Ok((63, false)), restricted_bit((0u128, 1 << 63)));
+        assert_eq!(Ok((0, true)), restricted_bit((u128::MAX, u128::MAX - 1)));
+    }
+
+    #[test]
+    fn ingest() {
+        let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+
+        constraints
+            .ingest([(0b1000, 0), (0b1000, 0b1100)].iter().copied())
+            .unwrap();
+
+        assert_eq!(Some(true), constraints.bits().get_bit(3));
+        assert_eq!(Some(false), constraints.bits().get_bit(2));
+        assert_eq!(0b1000, constraints.position());
+
+        let conflict = constraints.ingest([(0, 0b1001)].iter().copied());
+
+        assert_eq!(
+            Err(InequalityError::Conflict(Conflict {
+                inequality: (0, 0b1001),
+                bit_index: 3,
+                required: false,
+                decided_by: (0b1000, 0),
+            })),
+            conflict
+        );
+        // The set is left unchanged.
+        assert_eq!(0b1000, constraints.position());
+    }
+
+    #[test]
+    fn ingest_collecting_conflicts() {
+        let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+
+        let conflicts = constraints
+            .ingest_collecting_conflicts([(1, 2), (2, 1), (0, 1), (3, 2)].iter().copied());
+
+        let conflicts: Vec<Conflict<u8>> = conflicts
+            .into_iter()
+            .map(|error| match error {
+                InequalityError::Conflict(conflict) => conflict,
+                error => panic!("Unexpected {:?}!", error),
+            })
+            .collect();
+
+        assert_eq!(2, conflicts.len());
+        assert_eq!((2, 1), conflicts[0].inequality);
+        assert_eq!((3, 2), conflicts[1].inequality);
+        assert_eq!((1, 2), conflicts[0].decided_by);
+        assert_eq!((0, 1), conflicts[1].decided_by);
+    }
+
+    #[test]
+    fn solutions() {
+        let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+        constraints
+            .ingest([(0b1000, 0), (1, 0b11)].iter().copied())
+            .unwrap();
+
+        let expected: Vec<u8> = (0..=u8::MAX)
+            .filter(|x| x & 0b1000 != 0 && x & 0b10 == 0)
+            .collect();
+        assert_eq!(0b1111_0101, constraints.free_mask());
+        assert_eq!(expected, constraints.solutions().collect::<Vec<u8>>());
+
+        // No restrictions are satisfied by all positions.
+        assert_eq!(256, ConstraintSet::<u8>::new().solutions().count());
+        assert_eq!(0, Solutions::<u8>::empty().count());
+    }
+
+    #[test]
+    fn solutions_satisfy_inequalities() {
+        // Reported positions satisfy the accepted inequalities and none of the conflicting ones.
+        let mut rng = StdRng::seed_from_u64(1536);
+
+        for _ in 0..200 {
+            let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+            let mut accepted = Vec::new();
+            let mut conflicting = Vec::new();
+
+            for _ in 0..rng.gen_range(0, 12) {
+                let (a, b): (u8, u8) = rng.gen();
+
+                if a == b {
+                    continue;
+                }
+
+                match constraints.add((a, b)) {
+                    Ok(()) => accepted.push((a, b)),
+                    Err(_) => conflicting.push((a, b)),
+                }
+            }
+
+            let expected: Vec<u8> = (0..=u8::MAX)
+                .filter(|x| accepted.iter().all(|(a, b)| a ^ x < b ^ x))
+                .collect();
+
+            assert_eq!(expected, constraints.solutions().collect::<Vec<u8>>());
+            assert!(expected
+                .iter()
+                .all(|x| conflicting.iter().all(|(a, b)| a ^ x > b ^ x)));
+        }
+    }
+
+    #[test]
+    fn ranges() {
+        let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+        constraints
+            .ingest([(0b1000, 0), (1, 0b11)].iter().copied())
+            .unwrap();
+
+        let ranges: Vec<(u8, u8)> = constraints.ranges().collect();
+
+        assert_eq!((0b1000, 0b1111_1101), constraints.bounds());
+        assert_eq!(32, ranges.len());
+        assert_eq!((0b1000, 0b1001), ranges[0]);
+        assert_eq!((0b1100, 0b1101), ranges[1]);
+
+        // Ranges cover exactly the solutions.
+        let covered: Vec<u8> = ranges.iter().flat_map(|(min, max)| *min..=*max).collect();
+        assert_eq!(constraints.solutions().collect::<Vec<u8>>(), covered);
+
+        assert_eq!(
+            vec![(0, u8::MAX)],
+            ConstraintSet::<u8>::new().ranges().collect::<Vec<_>>()
+        );
+        assert_eq!(0, Ranges::<u8>::empty().count());
+    }
+
+    #[test]
+    fn keys_same_as_integers() {
+        let mut rng = StdRng::seed_from_u64(1506);
+
+        for _ in 0..200 {
+            let mut numbers: ConstraintSet<u16> = ConstraintSet::new();
+            let mut keys: ConstraintSet<[u8; 2]> = ConstraintSet::for_keys();
+
+            for _ in 0..rng.gen_range(0, 12) {
+                let (a, b): (u16, u16) = rng.gen();
+                let keys_result = keys.add((a.to_be_bytes(), b.to_be_bytes()));
+
+                assert_eq!(numbers.add((a, b)).is_ok(), keys_result.is_ok());
+            }
+
+            assert_eq!(numbers.bits(), keys.bits());
+        }
+    }
+
+    #[test]
+    fn identical_points() {
+        assert_eq!(Err(BitsError::EqualValues), restricted_bit((5u8, 5)));
+
+        let mut constraints: ConstraintSet<u8> = ConstraintSet::new();
+        constraints.add((1, 0)).unwrap();
+
+        let error = constraints.add((5, 5)).unwrap_err();
+        assert_eq!(InequalityError::EqualPoints { point: 5 }, error);
+        assert_eq!("Points of an inequality must differ!", error.to_string());
+        assert_eq!(
+            Err(InequalityError::EqualPoints { point: 3 }),
+            constraints.ingest([(2, 0), (3, 3), (4, 0)].iter().copied())
+        );
+
+        // Inequalities before the failing one are kept, the failing one restricts nothing.
+        assert_eq!(Some((2, 0)), constraints.decided_by(1));
+        assert_eq!(None, constraints.decided_by(2));
+        assert_eq!(
+            vec![InequalityError::EqualPoints { point: 0 }],
+            constraints.ingest_collecting_conflicts([(0, 0), (4, 0)].iter().copied())
+        );
+        assert_eq!(Some((4, 0)), constraints.decided_by(2));
+    }
+}
@@ -0,0 +1,147 @@
+//! Entropy and balance statistics of `Bits` solution spaces.
+//!
+//! Every completion of the undecided bits of a pattern is equally likely, the statistics describe
+//! how much freedom is left and how evenly the completions spread over prefix buckets, e.g. for
+//! privacy reports of positions recovered by the reverse solver.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::bits::Bits;
+//! use xor_distance_exercise::stats::solution_stats;
+//!
+//! let mut pattern = Bits::new::<u8>();
+//! pattern.set_bit(7, true);
+//! pattern.set_bit(0, false);
+//!
+//! let stats = solution_stats(&pattern, 2);
+//!
+//! assert_eq!(6.0, stats.entropy);
+//! assert_eq!(vec![0.0, 0.0, 0.5, 0.5], stats.buckets);
+//! assert_eq!(1.0, stats.skew);
+//! ```
+
+use crate::bits::Bits;
+
+/// Maximal prefix length of buckets, bounding the buckets count.
+pub const MAX_BUCKET_BITS: usize = 20;
+
+/// Statistics of the completions of a `Bits` pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SolutionStats {
+    /// Entropy of the completions in bits.
+    pub entropy: f64,
+    /// Share of the completions per bucket of the highest bits, indexed by the prefix value.
+    pub buckets: Vec<f64>,
+    /// Kullback-Leibler divergence of the buckets distribution from the uniform one in bits, zero
+    /// for perfectly balanced buckets.
+    pub skew: f64,
+}
+
+/// Return statistics of the pattern completions with buckets by the `prefix_len` highest bits.
+///
+/// # Panics
+///
+/// Panics if `prefix_len` is greater than the pattern size or `MAX_BUCKET_BITS`.
+pub fn solution_stats(pattern: &Bits, prefix_len: usize) -> SolutionStats {
+    let buckets = bucket_distribution(pattern, prefix_len);
+
+    SolutionStats {
+        entropy: entropy(pattern),
+        skew: skew(&buckets),
+        buckets,
+    }
+}
+
+/// Return entropy of the pattern completions in bits, the number of undecided bits.
+pub fn entropy(pattern: &Bits) -> f64 {
+    pattern.undecided_count() as f64
+}
+
+/// Return share of the pattern completions per bucket of the `prefix_len` highest bits, indexed
+/// by the prefix value.
+///
+/// # Panics
+///
+/// Panics if `prefix_len` is greater than the pattern size or `MAX_BUCKET_BITS`.
+pub fn bucket_distribution(pattern: &Bits, prefix_len: usize) -> Vec<f64> {
+    assert!(
+        prefix_len <= pattern.size() && prefix_len <= MAX_BUCKET_BITS,
+        "Prefix length is out of range!"
+    );
+
+    let lowest = pattern.size() - prefix_len;
+    let undecided = pattern
+        .undecided_indices()
+        .filter(|index| *index >= lowest)
+        .count();
+    // Completions spread evenly over the buckets consistent with the decided prefix bits.
+    let share = 1.0 / (1u64 << undecided) as f64;
+
+    (0..1usize << prefix_len)
+        .map(|bucket| {
+            let consistent = (0..prefix_len).all(|bit| {
+                pattern
+                    .get_bit(lowest + bit)
+                    .is_none_or(|value| value == ((bucket >> bit) & 1 == 1))
+            });
+
+            if consistent {
+                share
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Return Kullback-Leibler divergence of the distribution from the uniform one in bits.
+pub fn skew(distribution: &[f64]) -> f64 {
+    let uniform = 1.0 / distribution.len() as f64;
+
+    distribution
+        .iter()
+        .filter(|share| **share > 0.0)
+        .map(|share| share * (share / uniform).log2())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bucket_distribution, entropy, skew, solution_stats};
+    use crate::bits::Bits;
+
+    #[test]
+    fn undecided_pattern() {
+        let stats = solution_stats(&Bits::new::<u16>(), 3);
+
+        assert_eq!(16.0, stats.entropy);
+        assert_eq!(vec![0.125; 8], stats.buckets);
+        assert_eq!(0.0, stats.skew);
+    }
+
+    #[test]
+    fn decided_pattern() {
+        let mut pattern = Bits::new::<u8>();
+        for index in 0..8 {
+            pattern.set_bit(index, index % 2 == 0);
+        }
+
+        assert_eq!(0.0, entropy(&pattern));
+
+        let buckets = bucket_distribution(&pattern, 4);
+        assert_eq!(1.0, buckets[0b0101]);
+        assert_eq!(1.0, buckets.iter().sum::<f64>());
+        assert_eq!(4.0, skew(&buckets));
+
+        assert_eq!(vec![1.0], bucket_distribution(&pattern, 0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Prefix length is out of range!")]
+    fn prefix_out_of_range() {
+        bucket_distribution(&Bits::new::<u8>(), 9);
+    }
+}
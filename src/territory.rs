@@ -0,0 +1,261 @@
+//! Service territories of farms as binary-prefix blocks.
+//!
+//! Every position is served by its closest farm by XOR distance, so a farm's territory is a set
+//! of positions which can be listed as aligned blocks sharing a binary prefix, just like CIDR
+//! blocks of IP addresses.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+//!
+//! let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0, 64, 128]);
+//!
+//! let territories = delivery_system.export_territories(16).unwrap();
+//! let blocks = |index: usize| -> Vec<String> {
+//!     territories[index]
+//!         .blocks
+//!         .iter()
+//!         .map(|block| block.to_string())
+//!         .collect()
+//! };
+//!
+//! assert_eq!(vec!["0/2"], blocks(0));
+//! assert_eq!(vec!["64/2"], blocks(1));
+//! assert_eq!(vec!["128/1"], blocks(2));
+//! ```
+
+use crate::bitops::BitOps;
+use crate::delivery_system::FoodDeliverySystem;
+use crate::error::DeliveryError;
+use num_traits::{PrimInt, Unsigned};
+use std::fmt;
+
+/// Block of all positions sharing the highest `len` bits with `prefix`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct PrefixBlock<T> {
+    /// The first position of the block, bits beyond the prefix length are zero.
+    pub prefix: T,
+    /// Number of the highest bits shared by all positions of the block.
+    pub len: usize,
+}
+
+impl<T: PrimInt + Unsigned> PrefixBlock<T> {
+    /// Return whether the block contains the position.
+    pub fn contains(&self, position: T) -> bool {
+        let bit_size = T::zero().count_zeros() as usize;
+
+        self.len == 0 || (position ^ self.prefix) >> (bit_size - self.len) == T::zero()
+    }
+}
+
+/// Formats the block as a CIDR-like `prefix/len` string.
+impl<T: fmt::Display> fmt::Display for PrefixBlock<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.prefix, self.len)
+    }
+}
+
+/// Positions served by a farm.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Territory<T> {
+    /// The serving farm.
+    pub farm: T,
+    /// Minimal set of prefix blocks covering the territory, in order of positions.
+    pub blocks: Vec<PrefixBlock<T>>,
+}
+
+impl<T: PrimInt + Unsigned + 'static> FoodDeliverySystem<T> {
+    /// Return territory of every farm, in order of farms, as the minimal sets of prefix blocks.
+    ///
+    /// Territories whose farms agree on high bits but differ on lower ones are striped, the
+    /// number of blocks grows exponentially with the number of agreeing bits then. Return
+    /// `Err(DeliveryError::TooManyBlocks)` if more than `max_blocks` blocks would be needed in
+    /// total.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+    ///
+    /// // Both farms share all bits but the lowest one, so territories are striped.
+    /// let delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![2, 3]);
+    ///
+    /// assert!(delivery_system.export_territories(1000).is_err());
+    /// ```
+    pub fn export_territories(
+        &self,
+        max_blocks: usize,
+    ) -> Result<Vec<Territory<T>>, DeliveryError> {
+        let mut farms: Vec<T> = self.xor_distance().points().copied().collect();
+        farms.sort();
+        farms.dedup();
+
+        let mut territories: Vec<Territory<T>> = farms
+            .iter()
+            .map(|farm| Territory {
+                farm: *farm,
+                blocks: Vec::new(),
+            })
+            .collect();
+
+        if !farms.is_empty() {
+            let mut budget = max_blocks;
+            let whole = PrefixBlock {
+                prefix: T::zero(),
+                len: 0,
+            };
+
+            cover(whole, &farms, &farms, &mut territories, &mut budget)?;
+        }
+
+        Ok(territories)
+    }
+}
+
+/// Add the block to territories, splitting it into halves until every part is served by a single
+/// farm.
+///
+/// Candidates are the farms sharing the highest bits closest to the block prefix, no other farm
+/// can be the closest one to any position of the block.
+fn cover<T: PrimInt + Unsigned>(
+    block: PrefixBlock<T>,
+    candidates: &[T],
+    farms: &[T],
+    territories: &mut [Territory<T>],
+    budget: &mut usize,
+) -> Result<(), DeliveryError> {
+    if let [farm] = candidates {
+        if *budget == 0 {
+            return Err(DeliveryError::TooManyBlocks);
+        }
+        *budget -= 1;
+
+        // Farms and territories are in the same order.
+        let index = farms.binary_search(farm).unwrap();
+        territories[index].blocks.push(block);
+
+        return Ok(());
+    }
+
+    // Distinct candidates sharing the prefix length bits differ in some lower bit.
+    let bit_index = T::zero().count_zeros() as usize - block.len - 1;
+
+    // Candidates are sorted, so the ones with the bit unset come first.
+    let split = candidates
+        .iter()
+        .position(|farm| farm.is_bit_set(bit_index))
+        .unwrap_or(candidates.len());
+    let (unset, set) = candidates.split_at(split);
+
+    for value in [false, true].iter() {
+        let mut prefix = block.prefix;
+        if *value {
+            prefix.set_bit(bit_index);
+        }
+
+        let half = PrefixBlock {
+            prefix,
+            len: block.len + 1,
+        };
+
+        let closer = if *value { set } else { unset };
+
+        // Positions further from all candidates in the bit keep all of them.
+        let half_candidates = if closer.is_empty() {
+            candidates
+        } else {
+            closer
+        };
+
+        cover(half, half_candidates, farms, territories, budget)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrefixBlock;
+    use crate::delivery_system::FoodDeliverySystem;
+    use crate::error::DeliveryError;
+
+    #[test]
+    fn territories_cover_all_positions() {
+        let delivery_system: FoodDeliverySystem<u8> =
+            FoodDeliverySystem::new(vec![3, 17, 18, 96, 200, 201]);
+
+        let territories = delivery_system.export_territories(usize::MAX).unwrap();
+
+        for position in 0..=u8::MAX {
+            let serving: Vec<u8> = territories
+                .iter()
+                .filter(|territory| {
+                    territory
+                        .blocks
+                        .iter()
+                        .any(|block| block.contains(position))
+                })
+                .map(|territory| territory.farm)
+                .collect();
+
+            assert_eq!(delivery_system.closest_farms(position, 1), serving);
+        }
+    }
+
+    #[test]
+    fn minimal_blocks() {
+        let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![2, 3]);
+
+        let territories = delivery_system.export_territories(usize::MAX).unwrap();
+
+        // Positions are split by the lowest bit only.
+        assert_eq!(128, territories[0].blocks.len());
+        assert!(territories[0]
+            .blocks
+            .iter()
+            .all(|block| block.len == 8 && block.prefix % 2 == 0));
+
+        // Whole space is served by a single farm.
+        let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![42]);
+        let territories = delivery_system.export_territories(1).unwrap();
+
+        assert_eq!(
+            vec![PrefixBlock {
+                prefix: 0u8,
+                len: 0
+            }],
+            territories[0].blocks
+        );
+    }
+
+    #[test]
+    fn blocks_limit() {
+        let delivery_system: FoodDeliverySystem<u8> = FoodDeliverySystem::new(vec![0, 128]);
+
+        assert!(delivery_system.export_territories(2).is_ok());
+        assert_eq!(
+            Err(DeliveryError::TooManyBlocks),
+            delivery_system.export_territories(1)
+        );
+        assert!(FoodDeliverySystem::<u8>::new(Vec::new())
+            .export_territories(0)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn cidr_strings() {
+        let block = PrefixBlock {
+            prefix: 0xC0u8,
+            len: 3,
+        };
+
+        assert_eq!("192/3", block.to_string());
+        assert!(block.contains(0xDF));
+        assert!(!block.contains(0xE0));
+    }
+}
@@ -0,0 +1,175 @@
+//! Random case generators and checks of the reverse closest points invariant.
+//!
+//! Cases are `(points, x, count)` triples with the closest points of `x`, which must reverse to a
+//! position having the very same closest points. Downstream code can be property tested against
+//! the invariant the same way the internal random tests are, with any seeded `Rng` reproducing a
+//! failing case.
+//!
+//! Available with the `testing` feature only.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate rand;
+//! extern crate xor_distance_exercise;
+//!
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//! use xor_distance_exercise::testing::{check_reverse, Case};
+//! use xor_distance_exercise::xor_distance::XorDistance;
+//!
+//! let mut rng = StdRng::seed_from_u64(7);
+//!
+//! for case in Case::<u32>::generate(&mut rng, 100).take(20) {
+//!     assert!(case.holds(), "Invariant violated by {:?}!", case);
+//! }
+//!
+//! // Arbitrary closest points lists either don't reverse or reverse consistently.
+//! let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 1, 2, 4, 8]);
+//! assert!(check_reverse(&xor_distance, &[8, 1]));
+//! ```
+
+use crate::xor_distance::XorDistance;
+use num_traits::{PrimInt, Unsigned};
+use rand::distributions::{Distribution, Standard};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+/// Points, a position and a count with the closest points of the position.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Case<T> {
+    /// The points, possibly with duplicates.
+    pub points: Vec<T>,
+    /// The queried position.
+    pub x: T,
+    /// The requested count of points.
+    pub count: usize,
+    /// Closest points of the position, known to be consistent with the points.
+    pub closest: Vec<T>,
+}
+
+impl<T: PrimInt + Unsigned> Case<T> {
+    /// Return a random case of up to `max_points` points.
+    ///
+    /// About a quarter of the points repeat an earlier one, the position is uniformly random or
+    /// equal to one of the points, the count ranges from zero to more than the number of points.
+    pub fn random<R: Rng + ?Sized>(rng: &mut R, max_points: usize) -> Self
+    where
+        Standard: Distribution<T>,
+    {
+        let len = rng.gen_range(0, max_points + 1);
+        let mut points: Vec<T> = Vec::with_capacity(len);
+
+        for _ in 0..len {
+            let point = match points.choose(rng) {
+                Some(point) if rng.gen_range(0, 4) == 0 => *point,
+                _ => rng.gen(),
+            };
+
+            points.push(point);
+        }
+
+        let x = match points.choose(rng) {
+            Some(point) if rng.gen::<bool>() => *point,
+            _ => rng.gen(),
+        };
+        let count = rng.gen_range(0, len + 2);
+        let closest = XorDistance::new(points.clone()).closest(x, count);
+
+        Case {
+            points,
+            x,
+            count,
+            closest,
+        }
+    }
+
+    /// Return an endless iterator over random cases of up to `max_points` points, see `random`.
+    pub fn generate<R: Rng + ?Sized>(
+        rng: &mut R,
+        max_points: usize,
+    ) -> impl Iterator<Item = Self> + '_
+    where
+        Standard: Distribution<T>,
+    {
+        (0..).map(move |_| Self::random(rng, max_points))
+    }
+
+    /// Return whether the closest points reverse to a position with the same closest points.
+    pub fn holds(&self) -> bool {
+        let xor_distance = XorDistance::new(self.points.clone());
+
+        match xor_distance.reverse_closest(&self.closest) {
+            Some(position) => xor_distance.closest(position, self.closest.len()) == self.closest,
+            None => false,
+        }
+    }
+}
+
+/// Return random closest points list of up to `max_count` distinct points, in a random order and
+/// mostly not consistent with any position.
+pub fn arbitrary_closest<T, R>(rng: &mut R, points: &[T], max_count: usize) -> Vec<T>
+where
+    T: PrimInt + Unsigned,
+    R: Rng + ?Sized,
+{
+    let count = rng.gen_range(0, max_count + 1);
+
+    points.choose_multiple(rng, count).cloned().collect()
+}
+
+/// Return whether the closest points either don't reverse at all, or reverse to a position with
+/// the same closest points.
+pub fn check_reverse<T: PrimInt + Unsigned>(xor_distance: &XorDistance<T>, closest: &[T]) -> bool {
+    xor_distance
+        .reverse_closest(closest)
+        .is_none_or(|position| xor_distance.closest(position, closest.len()) == closest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{arbitrary_closest, check_reverse, Case};
+    use crate::xor_distance::XorDistance;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn cases_hold() {
+        let mut rng = StdRng::seed_from_u64(1535);
+
+        for case in Case::<u8>::generate(&mut rng, 40).take(200) {
+            assert!(case.points.len() <= 40);
+            assert!(case.count <= case.points.len() + 1);
+            assert!(case.holds(), "Invariant violated by {:?}!", case);
+        }
+
+        for case in Case::<u64>::generate(&mut rng, 500).take(20) {
+            assert!(case.holds(), "Invariant violated by {:?}!", case);
+        }
+
+        // Inconsistent closest points don't hold.
+        let case = Case {
+            points: vec![0u8, 1, 2],
+            x: 0,
+            count: 3,
+            closest: vec![1, 2, 0],
+        };
+        assert!(!case.holds());
+    }
+
+    #[test]
+    fn arbitrary_closest_reverse() {
+        let mut rng = StdRng::seed_from_u64(1535);
+        let case = Case::<u16>::random(&mut rng, 300);
+        let xor_distance = XorDistance::new(case.points.clone());
+
+        for _ in 0..100 {
+            let closest = arbitrary_closest(&mut rng, &case.points, 5);
+
+            assert!(closest.len() <= 5);
+            assert!(check_reverse(&xor_distance, &closest));
+        }
+
+        assert!(arbitrary_closest::<u16, _>(&mut rng, &[], 5).is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+//! Service tiers of deliveries by distance band.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::delivery_system::FoodDeliverySystem;
+//! use xor_distance_exercise::tiers::{tier_volumes, ServiceTier, ServiceTiers};
+//!
+//! let mut delivery_system: FoodDeliverySystem<u64> = FoodDeliverySystem::new(vec![0, 64]);
+//! delivery_system.set_service_tiers(ServiceTiers::new(3, 15));
+//!
+//! let assignments: Vec<_> = [1, 2, 8, 40, 66]
+//!     .iter()
+//!     .filter_map(|position| delivery_system.assign_order(*position))
+//!     .collect();
+//!
+//! assert_eq!(Some(ServiceTier::Extended), assignments[3].tier);
+//!
+//! let volumes = tier_volumes(&assignments);
+//! assert_eq!(3, volumes[&ServiceTier::Express]);
+//! assert_eq!(1, volumes[&ServiceTier::Standard]);
+//! assert_eq!(1, volumes[&ServiceTier::Extended]);
+//! ```
+
+use crate::hooks::Assignment;
+use num_traits::{PrimInt, Unsigned};
+use std::collections::BTreeMap;
+
+/// Service level of a delivery.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ServiceTier {
+    /// The closest customers.
+    Express,
+    /// Customers within the usual distance.
+    Standard,
+    /// Customers beyond the usual distance.
+    Extended,
+}
+
+/// Mapping of XOR distance bands to service tiers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ServiceTiers<T> {
+    express_max: T,
+    standard_max: T,
+}
+
+impl<T: PrimInt + Unsigned> ServiceTiers<T> {
+    /// Create a new mapping, distances up to `express_max` are served as `Express`, up to
+    /// `standard_max` as `Standard` and further ones as `Extended`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `express_max` is greater than `standard_max`.
+    pub fn new(express_max: T, standard_max: T) -> Self {
+        assert!(
+            express_max <= standard_max,
+            "Express distance band exceeds the standard one!"
+        );
+
+        ServiceTiers {
+            express_max,
+            standard_max,
+        }
+    }
+
+    /// Return the service tier of the distance.
+    pub fn tier(&self, distance: T) -> ServiceTier {
+        if distance <= self.express_max {
+            ServiceTier::Express
+        } else if distance <= self.standard_max {
+            ServiceTier::Standard
+        } else {
+            ServiceTier::Extended
+        }
+    }
+}
+
+/// Return number of assignments per service tier, assignments without a tier are skipped.
+pub fn tier_volumes<T>(assignments: &[Assignment<T>]) -> BTreeMap<ServiceTier, usize> {
+    let mut volumes = BTreeMap::new();
+
+    for tier in assignments.iter().filter_map(|assignment| assignment.tier) {
+        *volumes.entry(tier).or_insert(0) += 1;
+    }
+
+    volumes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{tier_volumes, ServiceTier, ServiceTiers};
+    use crate::hooks::Assignment;
+
+    #[test]
+    fn tier_bands() {
+        let tiers: ServiceTiers<u8> = ServiceTiers::new(2, 2);
+
+        assert_eq!(ServiceTier::Express, tiers.tier(0));
+        assert_eq!(ServiceTier::Express, tiers.tier(2));
+        assert_eq!(ServiceTier::Extended, tiers.tier(3));
+
+        let tiers: ServiceTiers<u8> = ServiceTiers::new(0, 10);
+        assert_eq!(ServiceTier::Standard, tiers.tier(10));
+        assert_eq!(ServiceTier::Extended, tiers.tier(u8::MAX));
+    }
+
+    #[test]
+    fn volumes() {
+        let assignment = |tier| Assignment {
+            position: 1u8,
+            farm: 0,
+            tier,
+        };
+
+        let volumes = tier_volumes(&[
+            assignment(Some(ServiceTier::Standard)),
+            assignment(None),
+            assignment(Some(ServiceTier::Standard)),
+        ]);
+
+        assert_eq!(1, volumes.len());
+        assert_eq!(2, volumes[&ServiceTier::Standard]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Express distance band exceeds the standard one!")]
+    fn invalid_bands() {
+        ServiceTiers::new(5u8, 4);
+    }
+}
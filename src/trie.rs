@@ -0,0 +1,605 @@
+//! Path-compressed binary (critbit) trie index for any `Unsigned Integer` set, augmented by
+//! subtree point counts.
+//!
+//! Every branch node splits its points by the highest bit they differ in, chains of single-child
+//! nodes are not stored at all. The trie holds `2 * d - 1` nodes for `d` distinct points, while
+//! duplicates are only counted by their leaf.
+//!
+//! Walking the trie from the root while preferring the branch equal to the query's bit visits the
+//! points in increasing XOR distance order, and the subtree counts allow to skip whole branches
+//! when looking for the n-th closest point.
+//!
+//! Inserts and removals update a single path of at most `bit_size` nodes. Nodes of removed points
+//! are reused by later inserts, so the trie doesn't grow under interleaved mutations of a stable
+//! number of points.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::trie::XorTrie;
+//!
+//! let mut trie: XorTrie<u8> = XorTrie::new();
+//!
+//! trie.insert(8);
+//! trie.insert(12);
+//! trie.insert(2);
+//!
+//! assert_eq!(vec![8, 12], trie.closest(10, 2));
+//! assert_eq!(Some(2), trie.nth_closest(10, 2));
+//!
+//! trie.remove(8);
+//! assert_eq!(vec![12, 2], trie.closest(10, 3));
+//! ```
+
+use crate::bitops::BitOps;
+use crate::bits::Bits;
+use alloc::vec::Vec;
+use num_traits::{PrimInt, Unsigned};
+
+/// Trie node, either a leaf of a distinct point or a branch of two non-empty subtrees.
+#[derive(Clone)]
+struct Node<T> {
+    /// Children of a branch, the first one with the `bit` unset, leaves have none.
+    children: Option<[usize; 2]>,
+    /// Highest bit index the points of the branch subtrees differ in, unused by leaves.
+    bit: usize,
+    /// Point of a leaf, a branch's point shares the bits above `bit` with all of its points.
+    point: T,
+    /// Number of points stored in the subtree, duplicates included.
+    count: usize,
+}
+
+/// Path-compressed binary trie of points, branches are ordered by decreasing bit index from the
+/// root.
+#[derive(Clone)]
+pub struct XorTrie<T: PrimInt + Unsigned> {
+    nodes: Vec<Node<T>>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    bit_size: usize,
+}
+
+impl<T: PrimInt + BitOps + Unsigned> XorTrie<T> {
+    /// Create a new empty trie.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::trie::XorTrie;
+    ///
+    /// let trie: XorTrie<u64> = XorTrie::new();
+    ///
+    /// assert!(trie.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        XorTrie {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            root: None,
+            bit_size: Bits::bit_size::<T>(),
+        }
+    }
+
+    /// Return number of points stored in the trie, duplicates included.
+    pub fn len(&self) -> usize {
+        self.count(self.root)
+    }
+
+    /// Return whether the trie holds no points.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert the point, duplicates are allowed and counted.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::trie::XorTrie;
+    ///
+    /// let mut trie: XorTrie<u64> = XorTrie::new();
+    /// trie.insert(4);
+    /// trie.insert(4);
+    ///
+    /// assert_eq!(2, trie.len());
+    /// ```
+    pub fn insert(&mut self, point: T) {
+        let mut node = match self.root {
+            Some(root) => root,
+            None => {
+                self.root = Some(self.new_node(None, 0, point, 1));
+                return;
+            }
+        };
+        let mut parent = None;
+
+        loop {
+            let diff = point ^ self.nodes[node].point;
+            let highest = self.highest_bit(diff);
+
+            match self.nodes[node].children {
+                // The point shares the prefix of the branch, so it belongs to one of its subtrees.
+                Some(children) if highest.is_none_or(|high| high <= self.nodes[node].bit) => {
+                    let branch = point.is_bit_set(self.nodes[node].bit) as usize;
+
+                    self.nodes[node].count += 1;
+                    parent = Some((node, branch));
+                    node = children[branch];
+                }
+                None if highest.is_none() => {
+                    self.nodes[node].count += 1;
+                    return;
+                }
+                _ => {
+                    // The point differs from all points of the node on the highest differing bit,
+                    // so it gets a new branch above the node.
+                    let high = highest.unwrap();
+                    let leaf = self.new_node(None, 0, point, 1);
+                    let mut children = [node, node];
+                    children[point.is_bit_set(high) as usize] = leaf;
+
+                    let count = self.nodes[node].count + 1;
+                    let branch = self.new_node(Some(children), high, point, count);
+                    self.link(parent, branch);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Remove one occurrence of the point.
+    ///
+    /// Returns `true` if the point was present, `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::trie::XorTrie;
+    ///
+    /// let mut trie: XorTrie<u64> = XorTrie::new();
+    /// trie.insert(4);
+    ///
+    /// assert!(trie.remove(4));
+    /// assert!(!trie.remove(4));
+    /// ```
+    pub fn remove(&mut self, point: T) -> bool {
+        let leaf = match self.leaf(point) {
+            Some(leaf) if self.nodes[leaf].point == point => leaf,
+            _ => return false,
+        };
+
+        // The point is present, so the path to its leaf only holds branches with the point.
+        let mut path = Vec::new();
+        let mut node = self.root.unwrap();
+
+        while let Some(children) = self.nodes[node].children {
+            self.nodes[node].count -= 1;
+            let branch = point.is_bit_set(self.nodes[node].bit) as usize;
+            path.push((node, branch));
+            node = children[branch];
+        }
+
+        self.nodes[leaf].count -= 1;
+
+        if self.nodes[leaf].count == 0 {
+            self.free.push(leaf);
+
+            // The parent branch is left with a single subtree, which replaces it.
+            match path.pop() {
+                Some((parent, branch)) => {
+                    let sibling = self.nodes[parent].children.unwrap()[1 - branch];
+                    self.free.push(parent);
+                    self.link(path.last().copied(), sibling);
+                }
+                None => self.root = None,
+            }
+        }
+
+        true
+    }
+
+    /// Return whether the point is stored in the trie.
+    pub fn contains(&self, point: T) -> bool {
+        self.leaf(point)
+            .is_some_and(|leaf| self.nodes[leaf].point == point)
+    }
+
+    /// Return the n-th closest point to `x` by XOR distance, indexed from zero.
+    ///
+    /// Returns `None` if the trie holds `n` points or less.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::trie::XorTrie;
+    ///
+    /// let mut trie: XorTrie<u64> = XorTrie::new();
+    /// trie.insert(8);
+    /// trie.insert(12);
+    ///
+    /// assert_eq!(Some(8), trie.nth_closest(10, 0));
+    /// assert_eq!(Some(12), trie.nth_closest(10, 1));
+    /// assert_eq!(None, trie.nth_closest(10, 2));
+    /// ```
+    pub fn nth_closest(&self, x: T, n: usize) -> Option<T> {
+        if n >= self.len() {
+            return None;
+        }
+
+        let mut node = self.root.unwrap();
+        let mut rank = n;
+
+        while let Some(children) = self.nodes[node].children {
+            let preferred = x.is_bit_set(self.nodes[node].bit) as usize;
+            let preferred_count = self.nodes[children[preferred]].count;
+
+            // The whole preferred branch is closer than the other one, skip it if the requested
+            // rank lies beyond it.
+            node = if rank < preferred_count {
+                children[preferred]
+            } else {
+                rank -= preferred_count;
+                children[1 - preferred]
+            };
+        }
+
+        Some(self.nodes[node].point)
+    }
+
+    /// Return up to requested count of closest points to the provided `x`, ordered from the closest
+    /// to the n-th closest, where `n` is the count.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::trie::XorTrie;
+    ///
+    /// let mut trie: XorTrie<u64> = XorTrie::new();
+    /// trie.insert(8);
+    /// trie.insert(12);
+    /// trie.insert(2);
+    ///
+    /// assert_eq!(vec![8, 12, 2], trie.closest(10, 5));
+    /// ```
+    pub fn closest(&self, x: T, count: usize) -> Vec<T> {
+        self.closest_bounded(x, count, T::max_value())
+    }
+
+    /// Return up to requested count of closest points to the provided `x` within the XOR
+    /// distance `max_distance`, ordered from the closest to the n-th closest.
+    ///
+    /// Branches with all points further than `max_distance` are not visited at all.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::trie::XorTrie;
+    ///
+    /// let mut trie: XorTrie<u64> = XorTrie::new();
+    /// trie.insert(8);
+    /// trie.insert(12);
+    /// trie.insert(2);
+    ///
+    /// assert_eq!(vec![8], trie.closest_bounded(10, 5, 4));
+    /// ```
+    pub fn closest_bounded(&self, x: T, count: usize, max_distance: T) -> Vec<T> {
+        let mut closest = Vec::with_capacity(count.min(self.len()));
+
+        // Depth first walk visiting the preferred branch first, stack holds nodes to visit.
+        let mut stack = Vec::new();
+        stack.extend(
+            self.root
+                .filter(|root| self.min_distance(*root, x) <= max_distance),
+        );
+
+        while let Some(node) = stack.pop() {
+            if closest.len() == count {
+                break;
+            }
+
+            let children = match self.nodes[node].children {
+                Some(children) => children,
+                None => {
+                    // Leaf holds all duplicates of the same point.
+                    let duplicates = self.nodes[node].count.min(count - closest.len());
+                    closest.extend((0..duplicates).map(|_| self.nodes[node].point));
+                    continue;
+                }
+            };
+
+            let preferred = x.is_bit_set(self.nodes[node].bit) as usize;
+
+            // Push the other branch first so the preferred one is popped and visited first.
+            for &branch in [1 - preferred, preferred].iter() {
+                let child = children[branch];
+
+                // Skip the branch if even its closest possible point is too far.
+                if self.min_distance(child, x) <= max_distance {
+                    stack.push(child);
+                }
+            }
+        }
+
+        closest
+    }
+
+    /// Return the leaf the point would be stored in, not necessarily holding the point.
+    fn leaf(&self, point: T) -> Option<usize> {
+        let mut node = self.root?;
+
+        while let Some(children) = self.nodes[node].children {
+            node = children[point.is_bit_set(self.nodes[node].bit) as usize];
+        }
+
+        Some(node)
+    }
+
+    /// Return the lowest XOR distance between `x` and a point of the node's subtree.
+    ///
+    /// Points of a branch share the bits above its `bit`, the closest possible one has all the
+    /// lower bits equal to the `x` ones.
+    fn min_distance(&self, node: usize, x: T) -> T {
+        let node = &self.nodes[node];
+        let distance = node.point ^ x;
+
+        match node.children {
+            Some(_) => distance & !T::mask_low(node.bit + 1),
+            None => distance,
+        }
+    }
+
+    /// Return index of the highest set bit, `None` for zero.
+    fn highest_bit(&self, value: T) -> Option<usize> {
+        if value.is_zero() {
+            None
+        } else {
+            Some(self.bit_size - 1 - value.leading_zeros() as usize)
+        }
+    }
+
+    /// Point the parent's branch, or the root if there is no parent, to the node.
+    fn link(&mut self, parent: Option<(usize, usize)>, node: usize) {
+        match parent {
+            Some((parent, branch)) => self.nodes[parent].children.as_mut().unwrap()[branch] = node,
+            None => self.root = Some(node),
+        }
+    }
+
+    /// Return index of a new node, reusing a freed one if there is any.
+    fn new_node(
+        &mut self,
+        children: Option<[usize; 2]>,
+        bit: usize,
+        point: T,
+        count: usize,
+    ) -> usize {
+        let node = Node {
+            children,
+            bit,
+            point,
+            count,
+        };
+
+        match self.free.pop() {
+            Some(index) => {
+                self.nodes[index] = node;
+                index
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Return points count of an optional node.
+    fn count(&self, node: Option<usize>) -> usize {
+        node.map_or(0, |node| self.nodes[node].count)
+    }
+}
+
+impl<T: PrimInt + BitOps + Unsigned> Default for XorTrie<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XorTrie;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn trie_from(points: &[u64]) -> XorTrie<u64> {
+        let mut trie = XorTrie::new();
+
+        for point in points.iter() {
+            trie.insert(*point);
+        }
+
+        trie
+    }
+
+    #[test]
+    fn closest() {
+        let trie = trie_from(&[
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ]);
+
+        assert_eq!(vec![444, 445, 408, 409], trie.closest(300, 4));
+        assert_eq!(vec![8, 12, 2, 0, 1, 6, 4, 18, 19, 22], trie.closest(10, 10));
+        assert_eq!(Vec::<u64>::new(), trie.closest(10, 0));
+        assert_eq!(19, trie.closest(10, 100).len());
+    }
+
+    #[test]
+    fn closest_duplicates() {
+        let trie = trie_from(&[5, 7, 5, 5]);
+
+        assert_eq!(vec![5, 5], trie.closest(4, 2));
+        assert_eq!(vec![5, 5, 5, 7], trie.closest(4, 4));
+        assert_eq!(vec![7, 5, 5, 5], trie.closest(7, 4));
+    }
+
+    #[test]
+    fn nth_closest() {
+        let points = [
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ];
+        let trie = trie_from(&points);
+        let expected = trie.closest(10, points.len());
+
+        for (n, point) in expected.iter().enumerate() {
+            assert_eq!(Some(*point), trie.nth_closest(10, n));
+        }
+
+        assert_eq!(None, trie.nth_closest(10, points.len()));
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut trie = trie_from(&[3, 3, 9]);
+
+        assert_eq!(3, trie.len());
+        assert!(trie.contains(3));
+        assert!(!trie.contains(4));
+
+        assert!(trie.remove(3));
+        assert!(trie.contains(3));
+        assert!(trie.remove(3));
+        assert!(!trie.contains(3));
+        assert!(!trie.remove(3));
+
+        assert_eq!(vec![9], trie.closest(3, 10));
+        assert_eq!(1, trie.len());
+
+        assert!(trie.remove(9));
+        assert!(trie.is_empty());
+        assert_eq!(None, trie.nth_closest(0, 0));
+    }
+
+    #[test]
+    fn u128_width() {
+        let mut trie: XorTrie<u128> = XorTrie::new();
+        trie.insert(u128::MAX);
+        trie.insert(1);
+
+        assert_eq!(vec![u128::MAX, 1], trie.closest(1 << 127, 2));
+        assert_eq!(Some(1), trie.nth_closest(0, 0));
+    }
+
+    #[test]
+    fn closest_bounded() {
+        let trie = trie_from(&[
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ]);
+
+        // Distances from 10 are `[2, 6, 8, 10, 11, 12, 14, 24, 25, 28, ...]`.
+        assert_eq!(vec![8, 12, 2], trie.closest_bounded(10, 10, 8));
+        assert_eq!(vec![8, 12], trie.closest_bounded(10, 2, 8));
+        assert_eq!(Vec::<u64>::new(), trie.closest_bounded(10, 10, 1));
+        assert_eq!(vec![8], trie.closest_bounded(10, 10, 2));
+        assert_eq!(trie.closest(10, 19), trie.closest_bounded(10, 19, u64::MAX));
+    }
+
+    #[test]
+    fn removed_nodes_reused() {
+        let mut trie = trie_from(&[0, 1, 2, u64::MAX]);
+        let nodes = trie.nodes.len();
+
+        // A leaf and a branch per distinct point, but the first one.
+        assert_eq!(7, nodes);
+
+        assert!(trie.remove(u64::MAX));
+        assert_eq!(2, trie.free.len());
+
+        trie.insert(1 << 63);
+        assert!(trie.free.is_empty());
+        assert_eq!(nodes, trie.nodes.len());
+        assert_eq!(vec![1 << 63, 2], trie.closest(u64::MAX, 2));
+
+        // Removing the last point frees all nodes.
+        for point in [0, 1, 2, 1 << 63].iter() {
+            assert!(trie.remove(*point));
+        }
+        assert_eq!(nodes, trie.free.len());
+        assert!(trie.is_empty());
+    }
+
+    #[test]
+    fn nodes_compressed() {
+        let mut rng = StdRng::seed_from_u64(1503);
+        let mut trie: XorTrie<u128> = XorTrie::new();
+
+        for _ in 0..1000 {
+            trie.insert(rng.gen());
+        }
+        trie.insert(7);
+        trie.insert(7);
+
+        assert_eq!(1002, trie.len());
+        assert_eq!(2 * 1001 - 1, trie.nodes.len());
+    }
+
+    #[test]
+    fn interleaved_mutations() {
+        let mut rng = StdRng::seed_from_u64(1544);
+        let mut trie: XorTrie<u16> = XorTrie::new();
+        let mut points: Vec<u16> = Vec::new();
+        let mut max_nodes = 0;
+
+        for round in 0..20_000 {
+            // Mostly small values, for duplicates and shared prefixes.
+            let point = if rng.gen() {
+                rng.gen_range(0, 64)
+            } else {
+                rng.gen()
+            };
+
+            if points.len() < 100 && rng.gen_range(0, 3) > 0 {
+                trie.insert(point);
+                points.push(point);
+            } else {
+                // Either a present point or mostly a missing one.
+                let point = if rng.gen::<bool>() && !points.is_empty() {
+                    points[rng.gen_range(0, points.len())]
+                } else {
+                    point
+                };
+                let position = points.iter().position(|p| *p == point);
+
+                assert_eq!(position.is_some(), trie.remove(point));
+                if let Some(position) = position {
+                    points.remove(position);
+                }
+            }
+
+            assert_eq!(points.len(), trie.len());
+
+            if round % 10 == 0 {
+                let x: u16 = rng.gen();
+                let count = rng.gen_range(0, points.len() + 2);
+
+                let mut expected = points.clone();
+                expected.sort_by_key(|point| *point ^ x);
+                expected.truncate(count);
+
+                assert_eq!(expected, trie.closest(x, count));
+                assert!(points.iter().all(|point| trie.contains(*point)));
+            }
+
+            max_nodes = max_nodes.max(trie.nodes.len());
+        }
+
+        // The nodes stay bounded by the most points held, freed nodes being reused.
+        assert!(max_nodes < 2 * 100);
+    }
+}
@@ -2,8 +2,168 @@
 
 use crate::bitops::BitOps;
 use crate::bits::Bits;
+use crate::error::XorDistanceError;
+use crate::metric::{DistanceMetric, MetricDistance};
+use crate::solver::{restricted_bit, ConstraintSet, InequalityError, Ranges, Solutions};
+use crate::trie::XorTrie;
+use alloc::borrow::Cow;
+use alloc::collections::{BTreeMap, BTreeSet, BinaryHeap};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::{self, FromIterator};
+use core::sync::atomic::{AtomicU64, Ordering};
 use num_traits::{PrimInt, Unsigned};
 
+pub use crate::solver::Conflict;
+
+/// Number of points shown in the `Debug` summary.
+const DEBUG_SAMPLE_SIZE: usize = 5;
+
+/// Index structure kept alongside the points to answer closest points queries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexKind {
+    /// No index, the closest points are selected by XOR distance on every query.
+    Sort,
+    /// Path-compressed binary trie augmented by subtree counts, of two nodes per distinct point.
+    /// Both mutations and queries walk at most `bit_size` nodes. Built by default, a closest
+    /// points query then only walks the trie paths of the returned points.
+    Trie,
+}
+
+/// Algorithm answering closest points queries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    /// Selected on every query by the number of points, the requested count and the recent
+    /// query history, the index is always used when built.
+    Adaptive,
+    /// The closest points are selected and only they are sorted by XOR distance, for small point
+    /// sets.
+    Sort,
+    /// Top points are kept in a bounded heap, for counts small relative to the points.
+    Heap,
+    /// All points are radix sorted by XOR distance, for large point sets and large counts.
+    Radix,
+    /// The trie index answers the query, sorting is used if the index is not built.
+    Index,
+}
+
+/// The heap is used for counts up to this fraction of the points.
+const HEAP_MAX_RATIO: f64 = 0.125;
+
+/// The radix sort is used from this number of points.
+const RADIX_MIN_POINTS: usize = 4096;
+
+/// Sentinel of no recorded query, not a bit pattern of any ratio.
+const NO_HISTORY: u64 = u64::MAX;
+
+/// Exponentially weighted average of the count to points ratio of recent queries.
+struct QueryHistory {
+    // Bits of the `f64` average, atomic so queries stay `&self`.
+    recent_ratio: AtomicU64,
+}
+
+impl QueryHistory {
+    fn new() -> Self {
+        QueryHistory {
+            recent_ratio: AtomicU64::new(NO_HISTORY),
+        }
+    }
+
+    /// Return the average updated by the ratio of a query, without recording it.
+    fn preview(&self, ratio: f64) -> f64 {
+        let previous = self.recent_ratio.load(Ordering::Relaxed);
+
+        if previous == NO_HISTORY {
+            ratio
+        } else {
+            0.75 * f64::from_bits(previous) + 0.25 * ratio
+        }
+    }
+
+    /// Record the ratio of a query and return the updated average.
+    fn record(&self, ratio: f64) -> f64 {
+        let recent = self.preview(ratio);
+
+        self.recent_ratio.store(recent.to_bits(), Ordering::Relaxed);
+
+        recent
+    }
+}
+
+impl Clone for QueryHistory {
+    fn clone(&self) -> Self {
+        QueryHistory {
+            recent_ratio: AtomicU64::new(self.recent_ratio.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Order of points with equal ranking keys.
+///
+/// Points equally distant from a position are equal, so it only makes a difference to rankings by
+/// `closest_by_key`, where different points can have equal keys. Closest points and their reverse
+/// are consistent under any of them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Points with equal keys are ordered from the smallest point.
+    ByValue,
+    /// Points with equal keys keep their insertion order, the default.
+    ByInsertionOrder,
+    /// Points with equal keys are in no particular order, for the fastest sorting.
+    Unstable,
+}
+
+/// Handling of duplicate points.
+///
+/// Duplicates are equally distant from any position, so the closest points of a position list all
+/// of them in a row, except for the last closest point possibly cut by the count. Reverse closest
+/// points are `None` for closest points not listing the duplicates so.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Duplicate points are kept, the default.
+    Keep,
+    /// Only the first occurrence of a point is kept, added duplicates are ignored.
+    Collapse,
+    /// Duplicate points are an error of the constructor, added duplicates are ignored.
+    Reject,
+}
+
+/// Strategy of removing points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemovalKind {
+    /// Points are removed from the points vector right away, shifting all following points.
+    Shift,
+    /// Removed points are only marked as removed (tombstoned) and physically removed lazily,
+    /// once tombstones make up the half of the points vector. Removals stay logarithmic, for
+    /// workloads with frequent removals.
+    Tombstone,
+}
+
+/// Tombstones of removed points, along with the live points lookup.
+#[derive(Clone)]
+struct Tombstones<T> {
+    removed: Vec<bool>,
+    count: usize,
+    live: BTreeMap<T, Vec<usize>>,
+}
+
+impl<T: PrimInt> Tombstones<T> {
+    fn new(points: &[T]) -> Self {
+        let mut live: BTreeMap<T, Vec<usize>> = BTreeMap::new();
+
+        for (position, point) in points.iter().enumerate() {
+            live.entry(*point).or_default().push(position);
+        }
+
+        Tombstones {
+            removed: vec![false; points.len()],
+            count: 0,
+            live,
+        }
+    }
+}
+
 /// Xor distance structure holding set of `Unsigned Integer` points.
 ///
 /// # Examples
@@ -22,376 +182,4198 @@ use num_traits::{PrimInt, Unsigned};
 /// // Reverse the operation to get a possible position number.
 /// let guess_pos = xor_distance.reverse_closest(&result).unwrap();
 /// ```
+#[derive(Clone)]
 pub struct XorDistance<T: PrimInt + Unsigned> {
     points: Vec<T>,
     bit_size: usize,
+    index: Option<XorTrie<T>>,
+    tombstones: Option<Tombstones<T>>,
+    generation: u64,
+    algorithm: Algorithm,
+    tie_break: TieBreak,
+    duplicates: DuplicatePolicy,
+    history: QueryHistory,
+}
+
+/// Iterator over the (not removed) points, in their insertion order.
+pub struct Points<'a, T> {
+    points: core::iter::Enumerate<core::slice::Iter<'a, T>>,
+    removed: Option<&'a [bool]>,
+}
+
+impl<'a, T> Iterator for Points<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let removed = self.removed;
+
+        self.points
+            .by_ref()
+            .find(|(position, _)| removed.is_none_or(|removed| !removed[*position]))
+            .map(|(_, point)| point)
+    }
+}
+
+/// Summary of the points count, key width, index kind and a few first points, as the points
+/// printed in full would flood logs and test failures.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::xor_distance::XorDistance;
+///
+/// let xor_distance: XorDistance<u8> = XorDistance::new((0..100).collect());
+///
+/// assert_eq!(
+///     "XorDistance { points: 100, bit_size: 8, index: Trie, generation: 0, \
+///      sample: [0, 1, 2, 3, 4, ..] }",
+///     format!("{:?}", xor_distance)
+/// );
+/// ```
+impl<T: PrimInt + Unsigned + fmt::Debug> fmt::Debug for XorDistance<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("XorDistance")
+            .field("points", &self.len())
+            .field("bit_size", &self.bit_size)
+            .field("index", &self.index_kind())
+            .field("generation", &self.generation)
+            .field(
+                "sample",
+                &Sample(self.points().take(DEBUG_SAMPLE_SIZE + 1).collect()),
+            )
+            .finish()
+    }
+}
+
+/// Structures are equal if they hold the same points in the same insertion order, with the same
+/// tie break and duplicate policy.
+///
+/// The index, the removal strategy and the algorithm make no difference, as they all give the same
+/// results.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::xor_distance::{IndexKind, XorDistance};
+///
+/// let indexed: XorDistance<u8> = XorDistance::new(vec![0, 8, 12]);
+/// let mut sorted = XorDistance::builder(vec![0, 8]).index(IndexKind::Sort).build();
+/// sorted.add_point(12);
+///
+/// assert_eq!(indexed, sorted);
+/// assert_ne!(indexed, XorDistance::new(vec![0, 12, 8]));
+/// ```
+impl<T: PrimInt + Unsigned> PartialEq for XorDistance<T> {
+    fn eq(&self, other: &XorDistance<T>) -> bool {
+        self.tie_break == other.tie_break
+            && self.duplicates == other.duplicates
+            && self.points().eq(other.points())
+    }
+}
+
+impl<T: PrimInt + Unsigned> Eq for XorDistance<T> {}
+
+/// Empty structure, points can be added later on.
+impl<T: PrimInt + BitOps + Unsigned> Default for XorDistance<T> {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+/// Structure of the collected points, as by `new`.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::xor_distance::XorDistance;
+///
+/// let xor_distance: XorDistance<u64> = (0..16).map(|point| point * 4).collect();
+///
+/// assert_eq!(vec![8, 12], xor_distance.closest(10, 2));
+/// ```
+impl<T: PrimInt + BitOps + Unsigned> FromIterator<T> for XorDistance<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(points: I) -> Self {
+        Self::new(points.into_iter().collect())
+    }
+}
+
+/// Points are added as by `extend_points`.
+impl<T: PrimInt + BitOps + Unsigned> Extend<T> for XorDistance<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, points: I) {
+        self.extend_points(points);
+    }
+}
+
+/// Points in their insertion order, as by `points`.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::xor_distance::XorDistance;
+///
+/// let xor_distance: XorDistance<u64> = XorDistance::new(vec![8, 0, 12]);
+/// let mut sum = 0;
+///
+/// for point in &xor_distance {
+///     sum += point;
+/// }
+///
+/// assert_eq!(20, sum);
+/// ```
+impl<'a, T: PrimInt + BitOps + Unsigned> IntoIterator for &'a XorDistance<T> {
+    type Item = &'a T;
+    type IntoIter = Points<'a, T>;
+
+    fn into_iter(self) -> Points<'a, T> {
+        self.points()
+    }
+}
+
+/// First points of a slice, followed by `..` if there are more of them.
+struct Sample<'a, T>(Vec<&'a T>);
+
+impl<'a, T: fmt::Debug> fmt::Debug for Sample<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[")?;
+
+        for (index, point) in self.0.iter().take(DEBUG_SAMPLE_SIZE).enumerate() {
+            if index > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:?}", point)?;
+        }
+
+        if self.0.len() > DEBUG_SAMPLE_SIZE {
+            write!(f, ", ..")?;
+        }
+
+        write!(f, "]")
+    }
+}
+
+/// Cursor scanning points in batches, detached from `XorDistance` so the points can be mutated
+/// between batches.
+///
+/// Any mutation after the scan started makes the following batches fail, rather than silently
+/// skipping or repeating points.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::xor_distance::XorDistance;
+///
+/// let mut xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6]);
+///
+/// let mut scan = xor_distance.scan();
+///
+/// assert_eq!(Ok(vec![0, 1]), scan.next_batch(&xor_distance, 2));
+///
+/// xor_distance.add_point(8);
+///
+/// assert!(scan.next_batch(&xor_distance, 2).is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PointsScan {
+    generation: u64,
+    position: usize,
+}
+
+impl PointsScan {
+    /// Return the next batch of up to `size` points, an empty batch once all points were scanned.
+    ///
+    /// Returns `Err(XorDistanceError)` if the points were modified since the scan started.
+    pub fn next_batch<T>(
+        &mut self,
+        xor_distance: &XorDistance<T>,
+        size: usize,
+    ) -> Result<Vec<T>, XorDistanceError>
+    where
+        T: PrimInt + BitOps + Unsigned,
+    {
+        if xor_distance.generation != self.generation {
+            return Err(XorDistanceError::ScanInvalidated {
+                scanned: self.generation,
+                current: xor_distance.generation,
+            });
+        }
+
+        let mut batch = Vec::with_capacity(size);
+
+        while batch.len() < size && self.position < xor_distance.points.len() {
+            if !xor_distance.is_removed(self.position) {
+                batch.push(xor_distance.points[self.position]);
+            }
+
+            self.position += 1;
+        }
+
+        Ok(batch)
+    }
+}
+
+/// Inequality `a ^ x < b ^ x` used by the reverse solver and the bit of `x` it fixed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CertificateStep<T> {
+    /// The inequality pair `(a, b)`.
+    pub inequality: (T, T),
+    /// Index of the most significant bit in which `a` and `b` differ.
+    pub bit_index: usize,
+    /// Value of the bit required by the inequality, the bit value of `a`.
+    pub value: bool,
+}
+
+/// Certificate of a reverse closest points result, checkable by `verify_certificate` without
+/// trusting the solver.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Certificate<T> {
+    /// The found position.
+    pub position: T,
+    /// All inequalities the closest points imply, with the bits they fixed.
+    pub steps: Vec<CertificateStep<T>>,
+}
+
+/// Effect of an inequality on the bits of the position being searched for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceOutcome<T> {
+    /// The inequality decided the bit.
+    Decided,
+    /// The bit was already decided to the same value.
+    Redundant {
+        /// The inequality pair which decided the bit.
+        decided_by: (T, T),
+    },
+    /// The bit was already decided to the other value.
+    Conflicting {
+        /// The inequality pair which decided the bit.
+        decided_by: (T, T),
+    },
+}
+
+/// Inequality `a ^ x < b ^ x` of the closest points, the bit of `x` it constrains and its effect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TraceStep<T> {
+    /// The inequality pair `(a, b)`.
+    pub inequality: (T, T),
+    /// Index of the most significant bit in which `a` and `b` differ.
+    pub bit_index: usize,
+    /// Value of the bit required by the inequality, the bit value of `a`.
+    pub value: bool,
+    /// Effect of the inequality.
+    pub outcome: TraceOutcome<T>,
+}
+
+/// Trace of reversing closest points, explaining the found position or why there is none.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Explanation<T> {
+    /// The first point not listed as many times as it is present, which makes the closest points
+    /// impossible regardless of the inequalities.
+    pub wrongly_listed: Option<T>,
+    /// All inequalities implied by the closest points, in the order of `inequalities_for`.
+    pub steps: Vec<TraceStep<T>>,
+    /// The found position, undecided bits are zero, `None` if there is a wrongly listed point or
+    /// a conflicting inequality.
+    pub position: Option<T>,
+}
+
+/// One line per step, e.g. `8 ^ x < 12 ^ x: bit 2 = 1, decided`, followed by the result.
+impl<T: fmt::Display> fmt::Display for Explanation<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(point) = &self.wrongly_listed {
+            writeln!(f, "point {} is listed wrongly", point)?;
+        }
+
+        for step in self.steps.iter() {
+            let (a, b) = &step.inequality;
+
+            write!(
+                f,
+                "{} ^ x < {} ^ x: bit {} = {}, ",
+                a, b, step.bit_index, step.value as u8
+            )?;
+
+            match &step.outcome {
+                TraceOutcome::Decided => writeln!(f, "decided")?,
+                TraceOutcome::Redundant { decided_by: (c, d) } => {
+                    writeln!(f, "redundant to {} ^ x < {} ^ x", c, d)?
+                }
+                TraceOutcome::Conflicting { decided_by: (c, d) } => {
+                    writeln!(f, "conflicting with {} ^ x < {} ^ x", c, d)?
+                }
+            }
+        }
+
+        match &self.position {
+            Some(position) => write!(f, "position {}", position),
+            None => write!(f, "no position"),
+        }
+    }
+}
+
+/// Reason a position doesn't have the closest points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Violation<T> {
+    /// The point is not present, or is not listed as many times as it is present.
+    Listing {
+        /// The wrongly listed point.
+        point: T,
+    },
+    /// The inequality `a ^ x < b ^ x` implied by the closest points doesn't hold for the position.
+    Inequality {
+        /// The inequality pair `(a, b)`.
+        inequality: (T, T),
+    },
+}
+
+/// Reverse closest points result with the size of its solution space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReverseResult<T> {
+    /// The found position, undecided bits are zero.
+    pub position: T,
+    /// Number of bits the closest points leave undecided, any of their values gives the same
+    /// closest points.
+    pub free_bits: usize,
+}
+
+impl<T> ReverseResult<T> {
+    /// Return number of positions with the same closest points, `2^free_bits`, `None` if it
+    /// doesn't fit into `u128`.
+    pub fn solution_count(&self) -> Option<u128> {
+        1u128.checked_shl(self.free_bits as u32)
+    }
+}
+
+/// Reason closest points reverse to no position, with the indexes of the points involved in the
+/// closest points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Contradiction<T> {
+    /// The point is not present, or is not listed as many times as it is present.
+    Listing {
+        /// The wrongly listed point.
+        point: T,
+        /// Index of the first listing of the point, `None` if a present point is not listed.
+        index: Option<usize>,
+    },
+    /// Two inequalities implied by the closest points restrict the same bit to different values.
+    Inequalities {
+        /// The contradicting inequality, the bit and the inequality which decided it first.
+        conflict: Conflict<T>,
+        /// Indexes of `a` and `b` of the contradicting inequality, `None` for further points.
+        indexes: (Option<usize>, Option<usize>),
+        /// Indexes of the points of the inequality which decided the bit first, as `indexes`.
+        decided_by_indexes: (Option<usize>, Option<usize>),
+    },
+}
+
+/// Single line, e.g. `2 ^ x < 0 ^ x (closest 1, -) requires bit 1 = 1, but 1 ^ x < 2 ^ x
+/// (closest 0, 1) requires bit 1 = 0`, where `-` stands for a further point.
+impl<T: fmt::Display> fmt::Display for Contradiction<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        struct Index(Option<usize>);
+
+        impl fmt::Display for Index {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                match self.0 {
+                    Some(index) => write!(f, "{}", index),
+                    None => write!(f, "-"),
+                }
+            }
+        }
+
+        match self {
+            Contradiction::Listing {
+                point,
+                index: Some(index),
+            } => write!(f, "point {} at closest {} is listed wrongly", point, index),
+            Contradiction::Listing { point, index: None } => {
+                write!(f, "point {} is not listed", point)
+            }
+            Contradiction::Inequalities {
+                conflict,
+                indexes,
+                decided_by_indexes,
+            } => {
+                let (a, b) = &conflict.inequality;
+                let (c, d) = &conflict.decided_by;
+
+                write!(
+                    f,
+                    "{} ^ x < {} ^ x (closest {}, {}) requires bit {} = {}, \
+                     but {} ^ x < {} ^ x (closest {}, {}) requires bit {} = {}",
+                    a,
+                    b,
+                    Index(indexes.0),
+                    Index(indexes.1),
+                    conflict.bit_index,
+                    conflict.required as u8,
+                    c,
+                    d,
+                    Index(decided_by_indexes.0),
+                    Index(decided_by_indexes.1),
+                    conflict.bit_index,
+                    !conflict.required as u8
+                )
+            }
+        }
+    }
+}
+
+/// Known point of closest points with a wildcard, see `reverse_closest_wildcard`.
+struct Rank<T> {
+    point: T,
+    // Inclusive range of the number of points closer than the point.
+    lowest: usize,
+    highest: usize,
+    // Points closer than the point if the bit of `x` differs from the bit of the point, and their
+    // sums over the bits below every bit.
+    siblings: Vec<usize>,
+    below: Vec<usize>,
+}
+
+/// Verify the certificate proves its position has the closest points amongst the points.
+///
+/// It checks the certificate contains every inequality the closest points imply, each of them
+/// fixes the right bit and the position has all the fixed bits. Duplicated points must be listed
+/// as many times as they are present, the last closest point at most as many times, as by
+/// `reverse_closest`.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::xor_distance::{verify_certificate, XorDistance};
+///
+/// let points: Vec<u64> = vec![0, 1, 2, 4, 6, 8, 12];
+/// let xor_distance = XorDistance::new(points.clone());
+///
+/// let certificate = xor_distance.reverse_closest_certified(&[8, 12]).unwrap();
+///
+/// assert!(verify_certificate(&points, &[8, 12], &certificate));
+/// assert!(!verify_certificate(&points, &[12, 8], &certificate));
+/// ```
+pub fn verify_certificate<T: PrimInt + Unsigned>(
+    points: &[T],
+    closest_points: &[T],
+    certificate: &Certificate<T>,
+) -> bool {
+    let bit_size = T::zero().count_zeros();
+
+    let occurrences = |list: &[T], point: &T| list.iter().filter(|p| *p == point).count();
+    let last = closest_points.last();
+
+    let listed_rightly = closest_points.iter().all(|point| {
+        let listed = occurrences(closest_points, point);
+        let present = occurrences(points, point);
+
+        listed == present || (Some(point) == last && listed < present)
+    });
+
+    if !listed_rightly {
+        return false;
+    }
+
+    // Every step fixes the most significant differing bit to the bit of the closer point.
+    let steps_valid = certificate.steps.iter().all(|step| {
+        let (a, b) = step.inequality;
+        let differing = a ^ b;
+
+        differing != T::zero()
+            && step.bit_index == (bit_size - differing.leading_zeros() - 1) as usize
+            && a.checked_is_bit_set(step.bit_index) == Ok(step.value)
+            && certificate.position.checked_is_bit_set(step.bit_index) == Ok(step.value)
+    });
+
+    if !steps_valid {
+        return false;
+    }
+
+    // The steps cover all inequalities implied by the closest points.
+    let proven: BTreeSet<(T, T)> = certificate
+        .steps
+        .iter()
+        .map(|step| step.inequality)
+        .collect();
+
+    // Duplicates tie in every order, they imply no inequality.
+    let ordered = closest_points
+        .windows(2)
+        .filter(|pair| pair[0] != pair[1])
+        .all(|pair| proven.contains(&(pair[0], pair[1])));
+    let separated = closest_points.last().is_none_or(|last| {
+        points
+            .iter()
+            .filter(|point| !closest_points.contains(point))
+            .all(|further| proven.contains(&(*last, *further)))
+    });
+
+    ordered && separated
+}
+
+/// Change of a point's rank between closest points lists of two positions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RankChange<T> {
+    /// The point which changed its rank.
+    pub point: T,
+    /// Rank amongst the closest points of the first position, indexed from zero, `None` if not
+    /// amongst them.
+    pub from_rank: Option<usize>,
+    /// Rank amongst the closest points of the second position, indexed from zero, `None` if not
+    /// amongst them.
+    pub to_rank: Option<usize>,
+    /// Index of the most significant bit differing between the two positions which flipped the
+    /// order of this point and another point of the lists.
+    pub bit_index: Option<usize>,
+}
+
+/// Differences between closest points lists of two positions.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RankingDiff<T> {
+    /// Points present only amongst the closest points of the second position.
+    pub entered: Vec<RankChange<T>>,
+    /// Points present only amongst the closest points of the first position.
+    pub left: Vec<RankChange<T>>,
+    /// Points present in both lists, but with a different rank.
+    pub moved: Vec<RankChange<T>>,
+}
+
+/// Options of `XorDistance`, the defaults are those of `XorDistance::new`.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::xor_distance::{TieBreak, XorDistance, XorDistanceConfig};
+///
+/// let config = XorDistanceConfig {
+///     tie_break: TieBreak::ByValue,
+///     ..XorDistanceConfig::default()
+/// };
+/// let xor_distance: XorDistance<u64> = XorDistance::with_config(vec![0, 1, 2, 4], config);
+///
+/// assert_eq!(TieBreak::ByValue, xor_distance.tie_break());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XorDistanceConfig {
+    /// Index structure used to answer closest points queries.
+    pub index: IndexKind,
+    /// Strategy of removing points.
+    pub removal: RemovalKind,
+    /// Algorithm answering closest points queries.
+    pub algorithm: Algorithm,
+    /// Order of points with equal ranking keys.
+    pub tie_break: TieBreak,
+    /// Handling of duplicate points.
+    pub duplicates: DuplicatePolicy,
+}
+
+impl Default for XorDistanceConfig {
+    fn default() -> Self {
+        XorDistanceConfig {
+            index: IndexKind::Trie,
+            removal: RemovalKind::Shift,
+            algorithm: Algorithm::Adaptive,
+            tie_break: TieBreak::ByInsertionOrder,
+            duplicates: DuplicatePolicy::Keep,
+        }
+    }
+}
+
+/// Builder of `XorDistance` with non-default options.
+///
+/// # Examples
+/// ```
+/// extern crate xor_distance_exercise;
+///
+/// use xor_distance_exercise::xor_distance::{IndexKind, XorDistance};
+///
+/// let xor_distance: XorDistance<u64> = XorDistance::builder(vec![0, 1, 2, 4, 6, 8, 12])
+///     .index(IndexKind::Sort)
+///     .build();
+///
+/// assert_eq!(vec![8, 12], xor_distance.closest(10, 2));
+/// ```
+pub struct XorDistanceBuilder<T: PrimInt + Unsigned> {
+    points: Vec<T>,
+    config: XorDistanceConfig,
 }
 
-impl<T: PrimInt + BitOps + Unsigned> XorDistance<T> {
-    pub fn new(points: Vec<T>) -> Self {
-        let bit_size = Bits::bit_size::<T>();
+impl<T: PrimInt + BitOps + Unsigned> XorDistanceBuilder<T> {
+    /// Create a new builder with default options.
+    pub fn new(points: Vec<T>) -> Self {
+        XorDistanceBuilder {
+            points,
+            config: XorDistanceConfig::default(),
+        }
+    }
+
+    /// Select all options at once.
+    pub fn config(mut self, config: XorDistanceConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Select the index structure used to answer closest points queries.
+    pub fn index(mut self, index: IndexKind) -> Self {
+        self.config.index = index;
+        self
+    }
+
+    /// Select the strategy of removing points.
+    pub fn removal(mut self, removal: RemovalKind) -> Self {
+        self.config.removal = removal;
+        self
+    }
+
+    /// Override the adaptive selection of the algorithm answering closest points queries.
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.config.algorithm = algorithm;
+        self
+    }
+
+    /// Select the order of points with equal ranking keys.
+    pub fn tie_break(mut self, tie_break: TieBreak) -> Self {
+        self.config.tie_break = tie_break;
+        self
+    }
+
+    /// Select the handling of duplicate points.
+    pub fn duplicates(mut self, duplicates: DuplicatePolicy) -> Self {
+        self.config.duplicates = duplicates;
+        self
+    }
+
+    /// Build the `XorDistance`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the points have duplicates and `DuplicatePolicy::Reject` is selected.
+    pub fn build(self) -> XorDistance<T> {
+        match self.try_build() {
+            Ok(xor_distance) => xor_distance,
+            Err(error) => panic!("{}", error),
+        }
+    }
+
+    /// Build the `XorDistance`.
+    ///
+    /// Returns `Err(XorDistanceError::DuplicatePoint)` if the points have duplicates and
+    /// `DuplicatePolicy::Reject` is selected.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::error::XorDistanceError;
+    /// use xor_distance_exercise::xor_distance::{DuplicatePolicy, XorDistance};
+    ///
+    /// let result = XorDistance::builder(vec![0u8, 1, 2, 1])
+    ///     .duplicates(DuplicatePolicy::Reject)
+    ///     .try_build();
+    ///
+    /// assert_eq!(Some(XorDistanceError::DuplicatePoint { index: 3 }), result.err());
+    /// ```
+    pub fn try_build(mut self) -> Result<XorDistance<T>, XorDistanceError> {
+        let bit_size = Bits::bit_size::<T>();
+        let mut seen = BTreeSet::new();
+
+        match self.config.duplicates {
+            DuplicatePolicy::Keep => {}
+            DuplicatePolicy::Collapse => self.points.retain(|point| seen.insert(*point)),
+            DuplicatePolicy::Reject => {
+                if let Some(index) = self.points.iter().position(|point| !seen.insert(*point)) {
+                    return Err(XorDistanceError::DuplicatePoint { index });
+                }
+            }
+        }
+
+        let index = match self.config.index {
+            IndexKind::Sort => None,
+            IndexKind::Trie => {
+                let mut trie = XorTrie::new();
+
+                for point in self.points.iter() {
+                    trie.insert(*point);
+                }
+
+                Some(trie)
+            }
+        };
+
+        let tombstones = match self.config.removal {
+            RemovalKind::Shift => None,
+            RemovalKind::Tombstone => Some(Tombstones::new(&self.points)),
+        };
+
+        Ok(XorDistance {
+            points: self.points,
+            bit_size,
+            index,
+            tombstones,
+            generation: 0,
+            algorithm: self.config.algorithm,
+            tie_break: self.config.tie_break,
+            duplicates: self.config.duplicates,
+            history: QueryHistory::new(),
+        })
+    }
+}
+
+impl<T: PrimInt + BitOps + Unsigned> XorDistance<T> {
+    pub fn new(points: Vec<T>) -> Self {
+        XorDistanceBuilder::new(points).build()
+    }
+
+    /// Create a new structure holding the points with duplicates collapsed, only the first
+    /// occurrence of every point is kept.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{DuplicatePolicy, XorDistance};
+    ///
+    /// let mut xor_distance: XorDistance<u64> = XorDistance::new_dedup(vec![4, 0, 4, 1, 0]);
+    ///
+    /// assert_eq!(DuplicatePolicy::Collapse, xor_distance.duplicate_policy());
+    /// assert_eq!(vec![4, 0, 1], xor_distance.points().copied().collect::<Vec<u64>>());
+    ///
+    /// assert!(!xor_distance.add_point(1));
+    /// assert_eq!(vec![0, 1], xor_distance.closest(0, 2));
+    /// ```
+    pub fn new_dedup(points: Vec<T>) -> Self {
+        XorDistanceBuilder::new(points)
+            .duplicates(DuplicatePolicy::Collapse)
+            .build()
+    }
+
+    /// Return a builder to construct `XorDistance` with non-default options.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{IndexKind, XorDistance};
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::builder(vec![0, 1, 2])
+    ///     .index(IndexKind::Sort)
+    ///     .build();
+    /// ```
+    pub fn builder(points: Vec<T>) -> XorDistanceBuilder<T> {
+        XorDistanceBuilder::new(points)
+    }
+
+    /// Create a new structure holding the points with the options of the config, see
+    /// `XorDistanceConfig`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the points have duplicates and `DuplicatePolicy::Reject` is selected, see
+    /// `XorDistanceBuilder::try_build` for the fallible construction.
+    pub fn with_config(points: Vec<T>, config: XorDistanceConfig) -> Self {
+        XorDistanceBuilder::new(points).config(config).build()
+    }
+
+    /// Return the index structure kind used to answer closest points queries.
+    pub fn index_kind(&self) -> IndexKind {
+        match self.index {
+            Some(_) => IndexKind::Trie,
+            None => IndexKind::Sort,
+        }
+    }
+
+    /// Return the strategy of removing points.
+    pub fn removal_kind(&self) -> RemovalKind {
+        match self.tombstones {
+            Some(_) => RemovalKind::Tombstone,
+            None => RemovalKind::Shift,
+        }
+    }
+
+    /// Return number of points, duplicates included.
+    pub fn len(&self) -> usize {
+        self.points.len() - self.tombstones.as_ref().map_or(0, |t| t.count)
+    }
+
+    /// Return whether there are no points.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Return number of bits of the points type, e.g. to assert the width of positions exchanged
+    /// with other systems.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u128> = XorDistance::new(vec![0, 1]);
+    ///
+    /// assert_eq!(128, xor_distance.bit_size());
+    /// ```
+    pub fn bit_size(&self) -> usize {
+        self.bit_size
+    }
+
+    /// Return whether the point is present.
+    ///
+    /// It is logarithmic in the number of points when the trie index or the tombstone removal is
+    /// used and linear otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let mut xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2]);
+    ///
+    /// assert!(xor_distance.contains(1));
+    /// xor_distance.remove_point(1);
+    /// assert!(!xor_distance.contains(1));
+    /// ```
+    pub fn contains(&self, point: T) -> bool {
+        if let Some(trie) = self.index.as_ref() {
+            return trie.contains(point);
+        }
+
+        match self.tombstones.as_ref() {
+            Some(tombstones) => tombstones.live.contains_key(&point),
+            None => self.points.contains(&point),
+        }
+    }
+
+    /// Return all points in their insertion order, duplicates included.
+    ///
+    /// The points are iterated rather than borrowed as a slice, as removed points may be only
+    /// tombstoned in the points vector.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{RemovalKind, XorDistance};
+    ///
+    /// let mut xor_distance: XorDistance<u64> = XorDistance::builder(vec![4, 0, 2, 0])
+    ///     .removal(RemovalKind::Tombstone)
+    ///     .build();
+    /// xor_distance.remove_point(2);
+    ///
+    /// assert_eq!(vec![4, 0, 0], xor_distance.points().copied().collect::<Vec<_>>());
+    /// ```
+    pub fn points(&self) -> Points<'_, T> {
+        Points {
+            points: self.points.iter().enumerate(),
+            removed: self.tombstones.as_ref().map(|t| &t.removed[..]),
+        }
+    }
+
+    /// Return the generation of points, increased by every mutation.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let mut xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2]);
+    /// let generation = xor_distance.generation();
+    ///
+    /// xor_distance.add_point(4);
+    ///
+    /// assert!(xor_distance.generation() > generation);
+    /// ```
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Start scanning points in batches, in their insertion order.
+    pub fn scan(&self) -> PointsScan {
+        PointsScan {
+            generation: self.generation,
+            position: 0,
+        }
+    }
+
+    /// Add a new point.
+    ///
+    /// Returns `false` if the point is already present and duplicates are not kept, `true`
+    /// otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let mut xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2]);
+    /// xor_distance.add_point(10);
+    ///
+    /// assert_eq!(vec![10], xor_distance.closest(10, 1));
+    /// ```
+    pub fn add_point(&mut self, point: T) -> bool {
+        if !self.accepts(point) {
+            return false;
+        }
+
+        self.push_point(point);
+        self.generation += 1;
+
+        true
+    }
+
+    /// Add all the points, duplicates are skipped unless they are kept.
+    ///
+    /// The generation is increased once for the whole batch, so running scans are invalidated
+    /// just as by a single `add_point`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let mut xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2]);
+    /// xor_distance.extend_points(vec![10, 11]);
+    ///
+    /// assert_eq!(5, xor_distance.len());
+    /// assert_eq!(vec![11, 10], xor_distance.closest(11, 2));
+    /// ```
+    pub fn extend_points<I: IntoIterator<Item = T>>(&mut self, points: I) {
+        let points = points.into_iter();
+        let len = self.points.len();
+
+        self.points.reserve(points.size_hint().0);
+
+        for point in points {
+            if self.accepts(point) {
+                self.push_point(point);
+            }
+        }
+
+        if self.points.len() > len {
+            self.generation += 1;
+        }
+    }
+
+    /// Return whether the point can be added under the duplicate policy.
+    fn accepts(&self, point: T) -> bool {
+        self.duplicates == DuplicatePolicy::Keep || !self.contains(point)
+    }
+
+    /// Add a new point to the points and to the index structures, leaving the generation intact.
+    fn push_point(&mut self, point: T) {
+        if let Some(tombstones) = self.tombstones.as_mut() {
+            tombstones.removed.push(false);
+            tombstones
+                .live
+                .entry(point)
+                .or_default()
+                .push(self.points.len());
+        }
+
+        self.points.push(point);
+
+        if let Some(trie) = self.index.as_mut() {
+            trie.insert(point);
+        }
+    }
+
+    /// Remove one occurrence of the point.
+    ///
+    /// Returns `true` if the point was present, `false` otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let mut xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2]);
+    ///
+    /// assert!(xor_distance.remove_point(1));
+    /// assert!(!xor_distance.remove_point(1));
+    /// assert_eq!(vec![0, 2], xor_distance.closest(0, 3));
+    /// ```
+    pub fn remove_point(&mut self, point: T) -> bool {
+        let removed = match self.tombstones.as_mut() {
+            Some(tombstones) => Self::tombstone_point(tombstones, point),
+            None => match self.points.iter().position(|p| *p == point) {
+                Some(position) => {
+                    self.points.remove(position);
+                    true
+                }
+                None => false,
+            },
+        };
+
+        if !removed {
+            return false;
+        }
+
+        self.generation += 1;
+
+        if let Some(trie) = self.index.as_mut() {
+            trie.remove(point);
+        }
+
+        // Compact once tombstones make up the half of the points.
+        if let Some(tombstones) = self.tombstones.as_ref() {
+            if tombstones.count * 2 >= self.points.len() {
+                self.compact();
+            }
+        }
+
+        true
+    }
+
+    /// Physically remove tombstoned points, it is done automatically once tombstones make up the
+    /// half of the points.
+    ///
+    /// Does nothing unless `RemovalKind::Tombstone` is used.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{RemovalKind, XorDistance};
+    ///
+    /// let mut xor_distance: XorDistance<u64> = XorDistance::builder(vec![0, 1, 2, 4, 6])
+    ///     .removal(RemovalKind::Tombstone)
+    ///     .build();
+    ///
+    /// assert!(xor_distance.remove_point(1));
+    /// xor_distance.compact();
+    ///
+    /// assert_eq!(vec![0, 2, 4, 6], xor_distance.points().copied().collect::<Vec<_>>());
+    /// ```
+    pub fn compact(&mut self) {
+        let tombstones = match self.tombstones.as_ref() {
+            Some(tombstones) if tombstones.count > 0 => tombstones,
+            _ => return,
+        };
+
+        let points = core::mem::take(&mut self.points);
+        self.points = points
+            .into_iter()
+            .zip(tombstones.removed.iter())
+            .filter(|(_, removed)| !**removed)
+            .map(|(point, _)| point)
+            .collect();
+
+        self.tombstones = Some(Tombstones::new(&self.points));
+        self.generation += 1;
+    }
+
+    /// Mark a live occurrence of the point as removed, returns whether there was any.
+    fn tombstone_point(tombstones: &mut Tombstones<T>, point: T) -> bool {
+        let positions = match tombstones.live.get_mut(&point) {
+            Some(positions) => positions,
+            None => return false,
+        };
+
+        // Remove the first occurrence, as the shifting removal does.
+        let position = positions.remove(0);
+
+        if positions.is_empty() {
+            tombstones.live.remove(&point);
+        }
+
+        tombstones.removed[position] = true;
+        tombstones.count += 1;
+
+        true
+    }
+
+    /// Return whether the point at the position in the points vector is tombstoned.
+    fn is_removed(&self, position: usize) -> bool {
+        self.tombstones
+            .as_ref()
+            .is_some_and(|tombstones| tombstones.removed[position])
+    }
+
+    /// Return up to requested count of closest points to the provided `x`, ordered from the closest
+    /// to the n-th closest, where `n` is the count.
+    ///
+    /// The returned closest points count my be lower than the specified count and equal to all
+    /// points count only in the case that: `count > points.len()`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    /// ]);
+    ///
+    /// let x = 200;
+    /// let count = 10;
+    ///
+    /// let closest_points = xor_distance.closest(x, count);
+    /// ```
+    pub fn closest(&self, x: T, count: usize) -> Vec<T> {
+        match self.choose_and_record(count) {
+            Algorithm::Heap => self.closest_heap(x, count),
+            Algorithm::Radix => self.closest_radix(x, count),
+            Algorithm::Index => match self.index.as_ref() {
+                Some(trie) => trie.closest(x, count),
+                None => self.closest_sort(x, count),
+            },
+            _ => self.closest_sort(x, count),
+        }
+    }
+
+    /// Return up to requested count of closest points to the provided `x` with their XOR distances,
+    /// as `(point, distance)` pairs ordered as by `closest`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// assert_eq!(
+    ///     vec![(8, 2), (12, 6), (2, 8)],
+    ///     xor_distance.closest_with_distances(10, 3)
+    /// );
+    /// ```
+    pub fn closest_with_distances(&self, x: T, count: usize) -> Vec<(T, T)> {
+        self.closest(x, count)
+            .into_iter()
+            .map(|point| (point, point ^ x))
+            .collect()
+    }
+
+    /// Return the points ranked by the provided distance metric, e.g. by `Hamming` distance of
+    /// similarity hashes, instead of the XOR distance.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::metric::Hamming;
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// assert_eq!(vec![8, 12], xor_distance.closest(10, 2));
+    /// assert_eq!(vec![2, 8], xor_distance.with_metric(Hamming).closest(10, 2));
+    /// ```
+    pub fn with_metric<M: DistanceMetric<T>>(&self, metric: M) -> MetricDistance<'_, T, M> {
+        MetricDistance::new(self, metric)
+    }
+
+    /// Return the handling of duplicate points, `DuplicatePolicy::Keep` unless overridden by the
+    /// builder.
+    pub fn duplicate_policy(&self) -> DuplicatePolicy {
+        self.duplicates
+    }
+
+    /// Return the order of points with equal ranking keys, `TieBreak::ByInsertionOrder` unless
+    /// overridden by the builder.
+    pub fn tie_break(&self) -> TieBreak {
+        self.tie_break
+    }
+
+    /// Return the algorithm answering closest points queries, `Algorithm::Adaptive` unless
+    /// overridden by the builder.
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Return the algorithm a closest points query of the count would be answered by.
+    ///
+    /// It only inspects the adaptive selection, the query is not recorded in its history.
+    ///
+    /// The heap is selected for counts small relative to the points, the radix sort for large
+    /// point sets otherwise. Queries close to the heap threshold follow the recent query history
+    /// instead, so workloads oscillating around it don't flip between algorithms.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{Algorithm, IndexKind, XorDistance};
+    ///
+    /// let xor_distance: XorDistance<u32> = XorDistance::builder((0..10_000).collect())
+    ///     .index(IndexKind::Sort)
+    ///     .build();
+    ///
+    /// assert_eq!(Algorithm::Heap, xor_distance.select_algorithm(10));
+    /// assert_eq!(Algorithm::Radix, xor_distance.select_algorithm(5000));
+    /// ```
+    pub fn select_algorithm(&self, count: usize) -> Algorithm {
+        self.choose_algorithm(count, |ratio| self.history.preview(ratio))
+    }
+
+    /// Return the algorithm answering a closest points query of the count, recording the query in
+    /// the history of the adaptive selection.
+    fn choose_and_record(&self, count: usize) -> Algorithm {
+        self.choose_algorithm(count, |ratio| self.history.record(ratio))
+    }
+
+    /// Return the algorithm of a closest points query, `recent` returns the average ratio of the
+    /// recent queries including the query's one.
+    fn choose_algorithm<F>(&self, count: usize, recent: F) -> Algorithm
+    where
+        F: FnOnce(f64) -> f64,
+    {
+        if self.algorithm != Algorithm::Adaptive {
+            return self.algorithm;
+        }
+
+        if self.index.is_some() {
+            return Algorithm::Index;
+        }
+
+        let len = self.len();
+        if len == 0 {
+            return Algorithm::Sort;
+        }
+
+        let ratio = count.min(len) as f64 / len as f64;
+        let recent = recent(ratio);
+
+        let near_threshold = (ratio - HEAP_MAX_RATIO).abs() * 2.0 < HEAP_MAX_RATIO;
+        let small_count = if near_threshold {
+            recent <= HEAP_MAX_RATIO
+        } else {
+            ratio <= HEAP_MAX_RATIO
+        };
+
+        if small_count {
+            Algorithm::Heap
+        } else if len >= RADIX_MIN_POINTS {
+            Algorithm::Radix
+        } else {
+            Algorithm::Sort
+        }
+    }
+
+    fn closest_sort(&self, x: T, count: usize) -> Vec<T> {
+        Self::select_closest(self.points().copied().collect(), x, count)
+    }
+
+    /// Return up to `count` of the points closest to `x`, ordered from the closest.
+    ///
+    /// Only the closest points are sorted after selecting them in linear time, which is
+    /// `O(n + k log k)` instead of sorting all points. Unstable algorithms are fine as equally
+    /// distant points are equal.
+    fn select_closest(mut points: Vec<T>, x: T, count: usize) -> Vec<T> {
+        if count < points.len() {
+            points.select_nth_unstable_by_key(count, |point| *point ^ x);
+            points.truncate(count);
+        }
+
+        points.sort_unstable_by_key(|point| *point ^ x);
+        points
+    }
+
+    fn closest_heap(&self, x: T, count: usize) -> Vec<T> {
+        // The count may be anything up to `usize::MAX`, never more points are returned.
+        let count = count.min(self.len());
+
+        if count == 0 {
+            return Vec::new();
+        }
+
+        // Max-heap of the distances of the closest points found so far, the distance maps back
+        // to its point as `distance ^ x`.
+        let mut heap: BinaryHeap<T> = BinaryHeap::with_capacity(count);
+
+        for point in self.points() {
+            let distance = *point ^ x;
+
+            if heap.len() < count {
+                heap.push(distance);
+            } else if heap.peek().is_some_and(|furthest| distance < *furthest) {
+                heap.pop();
+                heap.push(distance);
+            }
+        }
+
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|distance| distance ^ x)
+            .collect()
+    }
+
+    fn closest_radix(&self, x: T, count: usize) -> Vec<T> {
+        let mut distances: Vec<T> = self.points().map(|point| *point ^ x).collect();
+        let mut buffer = vec![T::zero(); distances.len()];
+        // Integer types are always one byte at least.
+        let byte_mask = T::from(0xFF).unwrap();
+
+        // Least significant digit first, every pass is a stable counting sort of one byte.
+        for shift in (0..self.bit_size).step_by(8) {
+            // Bytes always fit into `usize`.
+            let digit = |distance: T| ((distance >> shift) & byte_mask).to_usize().unwrap();
+
+            let mut offsets = [0usize; 256];
+            for distance in distances.iter() {
+                offsets[digit(*distance)] += 1;
+            }
+
+            let mut total = 0;
+            for offset in offsets.iter_mut() {
+                let digit_count = *offset;
+                *offset = total;
+                total += digit_count;
+            }
+
+            for distance in distances.iter() {
+                let offset = &mut offsets[digit(*distance)];
+                buffer[*offset] = *distance;
+                *offset += 1;
+            }
+
+            core::mem::swap(&mut distances, &mut buffer);
+        }
+
+        distances
+            .into_iter()
+            .take(count)
+            .map(|distance| distance ^ x)
+            .collect()
+    }
+
+    /// Return up to requested count of closest points to the provided `x` within the XOR distance
+    /// `max_distance`, ordered from the closest to the n-th closest, where `n` is the count.
+    ///
+    /// Points further than `max_distance` are not considered at all, so it combines the closest
+    /// points and the range semantics in one pass, the top-k within a radius. The `max_distance`
+    /// is inclusive and fewer than `count` points are returned rather than any further point.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    /// ]);
+    ///
+    /// assert_eq!(vec![8, 12, 2], xor_distance.closest_bounded(10, 10, 8));
+    ///
+    /// // Within the radius, the count applies.
+    /// assert_eq!(vec![8, 12], xor_distance.closest_bounded(10, 2, 8));
+    ///
+    /// // The point `8` is exactly at the distance 2.
+    /// assert_eq!(vec![8], xor_distance.closest_bounded(10, 10, 2));
+    /// ```
+    pub fn closest_bounded(&self, x: T, count: usize, max_distance: T) -> Vec<T> {
+        if let Some(trie) = self.index.as_ref() {
+            return trie.closest_bounded(x, count, max_distance);
+        }
+
+        let within: Vec<T> = self
+            .points()
+            .copied()
+            .filter(|point| *point ^ x <= max_distance)
+            .collect();
+
+        Self::select_closest(within, x, count)
+    }
+
+    /// Return up to requested count of closest points to the provided `x` within the XOR distance
+    /// `max_dist`, the top-k within a radius, see `closest_bounded`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12, 444, 445]);
+    ///
+    /// // Both `444` and `445` are too far away.
+    /// assert_eq!(vec![8, 12, 2, 0, 1, 6, 4], xor_distance.closest_within(10, 9, 255));
+    /// ```
+    pub fn closest_within(&self, x: T, count: usize, max_dist: T) -> Vec<T> {
+        self.closest_bounded(x, count, max_dist)
+    }
+
+    /// Return all points within the XOR distance `max_distance` to the provided `x`, ordered from
+    /// the closest, the radius counterpart of `closest`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    /// ]);
+    ///
+    /// assert_eq!(vec![8, 12, 2, 0, 1], xor_distance.within_distance(10, 11));
+    /// ```
+    pub fn within_distance(&self, x: T, max_distance: T) -> Vec<T> {
+        self.closest_bounded(x, usize::MAX, max_distance)
+    }
+
+    /// Return up to requested count of points ordered by the key returned by `key_fn`, called
+    /// with each point and its XOR distance to the provided `x`.
+    ///
+    /// It allows composite rankings, e.g. the distance combined with an external score. Points
+    /// with equal keys are ordered by the `TieBreak` of the structure.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use std::cmp::Reverse;
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// // Prefer even points, the closest first.
+    /// let closest = xor_distance.closest_by_key(10, 3, |point, distance| (point % 2, distance));
+    /// assert_eq!(vec![8, 12, 2], closest);
+    ///
+    /// // The furthest points first.
+    /// let furthest = xor_distance.closest_by_key(10, 2, |_, distance| Reverse(distance));
+    /// assert_eq!(vec![4, 6], furthest);
+    /// ```
+    pub fn closest_by_key<K, F>(&self, x: T, count: usize, mut key_fn: F) -> Vec<T>
+    where
+        K: Ord,
+        F: FnMut(T, T) -> K,
+    {
+        let mut keyed: Vec<(K, T)> = self
+            .points()
+            .map(|point| (key_fn(*point, *point ^ x), *point))
+            .collect();
+
+        match self.tie_break {
+            TieBreak::ByValue => keyed.sort_unstable(),
+            TieBreak::ByInsertionOrder => keyed.sort_by(|a, b| a.0.cmp(&b.0)),
+            TieBreak::Unstable => keyed.sort_unstable_by(|a, b| a.0.cmp(&b.0)),
+        }
+
+        keyed
+            .into_iter()
+            .take(count)
+            .map(|(_, point)| point)
+            .collect()
+    }
+
+    /// Return the n-th closest point to the provided `x`, indexed from zero, or `None` if there
+    /// are `n` points or less.
+    ///
+    /// It is logarithmic in the number of points when the `IndexKind::Trie` index is used and
+    /// linear otherwise.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{IndexKind, XorDistance};
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::builder(vec![
+    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    /// ])
+    /// .index(IndexKind::Trie)
+    /// .build();
+    ///
+    /// assert_eq!(Some(408), xor_distance.nth_closest(300, 2));
+    /// ```
+    pub fn nth_closest(&self, x: T, n: usize) -> Option<T> {
+        if let Some(trie) = self.index.as_ref() {
+            return trie.nth_closest(x, n);
+        }
+
+        if n >= self.len() {
+            return None;
+        }
+
+        let mut points: Vec<T> = self.points().copied().collect();
+        let (_, nth, _) = points.select_nth_unstable_by_key(n, |point| *point ^ x);
+
+        Some(*nth)
+    }
+
+    /// Return every point paired with its closest other point, in the insertion order of points.
+    ///
+    /// Duplicate points are closest to each other. It is `O(n * bit_size)`, using the trie index
+    /// or a temporary trie if the index is not built. No pairs are returned for less than two
+    /// points.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 8, 12, 1, 12]);
+    ///
+    /// assert_eq!(
+    ///     vec![(0, 1), (8, 12), (12, 12), (1, 0), (12, 12)],
+    ///     xor_distance.nearest_neighbor_of_each()
+    /// );
+    /// ```
+    pub fn nearest_neighbor_of_each(&self) -> Vec<(T, T)> {
+        if self.len() < 2 {
+            return Vec::new();
+        }
+
+        let trie = self.trie();
+
+        // The closest point of a present point is the point itself, the next one is its neighbor.
+        self.points()
+            .map(|point| (*point, trie.nth_closest(*point, 1).unwrap()))
+            .collect()
+    }
+
+    /// Return the pair of points with the lowest XOR distance, `None` for less than two points.
+    ///
+    /// Sorted points are closest to their neighbors, so it is `O(n log n)`. Duplicate points are
+    /// a pair of zero distance. The smaller point is the first one and of equally close pairs the
+    /// one of the smallest points is returned.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 12, 8, 3, 7]);
+    ///
+    /// assert_eq!(Some((0, 3)), xor_distance.closest_pair());
+    /// assert_eq!(Some((8, 8)), XorDistance::new(vec![8u64, 0, 8]).closest_pair());
+    /// ```
+    pub fn closest_pair(&self) -> Option<(T, T)> {
+        let mut points: Vec<T> = self.points().copied().collect();
+        points.sort_unstable();
+
+        points
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .min_by_key(|(a, b)| *a ^ *b)
+    }
+
+    /// Return the pair of points with the highest XOR distance, the diameter of the points,
+    /// `None` for less than two points.
+    ///
+    /// The furthest point of every point is the closest one to its complement, so it is
+    /// `O(n * bit_size)`, using the trie index or a temporary trie if the index is not built.
+    /// The smaller point is the first one and it is the smallest one of all the furthest pairs.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 12, 8, 3, 7]);
+    ///
+    /// assert_eq!(Some((3, 12)), xor_distance.farthest_pair());
+    /// ```
+    pub fn farthest_pair(&self) -> Option<(T, T)> {
+        if self.len() < 2 {
+            return None;
+        }
+
+        let trie = self.trie();
+
+        self.points()
+            .copied()
+            .map(|point| {
+                // The trie isn't empty.
+                let furthest = trie.nth_closest(!point, 0).unwrap();
+                (point.min(furthest), point.max(furthest))
+            })
+            .max_by(|(a1, b1), (a2, b2)| (*a1 ^ *b1).cmp(&(*a2 ^ *b2)).then(a2.cmp(a1)))
+    }
+
+    /// Return the trie index, or a temporary trie of the points if the index is not built.
+    fn trie(&self) -> Cow<'_, XorTrie<T>> {
+        match self.index.as_ref() {
+            Some(trie) => Cow::Borrowed(trie),
+            None => {
+                let mut trie = XorTrie::new();
+                self.points().for_each(|point| trie.insert(*point));
+
+                Cow::Owned(trie)
+            }
+        }
+    }
+
+    /// Return points grouped into clusters of equal highest `prefix_bits` bits, e.g. regions of
+    /// geohash-like positions.
+    ///
+    /// Points of a cluster are closer to each other than to any point of other clusters, their
+    /// XOR distances are lower than `2^(bit_size - prefix_bits)`. Clusters are ordered by their
+    /// prefixes and the points of a cluster are ordered from the smallest, duplicates included.
+    /// All points are in a single cluster for zero `prefix_bits`, equal points only for
+    /// `prefix_bits` of the bit size or more.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u8> =
+    ///     XorDistance::new(vec![0b1000_0001, 0b0000_0011, 0b1011_0000, 0b0001_0000]);
+    ///
+    /// assert_eq!(
+    ///     vec![vec![0b0000_0011, 0b0001_0000], vec![0b1000_0001, 0b1011_0000]],
+    ///     xor_distance.clusters(2)
+    /// );
+    /// assert_eq!(4, xor_distance.clusters(4).len());
+    /// ```
+    pub fn clusters(&self, prefix_bits: usize) -> Vec<Vec<T>> {
+        let mask = T::mask_high(prefix_bits);
+
+        let mut points: Vec<T> = self.points().copied().collect();
+        points.sort_unstable();
+
+        points
+            .chunk_by(|a, b| *a & mask == *b & mask)
+            .map(|cluster| cluster.to_vec())
+            .collect()
+    }
+
+    /// Report points which enter, leave or change rank between closest points lists of `x1` and
+    /// `x2`, including the bit index responsible for each change.
+    ///
+    /// The order of two points `a` and `b` is decided by the first left hand-side bit in which
+    /// they differ, so it flips only if `x1` and `x2` differ in that bit too.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 3]);
+    ///
+    /// // Closest points are `[0, 1]` and `[1, 0]`.
+    /// let diff = xor_distance.ranking_diff(0, 1, 2);
+    ///
+    /// assert!(diff.entered.is_empty() && diff.left.is_empty());
+    /// assert_eq!(2, diff.moved.len());
+    /// assert_eq!(Some(0), diff.moved[0].bit_index);
+    /// ```
+    pub fn ranking_diff(&self, x1: T, x2: T, count: usize) -> RankingDiff<T> {
+        let first = self.closest(x1, count);
+        let second = self.closest(x2, count);
+        let flipped_bits = x1 ^ x2;
+
+        let rank = |list: &[T], point: T| list.iter().position(|p| *p == point);
+
+        // The most significant flipped bit deciding order of the point and any other listed point.
+        let responsible_bit = |point: T| {
+            first
+                .iter()
+                .chain(second.iter())
+                .filter_map(|other| restricted_bit((point, *other)).ok())
+                .map(|(bit_index, _)| bit_index)
+                .filter(|bit_index| flipped_bits.is_bit_set(*bit_index))
+                .max()
+        };
+
+        let mut diff = RankingDiff {
+            entered: Vec::new(),
+            left: Vec::new(),
+            moved: Vec::new(),
+        };
+
+        for (from_rank, point) in first.iter().enumerate() {
+            // Duplicates are reported just once, by their first occurrence.
+            if rank(&first, *point) != Some(from_rank) {
+                continue;
+            }
+
+            let to_rank = rank(&second, *point);
+
+            if to_rank == Some(from_rank) {
+                continue;
+            }
+
+            let change = RankChange {
+                point: *point,
+                from_rank: Some(from_rank),
+                to_rank,
+                bit_index: responsible_bit(*point),
+            };
+
+            match to_rank {
+                Some(_) => diff.moved.push(change),
+                None => diff.left.push(change),
+            }
+        }
+
+        for (to_rank, point) in second.iter().enumerate() {
+            if rank(&second, *point) == Some(to_rank) && rank(&first, *point).is_none() {
+                diff.entered.push(RankChange {
+                    point: *point,
+                    from_rank: None,
+                    to_rank: Some(to_rank),
+                    bit_index: responsible_bit(*point),
+                });
+            }
+        }
+
+        diff
+    }
+
+    /// Return a `Some(x)` such that `self.closest(x)` equals closest_points and return None in case
+    /// such a `x` does not exists.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    /// ]);
+    ///
+    /// let x = 200;
+    /// let count = 10;
+    ///
+    /// // Get closest points and reversed guess of `x`
+    /// let closest_points = xor_distance.closest(x, count);
+    /// let x_guess = xor_distance.reverse_closest(&closest_points).unwrap();
+    ///
+    /// // Check that both `x` and `guess_x` produce the same result.
+    /// assert_eq!(closest_points, xor_distance.closest(x_guess, count));
+    /// ```
+    ///
+    /// No closest points are the closest points of every position, the smallest one, zero, is
+    /// returned for an empty slice. A single point is reversed to the smallest position closer to
+    /// it than to any other point, `None` if the point is not present.
+    ///
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 8, 12]);
+    ///
+    /// assert_eq!(Some(0), xor_distance.reverse_closest(&[]));
+    /// assert_eq!(Some(12), xor_distance.reverse_closest(&[12]));
+    /// assert_eq!(None, xor_distance.reverse_closest(&[7]));
+    /// ```
+    pub fn reverse_closest(&self, closest_points: &[T]) -> Option<T> {
+        self.constraints_for(closest_points)
+            .map(|constraints| constraints.position())
+    }
+
+    /// Return a `Some(result)` with a position `x` such that `self.closest(x)` equals
+    /// closest_points and the number of such positions, `None` in case such a `x` does not
+    /// exists.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// let result = xor_distance.reverse_closest_detailed(&[8, 12]).unwrap();
+    ///
+    /// assert_eq!(8, result.position);
+    /// assert_eq!(6, result.free_bits);
+    /// assert_eq!(Some(64), result.solution_count());
+    /// ```
+    pub fn reverse_closest_detailed(&self, closest_points: &[T]) -> Option<ReverseResult<T>> {
+        let constraints = self.constraints_for(closest_points)?;
+
+        Some(ReverseResult {
+            position: constraints.position(),
+            free_bits: constraints.bits().undecided_count(),
+        })
+    }
+
+    /// Return an iterator over all positions `x` such that `self.closest(x)` equals
+    /// closest_points, in ascending order, empty in case there is no such a `x`.
+    ///
+    /// Every undecided bit doubles the number of positions, so the iterator should be bounded
+    /// (e.g. by `take`) unless the closest points decide most of the bits.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// for x in xor_distance.reverse_closest_iter(&[8, 12]) {
+    ///     assert_eq!(vec![8, 12], xor_distance.closest(x, 2));
+    /// }
+    ///
+    /// assert_eq!(Some(8), xor_distance.reverse_closest_iter(&[8, 12]).next());
+    /// assert_eq!(0, xor_distance.reverse_closest_iter(&[8, 0, 12]).count());
+    /// ```
+    pub fn reverse_closest_iter(&self, closest_points: &[T]) -> Solutions<T> {
+        match self.constraints_for(closest_points) {
+            Some(constraints) => constraints.solutions(),
+            None => Solutions::empty(),
+        }
+    }
+
+    /// Return the smallest and the largest position `x` such that `self.closest(x)` equals
+    /// closest_points, `None` in case there is no such a `x`.
+    ///
+    /// Positions between the bounds may produce different closest points, the exact solution
+    /// space is listed by `reverse_closest_ranges`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// assert_eq!(Some((8, 0b1111_1011)), xor_distance.reverse_closest_range(&[8, 12]));
+    /// assert_eq!(None, xor_distance.reverse_closest_range(&[8, 0, 12]));
+    /// ```
+    pub fn reverse_closest_range(&self, closest_points: &[T]) -> Option<(T, T)> {
+        self.constraints_for(closest_points)
+            .map(|constraints| constraints.bounds())
+    }
+
+    /// Return an iterator over the disjoint ranges `(min, max)` of all positions `x` such that
+    /// `self.closest(x)` equals closest_points, inclusive and in ascending order, empty in case
+    /// there is no such a `x`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// let ranges: Vec<(u8, u8)> = xor_distance.reverse_closest_ranges(&[8, 12]).collect();
+    ///
+    /// assert_eq!((8, 11), ranges[0]);
+    /// assert_eq!((24, 27), ranges[1]);
+    /// ```
+    pub fn reverse_closest_ranges(&self, closest_points: &[T]) -> Ranges<T> {
+        match self.constraints_for(closest_points) {
+            Some(constraints) => constraints.ranges(),
+            None => Ranges::empty(),
+        }
+    }
+
+    /// Return constraints of all inequalities implied by the closest points, `None` if they
+    /// conflict or the closest points don't list duplicate points as closest points do.
+    fn constraints_for(&self, closest_points: &[T]) -> Option<ConstraintSet<T>> {
+        if !self.lists_duplicates(closest_points) {
+            return None;
+        }
+
+        let mut constraints = ConstraintSet::new();
+
+        constraints
+            .ingest(self.inequalities_for(closest_points))
+            .ok()?;
+
+        Some(constraints)
+    }
+
+    /// Return whether every point of the closest points is listed as many times as it is present,
+    /// the last closest point at most as many times, as the count may cut its duplicates.
+    fn lists_duplicates(&self, closest_points: &[T]) -> bool {
+        self.wrongly_listed(closest_points).is_none()
+    }
+
+    /// Return the first point, in the order of points, not listed as by `lists_duplicates`.
+    fn wrongly_listed(&self, closest_points: &[T]) -> Option<T> {
+        let mut listed: BTreeMap<T, (usize, usize)> = BTreeMap::new();
+
+        for point in closest_points.iter() {
+            listed.entry(*point).or_insert((0, 0)).0 += 1;
+        }
+
+        if self.duplicates != DuplicatePolicy::Keep {
+            // Points are unique, no need to count them.
+            return listed
+                .iter()
+                .find(|(point, (count, _))| *count != 1 || !self.contains(**point))
+                .map(|(point, _)| *point);
+        }
+
+        for point in self.points() {
+            if let Some((_, present)) = listed.get_mut(point) {
+                *present += 1;
+            }
+        }
+
+        let last = closest_points.last();
+
+        listed
+            .iter()
+            .find(|(point, (count, present))| {
+                if Some(*point) == last {
+                    count > present
+                } else {
+                    count != present
+                }
+            })
+            .map(|(point, _)| *point)
+    }
+
+    /// Return a `Some(x)` such that `self.closest(x, n)` holds exactly the closest points in any
+    /// order, where `n` is the number of the closest points with their duplicates, and return
+    /// `None` in case such a `x` does not exists.
+    ///
+    /// It reverses the closest points known without their order, e.g. farms known to deliver to a
+    /// customer. Every closest point must be closer than every further point, which restricts one
+    /// bit per pair just as the ordered closest points do.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use std::collections::HashSet;
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// let farms: HashSet<u64> = [12, 8].iter().copied().collect();
+    /// let x = xor_distance.reverse_closest_unordered(&farms).unwrap();
+    ///
+    /// let mut closest = xor_distance.closest(x, 2);
+    /// closest.sort();
+    /// assert_eq!(vec![8, 12], closest);
+    ///
+    /// // Point `4` is closer than one of `0` and `12` to any position.
+    /// assert_eq!(None, xor_distance.reverse_closest_unordered(&[12, 0]));
+    /// ```
+    pub fn reverse_closest_unordered<'a, I>(&self, closest_points: I) -> Option<T>
+    where
+        I: IntoIterator<Item = &'a T>,
+        T: 'a,
+    {
+        let closest: BTreeSet<T> = closest_points.into_iter().copied().collect();
+
+        if !closest.iter().all(|point| self.contains(*point)) {
+            return None;
+        }
+
+        let inequalities = closest.iter().flat_map(|a| {
+            self.points()
+                .filter(|b| !closest.contains(b))
+                .map(move |b| (*a, *b))
+        });
+
+        let mut constraints = ConstraintSet::new();
+        constraints.ingest(inequalities).ok()?;
+
+        Some(constraints.position())
+    }
+
+    /// Return a certificate of a position `x` such that `self.closest(x)` equals closest_points,
+    /// `None` in case such a `x` does not exists.
+    ///
+    /// The certificate lists all inequalities and bits they fixed, so the result can be audited
+    /// by `verify_certificate`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// let certificate = xor_distance.reverse_closest_certified(&[8, 12]).unwrap();
+    ///
+    /// assert_eq!(vec![8, 12], xor_distance.closest(certificate.position, 2));
+    /// assert_eq!((8, 12), certificate.steps[0].inequality);
+    /// assert_eq!(2, certificate.steps[0].bit_index);
+    /// ```
+    pub fn reverse_closest_certified(&self, closest_points: &[T]) -> Option<Certificate<T>> {
+        if !self.lists_duplicates(closest_points) {
+            return None;
+        }
+
+        let mut constraints = ConstraintSet::new();
+        let mut steps = Vec::new();
+
+        for inequality in self.inequalities_for(closest_points) {
+            let (bit_index, value) = restricted_bit(inequality).ok()?;
+
+            constraints.add(inequality).ok()?;
+
+            steps.push(CertificateStep {
+                inequality,
+                bit_index,
+                value,
+            });
+        }
+
+        Some(Certificate {
+            position: constraints.position(),
+            steps,
+        })
+    }
+
+    /// Return trace of reversing the closest points, for every implied inequality the bit it
+    /// constrains and whether it decided the bit, was redundant or conflicting.
+    ///
+    /// Unlike `reverse_closest` it doesn't stop at the first contradiction, so the trace explains
+    /// also why no position exists.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{TraceOutcome, XorDistance};
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 3]);
+    ///
+    /// let explanation = xor_distance.reverse_closest_explain(&[2, 3]);
+    ///
+    /// assert_eq!(Some(2), explanation.position);
+    /// assert_eq!(
+    ///     TraceOutcome::Redundant { decided_by: (3, 0) },
+    ///     explanation.steps[2].outcome
+    /// );
+    /// assert_eq!(
+    ///     "2 ^ x < 3 ^ x: bit 0 = 0, decided\n\
+    ///      3 ^ x < 0 ^ x: bit 1 = 1, decided\n\
+    ///      3 ^ x < 1 ^ x: bit 1 = 1, redundant to 3 ^ x < 0 ^ x\n\
+    ///      position 2",
+    ///     explanation.to_string()
+    /// );
+    ///
+    /// // Both `1` and `2` can't be closer than `0` at the same time.
+    /// let explanation = xor_distance.reverse_closest_explain(&[1, 2]);
+    ///
+    /// assert_eq!(None, explanation.position);
+    /// assert_eq!(
+    ///     TraceOutcome::Conflicting { decided_by: (1, 2) },
+    ///     explanation.steps[1].outcome
+    /// );
+    /// ```
+    pub fn reverse_closest_explain(&self, closest_points: &[T]) -> Explanation<T> {
+        let wrongly_listed = self.wrongly_listed(closest_points);
+        let mut constraints = ConstraintSet::new();
+        let mut steps = Vec::new();
+
+        for inequality in self.inequalities_for(closest_points) {
+            let (bit_index, value) = match restricted_bit(inequality) {
+                Ok(bit) => bit,
+                Err(_) => unreachable!("Inequalities of closest points compare different points!"),
+            };
+            let previous = constraints.decided_by(bit_index);
+
+            let outcome = match (constraints.add(inequality), previous) {
+                (Err(InequalityError::Conflict(conflict)), _) => TraceOutcome::Conflicting {
+                    decided_by: conflict.decided_by,
+                },
+                (Err(InequalityError::EqualPoints { .. }), _) => unreachable!(),
+                (Ok(()), Some(decided_by)) => TraceOutcome::Redundant { decided_by },
+                (Ok(()), None) => TraceOutcome::Decided,
+            };
+
+            steps.push(TraceStep {
+                inequality,
+                bit_index,
+                value,
+                outcome,
+            });
+        }
+
+        let solvable = wrongly_listed.is_none()
+            && steps
+                .iter()
+                .all(|step| !matches!(step.outcome, TraceOutcome::Conflicting { .. }));
+
+        Explanation {
+            wrongly_listed,
+            steps,
+            position: if solvable {
+                Some(constraints.position())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Return a `Ok(x)` such that `self.closest(x)` equals closest_points, or all contradictions
+    /// found in case such a `x` does not exists.
+    ///
+    /// Unlike `reverse_closest_diagnosed` it doesn't stop at the first contradiction, so
+    /// systematically corrupted closest points can be diagnosed at once. A wrongly listed point
+    /// comes first, followed by all conflicts amongst the inequalities.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{Contradiction, XorDistance};
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 3]);
+    ///
+    /// // Both `1` and `2` can't be closer than `0` at the same time.
+    /// let contradictions = xor_distance.reverse_closest_conflicts(&[1, 2]).unwrap_err();
+    ///
+    /// match contradictions[0] {
+    ///     Contradiction::Inequalities { conflict, .. } => {
+    ///         assert_eq!((2, 0), conflict.inequality);
+    ///         assert_eq!((1, 2), conflict.decided_by);
+    ///     }
+    ///     Contradiction::Listing { .. } => unreachable!(),
+    /// }
+    ///
+    /// // No position has a closest point not present.
+    /// assert_eq!(
+    ///     Err(vec![Contradiction::Listing {
+    ///         point: 7,
+    ///         index: Some(0),
+    ///     }]),
+    ///     xor_distance.reverse_closest_conflicts(&[7])
+    /// );
+    /// ```
+    pub fn reverse_closest_conflicts(
+        &self,
+        closest_points: &[T],
+    ) -> Result<T, Vec<Contradiction<T>>> {
+        self.contradictions_for(closest_points, false)
+    }
+
+    /// Return a `Ok(x)` such that `self.closest(x)` equals closest_points, or the first
+    /// contradiction found in case such a `x` does not exists.
+    ///
+    /// The contradiction tells which points of the closest points make them impossible, by their
+    /// indexes, so it can be reported without searching the closest points again.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{Contradiction, XorDistance};
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 3]);
+    ///
+    /// assert_eq!(Ok(2), xor_distance.reverse_closest_diagnosed(&[2, 3]));
+    ///
+    /// // Both `1` and `2` can't be closer than `0` at the same time.
+    /// match xor_distance.reverse_closest_diagnosed(&[1, 2]).unwrap_err() {
+    ///     Contradiction::Inequalities {
+    ///         conflict,
+    ///         indexes,
+    ///         decided_by_indexes,
+    ///     } => {
+    ///         assert_eq!((2, 0), conflict.inequality);
+    ///         assert_eq!(1, conflict.bit_index);
+    ///         assert_eq!((Some(1), None), indexes);
+    ///         assert_eq!((Some(0), Some(1)), decided_by_indexes);
+    ///     }
+    ///     Contradiction::Listing { .. } => unreachable!(),
+    /// }
+    ///
+    /// assert_eq!(
+    ///     Err(Contradiction::Listing {
+    ///         point: 7,
+    ///         index: Some(1),
+    ///     }),
+    ///     xor_distance.reverse_closest_diagnosed(&[2, 7])
+    /// );
+    /// ```
+    pub fn reverse_closest_diagnosed(&self, closest_points: &[T]) -> Result<T, Contradiction<T>> {
+        self.contradictions_for(closest_points, true)
+            .map_err(|contradictions| contradictions[0])
+    }
+
+    /// Return the position of the closest points, or their contradictions, only the first one if
+    /// `first_only`.
+    fn contradictions_for(
+        &self,
+        closest_points: &[T],
+        first_only: bool,
+    ) -> Result<T, Vec<Contradiction<T>>> {
+        let mut contradictions = Vec::new();
+
+        if let Some(point) = self.wrongly_listed(closest_points) {
+            contradictions.push(Contradiction::Listing {
+                point,
+                index: closest_points.iter().position(|listed| *listed == point),
+            });
+
+            if first_only {
+                return Err(contradictions);
+            }
+        }
+
+        let mut constraints = ConstraintSet::new();
+        let mut deciding: BTreeMap<usize, (Option<usize>, Option<usize>)> = BTreeMap::new();
+
+        for (inequality, indexes) in self.indexed_inequalities_for(closest_points) {
+            match constraints.add(inequality) {
+                Ok(()) => {
+                    if let Ok((bit_index, _)) = restricted_bit(inequality) {
+                        deciding.entry(bit_index).or_insert(indexes);
+                    }
+                }
+                Err(InequalityError::EqualPoints { .. }) => {
+                    unreachable!("Inequalities of closest points compare different points!")
+                }
+                Err(InequalityError::Conflict(conflict)) => {
+                    contradictions.push(Contradiction::Inequalities {
+                        decided_by_indexes: deciding[&conflict.bit_index],
+                        conflict,
+                        indexes,
+                    });
+
+                    if first_only {
+                        return Err(contradictions);
+                    }
+                }
+            }
+        }
+
+        if contradictions.is_empty() {
+            Ok(constraints.position())
+        } else {
+            Err(contradictions)
+        }
+    }
+
+    /// Return a `Some(x)` consistent with the known entries of closest points, where `None`
+    /// entries stand for unknown points, and return None in case such a `x` does not exists.
+    ///
+    /// The known point at index `k` must be the `k`-th closest point, so exactly `k` points,
+    /// duplicates included, must be closer than it or tie with it ahead of it. The number of
+    /// closer points is a sum over the bits of `x`, which are searched from the most significant
+    /// one with backtracking, so the smallest such `x` is returned. Bits which change no count
+    /// are never tried both ways, yet pathological points make the search exponential in the
+    /// number of the bits. If all entries are known, it equals `reverse_closest`.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    /// ]);
+    ///
+    /// // The second and fourth closest points are unknown.
+    /// let closest_points = vec![Some(8), None, Some(2), None, Some(1)];
+    /// let x_guess = xor_distance.reverse_closest_wildcard(&closest_points).unwrap();
+    ///
+    /// // The known points are listed at their indexes.
+    /// let closest = xor_distance.closest(x_guess, 5);
+    /// assert_eq!((8, 2, 1), (closest[0], closest[2], closest[4]));
+    /// ```
+    pub fn reverse_closest_wildcard(&self, closest_points: &[Option<T>]) -> Option<T> {
+        let known: Vec<T> = closest_points.iter().filter_map(|point| *point).collect();
+
+        if known.len() == closest_points.len() {
+            return self.reverse_closest(&known);
+        }
+
+        if closest_points.len() > self.len() {
+            return None;
+        }
+
+        let mut sorted: Vec<T> = self.points().copied().collect();
+        sorted.sort_unstable();
+
+        // Number of the points within the inclusive range.
+        let within = |low: T, high: T| {
+            sorted.partition_point(|point| *point <= high)
+                - sorted.partition_point(|point| *point < low)
+        };
+
+        // Unknown entries may be any points, so known points must only not be listed more times
+        // than they are present.
+        let mut listed: BTreeMap<T, usize> = BTreeMap::new();
+        for point in known.iter() {
+            *listed.entry(*point).or_insert(0) += 1;
+        }
+        if listed
+            .iter()
+            .any(|(point, count)| *count > within(*point, *point))
+        {
+            return None;
+        }
+
+        let ranks: Vec<Rank<T>> = closest_points
+            .iter()
+            .enumerate()
+            .filter_map(|(index, point)| point.map(|point| (index, point)))
+            .map(|(index, point)| {
+                // Points in the sibling subtree of every bit, closer than the point if the bit of
+                // `x` differs from the bit of the point.
+                let siblings: Vec<usize> = (0..self.bit_size)
+                    .map(|bit_index| {
+                        let low_mask = (T::one() << bit_index) - T::one();
+                        let sibling = (point ^ (T::one() << bit_index)) & !low_mask;
+
+                        within(sibling, sibling | low_mask)
+                    })
+                    .collect();
+                let mut below = vec![0; self.bit_size + 1];
+                for bit_index in 0..self.bit_size {
+                    below[bit_index + 1] = below[bit_index] + siblings[bit_index];
+                }
+
+                // Duplicates of the point tie with it, any of them may be listed at the index.
+                Rank {
+                    point,
+                    lowest: (index + 1).saturating_sub(within(point, point)),
+                    highest: index,
+                    siblings,
+                    below,
+                }
+            })
+            .collect();
+
+        let closer = vec![0; ranks.len()];
+        if ranks
+            .iter()
+            .any(|rank| rank.below[self.bit_size] < rank.lowest)
+        {
+            return None;
+        }
+
+        Self::search_ranks(&ranks, self.bit_size, T::zero(), &closer)
+    }
+
+    /// Return the smallest `x` with the bits below `bits` free, such that every rank is met,
+    /// where `closer` holds the counts of points closer than the ranked points by the higher bits.
+    fn search_ranks(ranks: &[Rank<T>], bits: usize, x: T, closer: &[usize]) -> Option<T> {
+        if bits == 0 {
+            return Some(x);
+        }
+
+        let bit_index = bits - 1;
+        let bit = T::one() << bit_index;
+
+        // Both values of a bit restricting no rank lead to the same counts, zero is smaller.
+        let values: &[bool] = if ranks.iter().all(|rank| rank.siblings[bit_index] == 0) {
+            &[false]
+        } else {
+            &[false, true]
+        };
+
+        for value in values.iter() {
+            let next: Vec<usize> = ranks
+                .iter()
+                .zip(closer.iter())
+                .map(
+                    |(rank, closer)| match (rank.point & bit != T::zero()) != *value {
+                        true => closer + rank.siblings[bit_index],
+                        false => *closer,
+                    },
+                )
+                .collect();
+
+            let feasible = ranks.iter().zip(next.iter()).all(|(rank, closer)| {
+                *closer <= rank.highest && closer + rank.below[bit_index] >= rank.lowest
+            });
+
+            if feasible {
+                let x = if *value { x | bit } else { x };
+
+                if let Some(x) = Self::search_ranks(ranks, bit_index, x, &next) {
+                    return Some(x);
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn form_inequalities(&self, closest_points: &[T]) -> Vec<(T, T)> {
+        self.inequalities_for(closest_points).collect()
+    }
+
+    /// Return whether the closest points of `x` are the provided closest points, as
+    /// `self.closest(x, closest_points.len()) == closest_points` without querying them.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// assert!(xor_distance.verify(10, &[8, 12]));
+    /// assert!(!xor_distance.verify(10, &[12, 8]));
+    /// ```
+    pub fn verify(&self, x: T, closest_points: &[T]) -> bool {
+        self.verify_detailed(x, closest_points).is_ok()
+    }
+
+    /// Return `Ok(())` if the closest points of `x` are the provided closest points, the first
+    /// violation otherwise. Listing of the points is checked first, the inequalities in the
+    /// order of `inequalities_for` then.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::{Violation, XorDistance};
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// assert_eq!(Ok(()), xor_distance.verify_detailed(10, &[8, 12]));
+    /// assert_eq!(
+    ///     Err(Violation::Inequality { inequality: (12, 8) }),
+    ///     xor_distance.verify_detailed(10, &[12, 8])
+    /// );
+    /// assert_eq!(
+    ///     Err(Violation::Listing { point: 7 }),
+    ///     xor_distance.verify_detailed(10, &[8, 7])
+    /// );
+    /// ```
+    pub fn verify_detailed(&self, x: T, closest_points: &[T]) -> Result<(), Violation<T>> {
+        if let Some(point) = self.wrongly_listed(closest_points) {
+            return Err(Violation::Listing { point });
+        }
+
+        match self
+            .inequalities_for(closest_points)
+            .find(|(a, b)| *a ^ x >= *b ^ x)
+        {
+            Some(inequality) => Err(Violation::Inequality { inequality }),
+            None => Ok(()),
+        }
+    }
+
+    /// Return a lazy iterator over all inequalities `a ^ x < b ^ x` implied by the closest points,
+    /// first amongst the closest points in their order and then between the last closest point
+    /// and every further point.
+    ///
+    /// # Examples
+    /// ```
+    /// extern crate xor_distance_exercise;
+    ///
+    /// use xor_distance_exercise::xor_distance::XorDistance;
+    ///
+    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
+    ///
+    /// let mut inequalities = xor_distance.inequalities_for(&[8, 12]);
+    ///
+    /// assert_eq!(Some((8, 12)), inequalities.next());
+    /// assert_eq!(Some((12, 0)), inequalities.next());
+    /// assert_eq!(4, inequalities.count());
+    /// ```
+    pub fn inequalities_for<'a>(
+        &'a self,
+        closest_points: &'a [T],
+    ) -> impl Iterator<Item = (T, T)> + 'a {
+        self.compose_closest_points_inequalities(closest_points)
+            .chain(self.compose_further_points_inequalities(closest_points))
+    }
+
+    /// Return the inequalities of `inequalities_for` with indexes of their points in the closest
+    /// points, `None` for further points.
+    fn indexed_inequalities_for<'a>(
+        &'a self,
+        closest_points: &'a [T],
+    ) -> impl Iterator<Item = ((T, T), (Option<usize>, Option<usize>))> + 'a {
+        // Equal neighbours are skipped the same way as by `compose_closest_points_inequalities`.
+        let closest_indexes = (1..closest_points.len())
+            .filter(move |index| closest_points[index - 1] != closest_points[*index])
+            .map(|index| (Some(index - 1), Some(index)));
+        let last_index = closest_points.len().checked_sub(1);
+
+        self.inequalities_for(closest_points)
+            .zip(closest_indexes.chain(iter::repeat((last_index, None))))
+    }
+
+    /// Compose inequalities pairs amongst closest points and their order.
+    ///
+    /// We have a set of all existing unique points, represented as:
+    /// `P = [p1, p2, p3, p4, p5, ..., p(m-1), p(m)]`
+    ///
+    /// We have a position number represented by `x` and we also have a P subset of selected points
+    /// that are the closest points to `x` by XOR distance metric.
+    ///
+    /// The closest points are represented as:
+    /// `C = [c1, c2, c3, c4, c5, ..., c(n-1), c(n)]`
+    ///
+    /// and the following inequality applies:
+    /// `c1 ^ x < c2 ^ x < c3 ^ x < c4 ^ x < c5 ^ x < ... < c(n-1) ^ x < c(n) ^ x`
+    ///
+    /// Separating it into simple `(n-1)` inequalities:
+    /// `c1 ^ x < c2 ^ x`
+    /// `c2 ^ x < c3 ^ x`
+    /// `c3 ^ x < c4 ^ x`
+    /// `c4 ^ x < c5 ^ x`
+    /// `...`
+    /// `c(n-1) ^ x < c(n) ^ x`
+    ///
+    /// These `(n-1)` inequalities are what this method returns, except for inequalities of equal
+    /// neighbours. Duplicate points are equally distant from any `x`, they tie in every order and
+    /// restrict nothing.
+    fn compose_closest_points_inequalities<'a>(
+        &self,
+        closest_points: &'a [T],
+    ) -> impl Iterator<Item = (T, T)> + 'a {
+        // Point `a` must be closer to the point `x` then point `b`. The inequality is:
+        // `a ^ x < b ^ x` , where point `x` is the position being searched for.
+        closest_points
+            .windows(2)
+            .filter(|pair| pair[0] != pair[1])
+            .map(|pair| (pair[0], pair[1]))
+    }
+
+    /// Compose inequalities pairs between last closest point and all further points.
+    ///
+    /// We have a set of all existing unique points, represented as:
+    /// `P = [p1, p2, p3, p4, p5, ..., p(n-1), p(n)]`
+    ///
+    /// We have a position number represented by `x` and we also have a P subset of selected points
+    /// that are the closest points to `x` by XOR distance metric.
+    ///
+    /// The closest points are represented as:
+    /// `[c1, c2, c3, c4, c5, ..., c(n-1), c(n)]`
+    ///
+    /// The further points are all unselected points from P and are represented as (U = P - C):
+    /// `U = [u1, u2, u3, u4, u5, ..., u(n-1), u(n)]`
+    ///
+    /// and the following inequalities applies:
+    /// `c(n) ^ x < u1 ^ x`
+    /// `c(n) ^ x < u2 ^ x`
+    /// `c(n) ^ x < u3 ^ x`
+    /// `c(n) ^ x < u4 ^ x`
+    /// `c(n) ^ x < u5 ^ x`
+    /// ...`
+    /// `c(n) ^ x < u(m) ^ x`
+    ///
+    /// These inequalities are what this method returns.
+    fn compose_further_points_inequalities<'a>(
+        &'a self,
+        closest_points: &'a [T],
+    ) -> impl Iterator<Item = (T, T)> + 'a {
+        // Get the n-th closest point to `x` where the n is number of closest points.
+        closest_points.last().into_iter().flat_map(move |a| {
+            // Further points are the ones that were not selected as the closest. Point `a` must
+            // be closer to the point `x` then any of them, the inequality is `a ^ x < b ^ x`.
+            self.points()
+                .filter(move |b| !closest_points.contains(b))
+                .map(move |b| (*a, *b))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        verify_certificate, Algorithm, Conflict, Contradiction, DuplicatePolicy, IndexKind,
+        RankChange, RankingDiff, RemovalKind, TieBreak, TraceOutcome, Violation, XorDistance,
+        XorDistanceConfig, XorDistanceError,
+    };
+    use crate::bitops::BitOps;
+    use core::fmt;
+    use num_traits::{PrimInt, Unsigned};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeSet;
+
+    #[test]
+    fn compose_closest_points_inequalities() {
+        let points: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+        let xor_distance = XorDistance::new(points.clone());
+
+        let closest_points: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6];
+
+        // Test first example, count < number of points.
+        let result: Vec<(u8, u8)> = xor_distance
+            .compose_closest_points_inequalities(&closest_points)
+            .collect();
+        let expected: Vec<(u8, u8)> = vec![(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6)];
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn compose_further_points_inequalities() {
+        let points: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+        let xor_distance = XorDistance::new(points.clone());
+
+        let closest_points: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6];
+
+        // Test first example, count < number of points.
+        let result: Vec<(u8, u8)> = xor_distance
+            .compose_further_points_inequalities(&closest_points)
+            .collect();
+        let expected: Vec<(u8, u8)> = vec![(6, 7), (6, 8), (6, 9), (6, 10), (6, 11), (6, 12)];
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn closest_u64() {
+        let points: Vec<u64> = vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ];
+        let xor_distance = XorDistance::new(points.clone());
+
+        // Test first example, count < number of points.
+        let result = xor_distance.closest(300, 4);
+        let expected = vec![444, 445, 408, 409];
+
+        assert_eq!(expected, result);
+
+        // Test second example, count < number of points.
+        let result = xor_distance.closest(10, 10);
+        let expected = vec![8, 12, 2, 0, 1, 6, 4, 18, 19, 22];
+
+        assert_eq!(expected, result);
+
+        // Test third example, count < number of points.
+        let result = xor_distance.closest(888, 12);
+        let expected = vec![444, 445, 408, 409, 410, 406, 407, 18, 19, 20, 21, 22];
+
+        assert_eq!(expected, result);
+
+        // Test situation with count = 0.
+        let result = xor_distance.closest(10, 0);
+        let expected: Vec<u64> = Vec::new();
+
+        assert_eq!(expected, result);
+
+        // Test situation with count = number of points.
+        let result = xor_distance.closest(10, points.len());
+        let expected = vec![
+            8, 12, 2, 0, 1, 6, 4, 18, 19, 22, 20, 21, 410, 408, 409, 406, 407, 444, 445,
+        ];
+
+        assert_eq!(expected, result);
+        assert_eq!(points.len(), expected.len());
+
+        // Test situation with count > number of points.
+        let result = xor_distance.closest(10, points.len() + 1);
+        let expected = vec![
+            8, 12, 2, 0, 1, 6, 4, 18, 19, 22, 20, 21, 410, 408, 409, 406, 407, 444, 445,
+        ];
+
+        assert_eq!(expected, result);
+        assert_eq!(points.len(), expected.len());
+    }
+
+    #[test]
+    fn closest_u8() {
+        let points: Vec<u8> = vec![
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 20, 21, 22, 23, 24, 100, 220, 230, 240, 250,
+        ];
+        let xor_distance = XorDistance::new(points.clone());
+
+        // Test first example, count < number of points.
+        let result = xor_distance.closest(18, 8);
+        let expected = vec![22, 23, 20, 21, 24, 2, 3, 0];
+
+        assert_eq!(expected, result);
+
+        // Test second example, count < number of points.
+        let result = xor_distance.closest(200, 14);
+        let expected = vec![220, 230, 250, 240, 100, 8, 9, 10, 12, 0, 1, 2, 3, 4];
+
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn reverse_closest_u64() {
+        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ]);
+
+        let closest_points = vec![8, 12, 2, 0, 1, 6, 4, 18, 19, 22];
+        let count = closest_points.len();
+        let guess_pos = xor_distance.reverse_closest(&closest_points).unwrap();
+
+        assert_eq!(closest_points, xor_distance.closest(guess_pos, count));
+    }
+
+    #[test]
+    fn reverse_closest_u8() {
+        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 20, 21, 22, 23, 24, 100, 220, 230, 240, 250,
+        ]);
+
+        let closest_points = vec![220, 230, 250, 240, 100, 8, 9, 10, 12, 0, 1, 2, 3, 4];
+        let count = closest_points.len();
+        let guess_pos = xor_distance.reverse_closest(&closest_points).unwrap();
+
+        assert_eq!(closest_points, xor_distance.closest(guess_pos, count));
+    }
+
+    #[test]
+    fn reverse_closest_invalid_input() {
+        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ]);
+
+        let closest_points = vec![8, 2, 12, 6, 1, 0, 4, 18, 22];
+
+        // The output is `None` as there's no `x` that would satisfy the provided closest points
+        // input.
+        assert!(xor_distance.reverse_closest(&closest_points).is_none());
+    }
+
+    #[test]
+    fn reverse_closest_empty_and_single() {
+        let points: Vec<u8> = vec![0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22];
+        let xor_distance = XorDistance::new(points.clone());
+
+        // Every position has no closest points.
+        assert_eq!(Some(0), xor_distance.reverse_closest(&[]));
+        let result = xor_distance.reverse_closest_detailed(&[]).unwrap();
+        assert_eq!((0, 8), (result.position, result.free_bits));
+        assert_eq!(256, xor_distance.reverse_closest_iter(&[]).count());
+
+        // Even if there are no points at all.
+        let empty: XorDistance<u8> = XorDistance::new(vec![]);
+        assert_eq!(Some(0), empty.reverse_closest(&[]));
+        assert_eq!(None, empty.reverse_closest(&[0]));
+
+        for point in points.iter() {
+            // Exactly the positions having the point as the closest one.
+            let expected: Vec<u8> = (0..=u8::MAX)
+                .filter(|x| xor_distance.closest(*x, 1) == vec![*point])
+                .collect();
+
+            assert_eq!(
+                expected,
+                xor_distance
+                    .reverse_closest_iter(&[*point])
+                    .collect::<Vec<u8>>()
+            );
+            assert_eq!(
+                expected.first().copied(),
+                xor_distance.reverse_closest(&[*point])
+            );
+        }
+
+        // The point is not present.
+        assert_eq!(None, xor_distance.reverse_closest(&[3]));
+    }
+
+    #[test]
+    fn verify() {
+        let mut rng = StdRng::seed_from_u64(1538);
+        // Small keys, so many points are duplicated.
+        let points: Vec<u8> = (0..40).map(|_| rng.gen_range(0, 64)).collect();
+
+        for duplicates in [DuplicatePolicy::Keep, DuplicatePolicy::Collapse].iter() {
+            let xor_distance = XorDistance::builder(points.clone())
+                .duplicates(*duplicates)
+                .build();
+
+            for _ in 0..300 {
+                let x: u8 = rng.gen();
+                let closest = match rng.gen_range(0, 3) {
+                    0 => xor_distance.closest(x, rng.gen_range(0, 12)),
+                    1 => xor_distance.closest(rng.gen(), rng.gen_range(0, 12)),
+                    _ => (0..rng.gen_range(0, 4))
+                        .map(|_| rng.gen_range(0, 64))
+                        .collect(),
+                };
+
+                assert_eq!(
+                    xor_distance.closest(x, closest.len()) == closest,
+                    xor_distance.verify(x, &closest),
+                    "Verification of {:?} for {} differs!",
+                    closest,
+                    x
+                );
+            }
+        }
+
+        let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 3, 3, 8]);
+
+        assert!(xor_distance.verify(2, &[3, 3, 0]));
+        assert!(xor_distance.verify(2, &[3]));
+        assert_eq!(
+            Err(Violation::Listing { point: 3 }),
+            xor_distance.verify_detailed(2, &[3, 0])
+        );
+        assert_eq!(
+            Err(Violation::Inequality { inequality: (8, 0) }),
+            xor_distance.verify_detailed(2, &[3, 3, 8])
+        );
+        assert!(xor_distance.verify(77, &[]));
+    }
+
+    #[test]
+    fn reverse_closest_explain() {
+        let mut rng = StdRng::seed_from_u64(1539);
+        let points: Vec<u8> = (0..60).map(|_| rng.gen()).collect();
+        let xor_distance = XorDistance::new(points.clone());
+
+        for _ in 0..200 {
+            let closest = if rng.gen() {
+                xor_distance.closest(rng.gen(), rng.gen_range(0, 8))
+            } else {
+                (0..rng.gen_range(0, 4))
+                    .map(|_| points[rng.gen_range(0, points.len())])
+                    .collect()
+            };
+
+            let explanation = xor_distance.reverse_closest_explain(&closest);
+
+            assert_eq!(xor_distance.reverse_closest(&closest), explanation.position);
+            assert_eq!(
+                xor_distance.inequalities_for(&closest).collect::<Vec<_>>(),
+                explanation
+                    .steps
+                    .iter()
+                    .map(|step| step.inequality)
+                    .collect::<Vec<_>>()
+            );
+
+            // Every bit is decided once, redundant steps agree with the deciding one.
+            let mut decided = BTreeSet::new();
+
+            for step in explanation.steps.iter() {
+                match step.outcome {
+                    TraceOutcome::Decided => assert!(decided.insert(step.bit_index)),
+                    TraceOutcome::Redundant { decided_by } => {
+                        let first = explanation
+                            .steps
+                            .iter()
+                            .find(|first| first.inequality == decided_by)
+                            .unwrap();
+
+                        assert_eq!((first.bit_index, first.value), (step.bit_index, step.value));
+                    }
+                    TraceOutcome::Conflicting { .. } => assert!(explanation.position.is_none()),
+                }
+            }
+        }
+
+        // Wrongly listed point makes any trace unsolvable.
+        let explanation = XorDistance::new(vec![0u8, 3, 3]).reverse_closest_explain(&[3, 0]);
+
+        assert_eq!(Some(3), explanation.wrongly_listed);
+        assert_eq!(None, explanation.position);
+        assert!(explanation
+            .to_string()
+            .starts_with("point 3 is listed wrongly\n"));
+    }
+
+    #[test]
+    fn reverse_closest_edge_cases() {
+        // Edge cases of the reverse solver: empty slices, full-width values and duplicates.
+        let cases: Vec<(Vec<u8>, Vec<u8>, bool)> = vec![
+            (vec![], vec![], true),
+            (vec![], vec![1], false),
+            (vec![0, 255], vec![255, 0], true),
+            (vec![0, 255], vec![0, 0], false),
+            (vec![0, 255, 128], vec![255, 128, 0], true),
+            (vec![3, 3, 1], vec![3, 3], true),
+            (vec![3, 3, 1], vec![3], true),
+            (vec![3, 3, 1], vec![3, 1], false),
+            (vec![3, 3, 1], vec![3, 3, 1, 1], false),
+            (vec![5], vec![5, 5], false),
+            (vec![0, 1, 2], vec![2, 2, 0], false),
+        ];
+
+        for (points, closest, reversible) in cases.iter() {
+            let xor_distance = XorDistance::new(points.clone());
+
+            match xor_distance.reverse_closest(closest) {
+                Some(position) => {
+                    assert!(reversible);
+                    assert_eq!(closest, &xor_distance.closest(position, closest.len()));
+                }
+                None => assert!(!reversible),
+            }
+        }
+
+        let xor_distance: XorDistance<u128> = XorDistance::new(vec![0, u128::MAX, 1 << 127]);
+        let position = xor_distance
+            .reverse_closest(&[u128::MAX, 1 << 127])
+            .unwrap();
+        assert_eq!(vec![u128::MAX, 1 << 127], xor_distance.closest(position, 2));
+    }
+
+    #[test]
+    fn width_boundaries() {
+        fn check<T: PrimInt + BitOps + Unsigned + fmt::Debug>(bit_size: usize) {
+            let high = T::one() << (bit_size - 1);
+            let middle = T::one() << (bit_size / 2);
+            let points = vec![
+                T::zero(),
+                T::one(),
+                middle - T::one(),
+                middle,
+                high - T::one(),
+                high,
+                T::max_value() - T::one(),
+                T::max_value(),
+            ];
+            let positions = [
+                T::zero(),
+                T::one(),
+                middle,
+                high,
+                high - T::one(),
+                T::max_value(),
+            ];
+
+            for algorithm in [Algorithm::Sort, Algorithm::Heap, Algorithm::Radix].iter() {
+                for index in [IndexKind::Sort, IndexKind::Trie].iter() {
+                    let xor_distance = XorDistance::builder(points.clone())
+                        .algorithm(*algorithm)
+                        .index(*index)
+                        .build();
+                    assert_eq!(bit_size, xor_distance.bit_size());
+
+                    for x in positions.iter() {
+                        let mut expected = points.clone();
+                        expected.sort_by_key(|point| *point ^ *x);
+
+                        for count in 0..=points.len() {
+                            let closest = xor_distance.closest(*x, count);
+                            assert_eq!(expected[..count].to_vec(), closest);
+
+                            let position = xor_distance.reverse_closest(&closest).unwrap();
+                            assert_eq!(closest, xor_distance.closest(position, count));
+                        }
+                    }
+                }
+            }
+
+            // The most significant bit decides between the halves.
+            let xor_distance = XorDistance::new(vec![T::zero(), high]);
+            let position = xor_distance.reverse_closest(&[high, T::zero()]).unwrap();
+            assert_eq!(high, position);
+        }
+
+        check::<u8>(8);
+        check::<u16>(16);
+        check::<u32>(32);
+        check::<u64>(64);
+        check::<u128>(128);
+        check::<usize>(usize::BITS as usize);
+    }
+
+    #[test]
+    fn closest_trie_index() {
+        let points: Vec<u64> = vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ];
+        let sorted = XorDistance::builder(points.clone())
+            .index(IndexKind::Sort)
+            .build();
+        let indexed = XorDistance::new(points.clone());
+
+        assert_eq!(IndexKind::Sort, sorted.index_kind());
+        assert_eq!(IndexKind::Trie, indexed.index_kind());
+
+        for x in [0, 10, 300, 888, u64::MAX].iter() {
+            for count in 0..points.len() + 2 {
+                assert_eq!(sorted.closest(*x, count), indexed.closest(*x, count));
+            }
+        }
+    }
+
+    #[test]
+    fn interleaved_mutations_and_queries() {
+        let mut rng = StdRng::seed_from_u64(1544);
+
+        for removal in [RemovalKind::Shift, RemovalKind::Tombstone].iter() {
+            let mut indexed: XorDistance<u32> =
+                XorDistance::builder(vec![]).removal(*removal).build();
+            let mut sorted: XorDistance<u32> =
+                XorDistance::builder(vec![]).index(IndexKind::Sort).build();
+
+            for _ in 0..3000 {
+                let point = rng.gen_range(0, 200);
+
+                if rng.gen_range(0, 5) < 3 {
+                    assert!(indexed.add_point(point));
+                    assert!(sorted.add_point(point));
+                } else {
+                    assert_eq!(sorted.remove_point(point), indexed.remove_point(point));
+                }
+
+                assert_eq!(sorted.len(), indexed.len());
+
+                let x = rng.gen_range(0, 256);
+                let count = rng.gen_range(0, 20);
+                let closest = indexed.closest(x, count);
+
+                assert_eq!(sorted.closest(x, count), closest);
+                assert_eq!(
+                    sorted.closest(x, 1).first().copied(),
+                    indexed.nth_closest(x, 0)
+                );
+                assert_eq!(sorted.contains(x), indexed.contains(x));
+            }
+        }
+    }
+
+    #[test]
+    fn nearest_neighbor_of_each() {
+        let mut rng = StdRng::seed_from_u64(1552);
+
+        for index in [IndexKind::Sort, IndexKind::Trie].iter() {
+            for len in [0, 1, 2, 3, 50, 300].iter() {
+                let points: Vec<u16> = (0..*len).map(|_| rng.gen_range(0, 1000)).collect();
+                let xor_distance = XorDistance::builder(points.clone()).index(*index).build();
+
+                let neighbors = xor_distance.nearest_neighbor_of_each();
+
+                if *len < 2 {
+                    assert!(neighbors.is_empty());
+                    continue;
+                }
+
+                for (position, (point, neighbor)) in neighbors.iter().enumerate() {
+                    let closest = points
+                        .iter()
+                        .enumerate()
+                        .filter(|(other, _)| *other != position)
+                        .map(|(_, other)| other ^ point)
+                        .min()
+                        .unwrap();
+
+                    assert_eq!(points[position], *point);
+                    assert_eq!(closest, neighbor ^ point);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn closest_and_farthest_pairs() {
+        let mut rng = StdRng::seed_from_u64(1554);
+
+        for index in [IndexKind::Sort, IndexKind::Trie].iter() {
+            for len in [0, 1, 2, 3, 10, 40, 100].iter() {
+                let points: Vec<u8> = (0..*len).map(|_| rng.gen()).collect();
+                let xor_distance = XorDistance::builder(points.clone()).index(*index).build();
+
+                let mut pairs = Vec::new();
+                for (i, a) in points.iter().enumerate() {
+                    for b in points[i + 1..].iter() {
+                        pairs.push((*a.min(b), *a.max(b)));
+                    }
+                }
+
+                let closest = pairs.iter().map(|(a, b)| a ^ b).min();
+                let farthest = pairs.iter().map(|(a, b)| a ^ b).max();
+
+                // The smallest points of the closest and the farthest pairs.
+                let expected_closest = pairs
+                    .iter()
+                    .filter(|(a, b)| Some(a ^ b) == closest)
+                    .min()
+                    .copied();
+                let expected_farthest = pairs
+                    .iter()
+                    .filter(|(a, b)| Some(a ^ b) == farthest)
+                    .min()
+                    .copied();
+
+                assert_eq!(expected_closest, xor_distance.closest_pair());
+                assert_eq!(expected_farthest, xor_distance.farthest_pair());
+            }
+        }
+    }
+
+    #[test]
+    fn clusters() {
+        let mut rng = StdRng::seed_from_u64(1553);
+        let points: Vec<u16> = (0..500).map(|_| rng.gen()).chain(vec![7, 7]).collect();
+        let xor_distance = XorDistance::new(points.clone());
+
+        for prefix_bits in [0, 1, 3, 8, 15, 16, 20].iter() {
+            let clusters = xor_distance.clusters(*prefix_bits);
+            let mask = u16::mask_high(*prefix_bits);
+            let prefix = |cluster: &Vec<u16>| cluster[0] & mask;
+
+            // All the points are clustered exactly once, in the ascending order.
+            let mut sorted = points.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, clusters.concat());
+
+            for cluster in clusters.iter() {
+                assert!(cluster.iter().all(|point| point & mask == prefix(cluster)));
+            }
+            for pair in clusters.windows(2) {
+                assert!(prefix(&pair[0]) < prefix(&pair[1]));
+            }
+        }
+
+        assert_eq!(1, xor_distance.clusters(0).len());
+        assert!(xor_distance.clusters(16).contains(&vec![7, 7]));
+        assert!(XorDistance::<u8>::default().clusters(4).is_empty());
+    }
+
+    #[test]
+    fn nth_closest() {
+        let points: Vec<u64> = vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ];
+        let sorted = XorDistance::builder(points.clone())
+            .index(IndexKind::Sort)
+            .build();
+        let indexed = XorDistance::new(points.clone());
+
+        let expected = sorted.closest(10, points.len());
+
+        for (n, point) in expected.iter().enumerate() {
+            assert_eq!(Some(*point), sorted.nth_closest(10, n));
+            assert_eq!(Some(*point), indexed.nth_closest(10, n));
+        }
+
+        assert_eq!(None, sorted.nth_closest(10, points.len()));
+        assert_eq!(None, indexed.nth_closest(10, points.len()));
+    }
+
+    #[test]
+    fn add_remove_point() {
+        for index in [IndexKind::Sort, IndexKind::Trie].iter() {
+            let mut xor_distance: XorDistance<u64> =
+                XorDistance::builder(vec![0, 1, 2, 4]).index(*index).build();
+
+            xor_distance.add_point(12);
+            assert_eq!(vec![12, 4], xor_distance.closest(12, 2));
+
+            assert!(xor_distance.remove_point(4));
+            assert!(!xor_distance.remove_point(4));
+            assert_eq!(vec![12, 0], xor_distance.closest(12, 2));
+            assert_eq!(Some(2), xor_distance.nth_closest(12, 3));
+            assert_eq!(None, xor_distance.nth_closest(12, 4));
+
+            // Removed points are not part of the reverse engineering either.
+            let closest_points = xor_distance.closest(13, 4);
+            let guess_pos = xor_distance.reverse_closest(&closest_points).unwrap();
+            assert_eq!(closest_points, xor_distance.closest(guess_pos, 4));
+        }
+    }
+
+    #[test]
+    fn duplicate_policy() {
+        let kept: XorDistance<u8> = XorDistance::new(vec![5, 1, 5, 9]);
+
+        assert_eq!(vec![5, 5], kept.closest(4, 2));
+        for closest in [vec![5], vec![5, 5], vec![1, 5], vec![1, 5, 5, 9]].iter() {
+            let position = kept.reverse_closest(closest).unwrap();
+            assert_eq!(*closest, kept.closest(position, closest.len()));
+        }
+
+        // Duplicates are listed in a row and only the last closest point may miss some.
+        for closest in [vec![5, 1], vec![5, 5, 5], vec![3]].iter() {
+            assert_eq!(None, kept.reverse_closest(closest));
+            assert_eq!(None, kept.reverse_closest_certified(closest));
+            assert_eq!(0, kept.reverse_closest_iter(closest).count());
+        }
+
+        let mut collapsed: XorDistance<u8> = XorDistance::new_dedup(vec![5, 1, 5, 9]);
+
+        assert_eq!(
+            vec![5, 1, 9],
+            collapsed.points().copied().collect::<Vec<u8>>()
+        );
+        assert!(!collapsed.add_point(9));
+        collapsed.extend_points(vec![1, 2, 2]);
+        assert_eq!(4, collapsed.len());
+        assert_eq!(None, collapsed.reverse_closest(&[5, 5]));
+        let position = collapsed.reverse_closest(&[5]).unwrap();
+        assert_eq!(vec![5], collapsed.closest(position, 1));
+
+        assert_eq!(
+            Some(XorDistanceError::DuplicatePoint { index: 2 }),
+            XorDistance::builder(vec![5u8, 1, 5, 9])
+                .duplicates(DuplicatePolicy::Reject)
+                .try_build()
+                .err()
+        );
+
+        let mut rejected = XorDistance::builder(vec![5u8, 1, 9])
+            .removal(RemovalKind::Tombstone)
+            .index(IndexKind::Sort)
+            .duplicates(DuplicatePolicy::Reject)
+            .build();
+
+        assert!(!rejected.add_point(5));
+        assert!(rejected.remove_point(5));
+        assert!(rejected.add_point(5));
+        assert_eq!(3, rejected.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate points are not allowed!")]
+    fn duplicate_policy_reject_panics() {
+        XorDistance::builder(vec![1u8, 1])
+            .duplicates(DuplicatePolicy::Reject)
+            .build();
+    }
+
+    #[test]
+    fn tie_break() {
+        let points: Vec<u8> = vec![7, 2, 6, 3, 2, 12, 1, 0];
+        // Distances from 0 in steps of four, so small points tie.
+        let key_fn = |_: u8, distance: u8| distance / 4;
+
+        let ranked = |tie_break| {
+            XorDistance::builder(points.clone())
+                .tie_break(tie_break)
+                .build()
+                .closest_by_key(0, 7, key_fn)
+        };
+
+        assert_eq!(
+            vec![2, 3, 2, 1, 0, 7, 6],
+            ranked(TieBreak::ByInsertionOrder)
+        );
+        assert_eq!(vec![0, 1, 2, 2, 3, 6, 7], ranked(TieBreak::ByValue));
+
+        let mut unstable = ranked(TieBreak::Unstable);
+        unstable[..5].sort_unstable();
+        unstable[5..].sort_unstable();
+        assert_eq!(vec![0, 1, 2, 2, 3, 6, 7], unstable);
+
+        // Ties of closest points are equal points, `[3, 2, 2, 1]` reverses under any policy.
+        for tie_break in [
+            TieBreak::ByValue,
+            TieBreak::ByInsertionOrder,
+            TieBreak::Unstable,
+        ]
+        .iter()
+        {
+            let config = XorDistanceConfig {
+                tie_break: *tie_break,
+                ..XorDistanceConfig::default()
+            };
+            let xor_distance = XorDistance::with_config(points.clone(), config);
+            assert_eq!(*tie_break, xor_distance.tie_break());
+
+            let closest = xor_distance.closest(3, 4);
+            let position = xor_distance.reverse_closest(&closest).unwrap();
+            assert_eq!(closest, xor_distance.closest(position, 4));
+        }
+    }
+
+    #[test]
+    fn reverse_closest_unordered() {
+        let points: Vec<u8> = vec![0, 1, 2, 4, 6, 8, 12, 18, 140, 12];
+        let xor_distance = XorDistance::new(points.clone());
+
+        // Sets of the closest points of all positions.
+        let mut reachable = BTreeSet::new();
+        for x in 0..=255u8 {
+            for count in 1..4 {
+                let mut closest = xor_distance.closest(x, count);
+                closest.sort_unstable();
+                closest.dedup();
+                reachable.insert(closest);
+            }
+        }
+
+        for a in points.iter() {
+            for b in points.iter().filter(|b| a != *b) {
+                let set: BTreeSet<u8> = [*a, *b].iter().copied().collect();
+                let expected = set.iter().copied().collect::<Vec<u8>>();
+                let len = points.iter().filter(|point| set.contains(point)).count();
+
+                match xor_distance.reverse_closest_unordered(&set) {
+                    Some(x) => {
+                        let mut closest = xor_distance.closest(x, len);
+                        closest.sort_unstable();
+                        closest.dedup();
+                        assert_eq!(expected, closest);
+                    }
+                    None => assert!(!reachable.contains(&expected)),
+                }
+            }
+        }
+
+        assert_eq!(None, xor_distance.reverse_closest_unordered(&[3]));
+        assert!(xor_distance.reverse_closest_unordered(&[]).is_some());
+    }
+
+    #[test]
+    fn within_distance() {
+        let points: Vec<u8> = vec![0, 1, 2, 4, 6, 8, 12, 18, 140, 12];
+
+        for index in [IndexKind::Sort, IndexKind::Trie].iter() {
+            let xor_distance = XorDistance::builder(points.clone()).index(*index).build();
+
+            for x in [0, 10, 140, 255].iter() {
+                for max_distance in [0, 1, 7, 100, 255].iter() {
+                    let mut expected: Vec<u8> = points
+                        .iter()
+                        .copied()
+                        .filter(|point| point ^ x <= *max_distance)
+                        .collect();
+                    expected.sort_by_key(|point| point ^ x);
+
+                    assert_eq!(expected, xor_distance.within_distance(*x, *max_distance));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn closest_with_distances() {
+        let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12, 18, 140]);
+
+        for x in [0, 10, 140, 255].iter() {
+            let closest = xor_distance.closest_with_distances(*x, 5);
+
+            assert_eq!(
+                xor_distance.closest(*x, 5),
+                closest.iter().map(|(point, _)| *point).collect::<Vec<u8>>()
+            );
+            assert!(closest
+                .iter()
+                .all(|(point, distance)| point ^ x == *distance));
+            assert!(closest.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        }
+
+        assert!(xor_distance.closest_with_distances(10, 0).is_empty());
+    }
+
+    #[test]
+    fn extend_points() {
+        for removal in [RemovalKind::Shift, RemovalKind::Tombstone].iter() {
+            let mut extended: XorDistance<u64> = XorDistance::builder(vec![0, 1, 2])
+                .index(IndexKind::Trie)
+                .removal(*removal)
+                .build();
+            let mut added = XorDistance::new(vec![0, 1, 2]);
+
+            let generation = extended.generation();
+            extended.extend_points(std::iter::empty());
+            assert_eq!(generation, extended.generation());
+
+            extended.extend_points(vec![8, 12, 12, 2]);
+            assert_eq!(generation + 1, extended.generation());
+
+            for point in [8, 12, 12, 2].iter() {
+                added.add_point(*point);
+            }
+
+            assert!(extended.points().eq(added.points()));
+            assert_eq!(added.closest(13, 7), extended.closest(13, 7));
+
+            // Removal keeps working on the extended points and index.
+            assert!(extended.remove_point(12));
+            assert!(added.remove_point(12));
+            assert_eq!(added.closest(13, 7), extended.closest(13, 7));
+            assert_eq!(Some(12), extended.nth_closest(13, 0));
+        }
+    }
+
+    #[test]
+    fn reverse_closest_iter() {
+        let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12, 18, 140]);
+
+        for x in [0u8, 10, 77, 200].iter() {
+            for count in [1, 3, 9].iter() {
+                let closest_points = xor_distance.closest(*x, *count);
+                let expected: Vec<u8> = (0..=u8::MAX)
+                    .filter(|y| xor_distance.closest(*y, *count) == closest_points)
+                    .collect();
+
+                let positions: Vec<u8> =
+                    xor_distance.reverse_closest_iter(&closest_points).collect();
+
+                assert_eq!(expected, positions);
+
+                let result = xor_distance
+                    .reverse_closest_detailed(&closest_points)
+                    .unwrap();
+                assert_eq!(positions[0], result.position);
+                assert_eq!(Some(positions.len() as u128), result.solution_count());
+
+                let ranges: Vec<(u8, u8)> = xor_distance
+                    .reverse_closest_ranges(&closest_points)
+                    .collect();
+                let covered: Vec<u8> = ranges.iter().flat_map(|(min, max)| *min..=*max).collect();
+                assert_eq!(expected, covered);
+                assert_eq!(
+                    xor_distance.reverse_closest_range(&closest_points),
+                    Some((expected[0], expected[expected.len() - 1]))
+                );
+                assert_eq!(
+                    xor_distance.reverse_closest(&closest_points),
+                    positions.first().copied()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn reverse_closest_detailed() {
+        let xor_distance: XorDistance<u128> = XorDistance::new(vec![0, 1]);
+
+        // Nothing but the lowest bit is decided, `2^127` positions do not overflow.
+        let result = xor_distance.reverse_closest_detailed(&[1]).unwrap();
+        assert_eq!(1, result.position);
+        assert_eq!(127, result.free_bits);
+        assert_eq!(Some(1 << 127), result.solution_count());
+
+        // No closest points decide no bit at all.
+        let result = xor_distance.reverse_closest_detailed(&[]).unwrap();
+        assert_eq!(128, result.free_bits);
+        assert_eq!(None, result.solution_count());
+
+        assert_eq!(None, xor_distance.reverse_closest_detailed(&[0, 1, 0]));
+    }
+
+    #[test]
+    fn reverse_closest_conflicts() {
+        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ]);
+
+        // Valid closest points are reversed the same way as by `reverse_closest`.
+        let closest_points = vec![8, 12, 2, 0, 1, 6, 4, 18, 19, 22];
+        assert_eq!(
+            xor_distance.reverse_closest(&closest_points),
+            xor_distance.reverse_closest_conflicts(&closest_points).ok()
+        );
+
+        // Invalid closest points report every conflict, not just the first one.
+        let closest_points = vec![8, 2, 12, 6, 1, 0, 4, 18, 22];
+        let conflicts: Vec<Conflict<u64>> = xor_distance
+            .reverse_closest_conflicts(&closest_points)
+            .unwrap_err()
+            .into_iter()
+            .map(|contradiction| match contradiction {
+                Contradiction::Inequalities { conflict, .. } => conflict,
+                listing => panic!("Unexpected {:?}!", listing),
+            })
+            .collect();
+
+        assert_eq!(
+            Conflict {
+                inequality: (2, 12),
+                bit_index: 3,
+                required: false,
+                decided_by: (8, 2),
+            },
+            conflicts[0]
+        );
+        assert!(conflicts.len() > 1);
+
+        for conflict in conflicts.iter() {
+            let (a, b) = conflict.inequality;
+            let (c, d) = conflict.decided_by;
+
+            // Both inequalities restrict the same bit to different values.
+            assert_eq!(conflict.bit_index, (63 - (a ^ b).leading_zeros()) as usize);
+            assert_eq!(conflict.bit_index, (63 - (c ^ d).leading_zeros()) as usize);
+            assert_ne!((a >> conflict.bit_index) & 1, (c >> conflict.bit_index) & 1);
+        }
+
+        // Wrongly listed points are reported before the conflicts.
+        assert_eq!(
+            Err(vec![Contradiction::Listing {
+                point: 7,
+                index: Some(0),
+            }]),
+            xor_distance.reverse_closest_conflicts(&[7])
+        );
+
+        let contradictions = xor_distance
+            .reverse_closest_conflicts(&[8, 2, 12, 6, 1, 0, 4, 18, 22, 7])
+            .unwrap_err();
+        assert_eq!(
+            Contradiction::Listing {
+                point: 7,
+                index: Some(9),
+            },
+            contradictions[0]
+        );
+        assert!(contradictions[1..]
+            .iter()
+            .all(|contradiction| matches!(contradiction, Contradiction::Inequalities { .. })));
+    }
+
+    #[test]
+    fn reverse_closest_diagnosed() {
+        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ]);
+
+        // Valid closest points are reversed the same way as by `reverse_closest`.
+        let closest_points = vec![8, 12, 2, 0, 1, 6, 4, 18, 19, 22];
+        assert_eq!(
+            xor_distance.reverse_closest(&closest_points),
+            xor_distance.reverse_closest_diagnosed(&closest_points).ok()
+        );
+        assert_eq!(Ok(0), xor_distance.reverse_closest_diagnosed(&[]));
+
+        // The first conflict is the one of `reverse_closest_conflicts`, located by indexes.
+        let closest_points = vec![8, 2, 12, 6, 1, 0, 4, 18, 22];
+        let contradiction = xor_distance
+            .reverse_closest_diagnosed(&closest_points)
+            .unwrap_err();
+        let expected = Contradiction::Inequalities {
+            conflict: Conflict {
+                inequality: (2, 12),
+                bit_index: 3,
+                required: false,
+                decided_by: (8, 2),
+            },
+            indexes: (Some(1), Some(2)),
+            decided_by_indexes: (Some(0), Some(1)),
+        };
+
+        assert_eq!(expected, contradiction);
+        assert_eq!(
+            contradiction,
+            xor_distance
+                .reverse_closest_conflicts(&closest_points)
+                .unwrap_err()[0]
+        );
+        assert_eq!(
+            "2 ^ x < 12 ^ x (closest 1, 2) requires bit 3 = 0, \
+             but 8 ^ x < 2 ^ x (closest 0, 1) requires bit 3 = 1",
+            contradiction.to_string()
+        );
+
+        // Further points have no index.
+        let contradiction = xor_distance.reverse_closest_diagnosed(&[8, 12, 2, 0, 4]);
+        match contradiction.unwrap_err() {
+            Contradiction::Inequalities {
+                conflict,
+                indexes,
+                decided_by_indexes,
+            } => {
+                assert_eq!((4, 1), conflict.inequality);
+                assert_eq!((Some(4), None), indexes);
+                assert_eq!((8, 12), conflict.decided_by);
+                assert_eq!((Some(0), Some(1)), decided_by_indexes);
+            }
+            listing => panic!("Unexpected {:?}!", listing),
+        }
+
+        // Contradictions of random closest points agree with the other reverse methods.
+        let mut rng = StdRng::seed_from_u64(1559);
+        let points: Vec<u16> = (0..50).map(|_| rng.gen()).collect();
+        let xor_distance = XorDistance::new(points.clone());
+
+        for _ in 0..200 {
+            let count = rng.gen_range(0, 6);
+            let closest: Vec<u16> = (0..count)
+                .map(|_| points[rng.gen_range(0, points.len())])
+                .collect();
+
+            match xor_distance.reverse_closest_diagnosed(&closest) {
+                Ok(position) => assert_eq!(Some(position), xor_distance.reverse_closest(&closest)),
+                Err(Contradiction::Listing { point, index }) => {
+                    assert_eq!(None, xor_distance.reverse_closest(&closest));
+                    assert_eq!(index.map(|index| closest[index]), index.map(|_| point));
+                }
+                Err(Contradiction::Inequalities {
+                    conflict,
+                    indexes,
+                    decided_by_indexes,
+                }) => {
+                    assert_eq!(None, xor_distance.reverse_closest(&closest));
+
+                    let point = |index: Option<usize>, further: u16| match index {
+                        Some(index) => closest[index],
+                        None => further,
+                    };
+                    let (a, b) = conflict.inequality;
+                    let (c, d) = conflict.decided_by;
+
+                    assert_eq!((a, b), (point(indexes.0, a), point(indexes.1, b)));
+                    assert_eq!(
+                        (c, d),
+                        (
+                            point(decided_by_indexes.0, c),
+                            point(decided_by_indexes.1, d)
+                        )
+                    );
+                    assert!(indexes.0.is_some() && decided_by_indexes.0.is_some());
+                }
+            }
+        }
+
+        // A present point is not listed, or no present point is listed.
+        let xor_distance: XorDistance<u64> = XorDistance::builder(vec![1, 3, 3, 5])
+            .duplicates(DuplicatePolicy::Keep)
+            .build();
 
-        Self { points, bit_size }
+        assert_eq!(
+            Err(Contradiction::Listing {
+                point: 3,
+                index: Some(0),
+            }),
+            xor_distance.reverse_closest_diagnosed(&[3, 1])
+        );
+        assert_eq!(
+            "point 3 at closest 0 is listed wrongly",
+            xor_distance
+                .reverse_closest_diagnosed(&[3, 1])
+                .unwrap_err()
+                .to_string()
+        );
     }
 
-    /// Return up to requested count of closest points to the provided `x`, ordered from the closest
-    /// to the n-th closest, where `n` is the count.
-    ///
-    /// The returned closest points count my be lower than the specified count and equal to all
-    /// points count only in the case that: `count > points.len()`.
-    ///
-    /// # Examples
-    /// ```
-    /// extern crate xor_distance_exercise;
-    ///
-    /// use xor_distance_exercise::xor_distance::XorDistance;
-    ///
-    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![
-    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
-    /// ]);
-    ///
-    /// let x = 200;
-    /// let count = 10;
-    ///
-    /// let closest_points = xor_distance.closest(x, count);
-    /// ```
-    pub fn closest(&self, x: T, count: usize) -> Vec<T> {
-        let mut closest_sorted = self.points.clone();
-        closest_sorted.sort_by_key(|point| *point ^ x);
-        closest_sorted.truncate(count);
-        closest_sorted
-    }
+    #[test]
+    fn reverse_closest_wildcard() {
+        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ]);
 
-    /// Return a `Some(x)` such that `self.closest(x)` equals closest_points and return None in case
-    /// such a `x` does not exists.
-    ///
-    /// # Examples
-    /// ```
-    /// extern crate xor_distance_exercise;
-    ///
-    /// use xor_distance_exercise::xor_distance::XorDistance;
-    ///
-    /// let xor_distance: XorDistance<u64> = XorDistance::new(vec![
-    ///     0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
-    /// ]);
-    ///
-    /// let x = 200;
-    /// let count = 10;
-    ///
-    /// // Get closest points and reversed guess of `x`
-    /// let closest_points = xor_distance.closest(x, count);
-    /// let x_guess = xor_distance.reverse_closest(&closest_points).unwrap();
-    ///
-    /// // Check that both `x` and `guess_x` produce the same result.
-    /// assert_eq!(closest_points, xor_distance.closest(x_guess, count));
-    /// ```
-    pub fn reverse_closest(&self, closest_points: &[T]) -> Option<T> {
-        let inequalities = self.form_inequalities(closest_points);
+        let closest_points = vec![8, 12, 2, 0, 1, 6, 4, 18, 19, 22];
+
+        // All entries known is the same as the regular reverse.
+        let all_known: Vec<Option<u64>> = closest_points.iter().map(|p| Some(*p)).collect();
+        assert_eq!(
+            xor_distance.reverse_closest(&closest_points),
+            xor_distance.reverse_closest_wildcard(&all_known)
+        );
+
+        // Only unknown entries restrict nothing.
+        assert_eq!(
+            Some(0),
+            xor_distance.reverse_closest_wildcard(&[None, None])
+        );
+        assert_eq!(Some(0), xor_distance.reverse_closest_wildcard(&[]));
+
+        // Known entries keep their order.
+        let partial: Vec<Option<u64>> = xor_distance
+            .closest(300, 6)
+            .into_iter()
+            .enumerate()
+            .map(|(index, point)| Some(point).filter(|_| index % 3 != 1))
+            .collect();
+        let guess_pos = xor_distance.reverse_closest_wildcard(&partial).unwrap();
+        let closest = xor_distance.closest(guess_pos, 19);
+        let rank = |point| closest.iter().position(|p| *p == point).unwrap();
 
-        if let Some(bit_rep) = self.form_bits_restrictions_from_inequalities(&inequalities) {
-            // Asking for the same number type as we are bit-representing is fine.
-            let position = bit_rep.form_zero_padded_number::<T>().unwrap();
+        assert_eq!(Some(444), partial[0]);
+        assert!(rank(444) < rank(409));
+        assert!(rank(409) < rank(406));
 
-            return Some(position);
+        // Known entries keep their indexes.
+        for index in [0, 2, 3, 5].iter() {
+            assert_eq!(partial[*index], Some(closest[*index]));
         }
 
-        None
+        // Contradicting known entries have no solution.
+        let contradicting = vec![Some(8), None, Some(2), Some(12)];
+        assert!(xor_distance
+            .reverse_closest_wildcard(&contradicting)
+            .is_none());
+
+        // Known entries must be present, and at the index they are listed at.
+        let xor_distance: XorDistance<u8> = XorDistance::new(vec![0, 8, 12]);
+        assert_eq!(None, xor_distance.reverse_closest_wildcard(&[Some(7)]));
+        assert_eq!(
+            None,
+            xor_distance.reverse_closest_wildcard(&[Some(7), None])
+        );
+        assert_eq!(
+            Some(0),
+            xor_distance.reverse_closest_wildcard(&[None, Some(8)])
+        );
+        assert_eq!(
+            Some(4),
+            xor_distance.reverse_closest_wildcard(&[None, Some(12)])
+        );
+        assert_eq!(
+            None,
+            xor_distance.reverse_closest_wildcard(&[None, None, None, None])
+        );
+
+        // The smallest position, as found by checking all positions.
+        let mut rng = StdRng::seed_from_u64(1460);
+        for _ in 0..300 {
+            let len = rng.gen_range(1, 12);
+            let points: Vec<u8> = (0..len).map(|_| rng.gen_range(0, 64)).collect();
+            let duplicates = if rng.gen() {
+                DuplicatePolicy::Keep
+            } else {
+                DuplicatePolicy::Collapse
+            };
+            let xor_distance = XorDistance::builder(points.clone())
+                .duplicates(duplicates)
+                .build();
+
+            let len = rng.gen_range(0, xor_distance.len() + 2);
+            let closest_points: Vec<Option<u8>> = (0..len)
+                .map(|_| match rng.gen_range(0, 3) {
+                    0 => None,
+                    _ => Some(points[rng.gen_range(0, points.len())]),
+                })
+                .collect();
+
+            let consistent = |x: u8| {
+                let closest = xor_distance.closest(x, len);
+
+                closest.len() == len
+                    && closest_points
+                        .iter()
+                        .zip(closest.iter())
+                        .all(|(known, point)| known.is_none_or(|known| known == *point))
+            };
+            let expected = (0..=u8::MAX).find(|x| consistent(*x));
+
+            assert_eq!(
+                expected,
+                xor_distance.reverse_closest_wildcard(&closest_points),
+                "Wrong reverse of {:?} amongst {:?}!",
+                closest_points,
+                points
+            );
+        }
     }
 
-    pub fn form_inequalities(&self, closest_points: &[T]) -> Vec<(T, T)> {
-        let mut inequalities = self.compose_closest_points_inequalities(closest_points);
-        let mut further_inequalities = self.compose_further_points_inequalities(closest_points);
+    #[test]
+    fn ranking_diff() {
+        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ]);
+
+        // Same position has no differences.
+        assert_eq!(
+            RankingDiff::<u64>::default(),
+            xor_distance.ranking_diff(10, 10, 5)
+        );
 
-        inequalities.append(&mut further_inequalities);
+        // Closest points are `[8, 12, 2, 0]` and `[12, 8, 6, 4]`.
+        let diff = xor_distance.ranking_diff(10, 14, 4);
 
-        inequalities
+        assert_eq!(
+            vec![
+                RankChange {
+                    point: 6,
+                    from_rank: None,
+                    to_rank: Some(2),
+                    bit_index: Some(2),
+                },
+                RankChange {
+                    point: 4,
+                    from_rank: None,
+                    to_rank: Some(3),
+                    bit_index: Some(2),
+                },
+            ],
+            diff.entered
+        );
+        assert_eq!(
+            vec![
+                RankChange {
+                    point: 2,
+                    from_rank: Some(2),
+                    to_rank: None,
+                    bit_index: Some(2),
+                },
+                RankChange {
+                    point: 0,
+                    from_rank: Some(3),
+                    to_rank: None,
+                    bit_index: Some(2),
+                },
+            ],
+            diff.left
+        );
+        assert_eq!(
+            vec![
+                RankChange {
+                    point: 8,
+                    from_rank: Some(0),
+                    to_rank: Some(1),
+                    bit_index: Some(2),
+                },
+                RankChange {
+                    point: 12,
+                    from_rank: Some(1),
+                    to_rank: Some(0),
+                    bit_index: Some(2),
+                },
+            ],
+            diff.moved
+        );
     }
 
-    /// Compose inequalities pairs amongst closest points and their order.
-    ///
-    /// We have a set of all existing unique points, represented as:
-    /// `P = [p1, p2, p3, p4, p5, ..., p(m-1), p(m)]`
-    ///
-    /// We have a position number represented by `x` and we also have a P subset of selected points
-    /// that are the closest points to `x` by XOR distance metric.
-    ///
-    /// The closest points are represented as:
-    /// `C = [c1, c2, c3, c4, c5, ..., c(n-1), c(n)]`
-    ///
-    /// and the following inequality applies:
-    /// `c1 ^ x < c2 ^ x < c3 ^ x < c4 ^ x < c5 ^ x < ... < c(n-1) ^ x < c(n) ^ x`
-    ///
-    /// Separating it into simple `(n-1)` inequalities:
-    /// `c1 ^ x < c2 ^ x`
-    /// `c2 ^ x < c3 ^ x`
-    /// `c3 ^ x < c4 ^ x`
-    /// `c4 ^ x < c5 ^ x`
-    /// `...`
-    /// `c(n-1) ^ x < c(n) ^ x`
-    ///
-    /// These `(n-1)` inequalities are what this method returns.
-    fn compose_closest_points_inequalities(&self, closest_points: &[T]) -> Vec<(T, T)> {
-        // Prepare the inequalities container.
-        let size = closest_points.len();
-        let mut inequalities = Vec::with_capacity(size);
+    #[test]
+    fn closest_bounded() {
+        let points: Vec<u64> = vec![
+            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+        ];
 
-        // Collect pairs of inequalities.
-        for i in 0..size - 1 {
-            // Point `a` must be closer to the point `x` then point `b`. The inequality is:
-            // `a ^ x < b ^ x` , where point `x` is the position being searched for.
-            let a = closest_points[i];
-            let b = closest_points[i + 1];
+        for index in [IndexKind::Sort, IndexKind::Trie].iter() {
+            let xor_distance = XorDistance::builder(points.clone()).index(*index).build();
 
-            inequalities.push((a, b));
+            assert_eq!(vec![8, 12, 2], xor_distance.closest_bounded(10, 10, 8));
+            assert_eq!(vec![8, 12], xor_distance.closest_bounded(10, 2, 8));
+            assert_eq!(Vec::<u64>::new(), xor_distance.closest_bounded(10, 10, 1));
+            assert_eq!(
+                xor_distance.closest_bounded(300, 4, 200),
+                xor_distance.closest_within(300, 4, 200)
+            );
+            assert_eq!(
+                xor_distance.closest(300, 19),
+                xor_distance.closest_bounded(300, 19, u64::MAX)
+            );
         }
+    }
+
+    #[test]
+    fn generation() {
+        let mut xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2]);
+        let generation = xor_distance.generation();
+
+        // Failed removal is not a mutation.
+        assert!(!xor_distance.remove_point(8));
+        assert_eq!(generation, xor_distance.generation());
 
-        inequalities
+        xor_distance.add_point(8);
+        assert_eq!(generation + 1, xor_distance.generation());
+
+        assert!(xor_distance.remove_point(8));
+        assert_eq!(generation + 2, xor_distance.generation());
     }
 
-    /// Compose inequalities pairs between last closest point and all further points.
-    ///
-    /// We have a set of all existing unique points, represented as:
-    /// `P = [p1, p2, p3, p4, p5, ..., p(n-1), p(n)]`
-    ///
-    /// We have a position number represented by `x` and we also have a P subset of selected points
-    /// that are the closest points to `x` by XOR distance metric.
-    ///
-    /// The closest points are represented as:
-    /// `[c1, c2, c3, c4, c5, ..., c(n-1), c(n)]`
-    ///
-    /// The further points are all unselected points from P and are represented as (U = P - C):
-    /// `U = [u1, u2, u3, u4, u5, ..., u(n-1), u(n)]`
-    ///
-    /// and the following inequalities applies:
-    /// `c(n) ^ x < u1 ^ x`
-    /// `c(n) ^ x < u2 ^ x`
-    /// `c(n) ^ x < u3 ^ x`
-    /// `c(n) ^ x < u4 ^ x`
-    /// `c(n) ^ x < u5 ^ x`
-    /// ...`
-    /// `c(n) ^ x < u(m) ^ x`
-    ///
-    /// These inequalities are what this method returns.
-    fn compose_further_points_inequalities(&self, closest_points: &[T]) -> Vec<(T, T)> {
-        // Get the n-th closest point to `x` where the n is number of closest points.
-        if let Some(a) = closest_points.last() {
-            let further_points = self.get_further_points(closest_points);
+    #[test]
+    fn scan() {
+        let mut xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6]);
 
-            // Prepare the inequalities container.
-            let size = further_points.len();
-            let mut inequalities = Vec::with_capacity(size);
+        let mut scan = xor_distance.scan();
+        assert_eq!(Ok(vec![0, 1]), scan.next_batch(&xor_distance, 2));
+        assert_eq!(Ok(vec![2, 4]), scan.next_batch(&xor_distance, 2));
+        assert_eq!(Ok(vec![6]), scan.next_batch(&xor_distance, 2));
+        assert_eq!(Ok(vec![]), scan.next_batch(&xor_distance, 2));
 
-            // Collect pairs of inequalities.
-            for b in further_points.iter() {
-                // Point `a` must be closer to the point `x` then point `b`. The inequality is:
-                // `a ^ x < b ^ x` , where point `x` is the position being searched for.
-                inequalities.push((*a, *b));
-            }
+        // Mutation makes the started scan fail, while a new scan works.
+        let mut scan = xor_distance.scan();
+        assert_eq!(Ok(vec![0]), scan.next_batch(&xor_distance, 1));
 
-            return inequalities;
-        }
+        assert!(xor_distance.remove_point(0));
 
-        // There are no inequalities.
-        Vec::new()
+        assert_eq!(
+            Err(XorDistanceError::ScanInvalidated {
+                scanned: 0,
+                current: 1
+            }),
+            scan.next_batch(&xor_distance, 1)
+        );
+        assert_eq!(
+            Ok(vec![1]),
+            xor_distance.scan().next_batch(&xor_distance, 1)
+        );
     }
 
-    fn get_further_points(&self, closest_points: &[T]) -> Vec<T> {
-        // Get further points (the ones that were not selected as the closest).
-        let mut further_points = self.points.clone();
-        // Exclude all closest points.
-        further_points.retain(|x| !closest_points.contains(&x));
+    #[test]
+    fn debug_summary() {
+        let xor_distance: XorDistance<u64> = XorDistance::builder(vec![8, 1, 2])
+            .index(IndexKind::Trie)
+            .build();
+
+        assert_eq!(
+            "XorDistance { points: 3, bit_size: 64, index: Trie, generation: 0, sample: [8, 1, 2] }",
+            format!("{:?}", xor_distance)
+        );
 
-        further_points
+        let empty: XorDistance<u8> = XorDistance::new(Vec::new());
+        assert!(format!("{:?}", empty).ends_with("sample: [] }"));
     }
 
-    /// Form bits restrictions as a bit representation based on provided inequalities.
-    ///
-    /// Returns `Some(b)` if bits restrictions can be constructed within constrains (no two
-    /// inequalities contradict themselves), `None` otherwise.
-    fn form_bits_restrictions_from_inequalities(&self, inequalities: &[(T, T)]) -> Option<Bits> {
-        let mut bit_rep = Bits::new::<T>();
+    #[test]
+    fn contains() {
+        for index in [IndexKind::Sort, IndexKind::Trie].iter() {
+            for removal in [RemovalKind::Shift, RemovalKind::Tombstone].iter() {
+                let mut xor_distance: XorDistance<u16> = XorDistance::builder(vec![3, 3, 9])
+                    .index(*index)
+                    .removal(*removal)
+                    .build();
+
+                assert!(xor_distance.contains(3));
+                assert!(!xor_distance.contains(4));
 
-        // Combine all inequalities to form bits restrictions.
-        for pair in inequalities.iter() {
-            if self
-                .add_bit_restriction_from_inequality(pair, &mut bit_rep)
-                .is_err()
-            {
-                // Required bit can not be set within constrains and thus valid Bits
-                // can not be formed.
-                return None;
+                xor_distance.remove_point(3);
+                assert!(xor_distance.contains(3));
+                xor_distance.remove_point(3);
+                assert!(!xor_distance.contains(3));
+
+                assert_eq!(1, xor_distance.len());
+                assert_eq!(vec![9], xor_distance.points().copied().collect::<Vec<_>>());
             }
         }
-
-        Some(bit_rep)
     }
 
-    /// Incorporate bit restriction from provided inequality `a ^ x < b ^ x`, where `x` is the
-    /// position being searched for.
-    ///
-    /// Returns `Ok(())` in case the inequality doesn't contradict any inequality processed so far,
-    /// `Err(&str)` otherwise.
-    fn add_bit_restriction_from_inequality(
-        &self,
-        &(a, b): &(T, T),
-        bit_rep: &mut Bits,
-    ) -> Result<(), &'static str> {
-        let xor_distance: T = a ^ b;
+    #[test]
+    fn collect_extend_iterate() {
+        let mut xor_distance: XorDistance<u64> = vec![0, 1, 2].into_iter().collect();
+        assert_eq!(XorDistance::new(vec![0, 1, 2]), xor_distance);
+
+        let generation = xor_distance.generation();
+        xor_distance.extend(vec![4, 8]);
+        assert_eq!(generation + 1, xor_distance.generation());
 
-        // Index of the first left hand-side bit in which `a` and `b` differ. The index starts by 0.
-        let bit_index = (self.bit_size as u32 - xor_distance.leading_zeros() - 1) as usize;
+        let points: Vec<u64> = (&xor_distance).into_iter().copied().collect();
+        assert_eq!(vec![0, 1, 2, 4, 8], points);
 
-        // As `a` is closer to the position we are searching for then `b`, we need to restrict
-        // to bit value of `a`.
-        let a_bit = a.is_bit_set(bit_index);
+        let empty: XorDistance<u8> = core::iter::empty().collect();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn clone_eq_default() {
+        for removal in [RemovalKind::Shift, RemovalKind::Tombstone].iter() {
+            let mut xor_distance: XorDistance<u64> = XorDistance::builder(vec![0, 1, 2, 4, 8])
+                .removal(*removal)
+                .build();
+            xor_distance.remove_point(2);
+
+            let mut clone = xor_distance.clone();
+            assert_eq!(xor_distance, clone);
+            assert_eq!(xor_distance.removal_kind(), clone.removal_kind());
+            assert_eq!(xor_distance.closest(3, 4), clone.closest(3, 4));
 
-        // Required bit can not be set within constrains.
-        if let Err(e) = bit_rep.set_bit_within_constrains(bit_index, a_bit) {
-            return Err(e);
+            // The clone is independent.
+            clone.add_point(3);
+            assert_ne!(xor_distance, clone);
+            assert_eq!(vec![1, 0], xor_distance.closest(3, 2));
+            assert_eq!(vec![3, 1], clone.closest(3, 2));
         }
 
-        Ok(())
+        let tie_break = XorDistance::builder(vec![0u8, 1])
+            .tie_break(TieBreak::ByValue)
+            .build();
+        assert_ne!(XorDistance::new(vec![0u8, 1]), tie_break);
+
+        let mut empty: XorDistance<u8> = XorDistance::default();
+        assert!(empty.is_empty());
+        assert_eq!(IndexKind::Trie, empty.index_kind());
+
+        empty.add_point(5);
+        assert_eq!(Some(5), empty.nth_closest(0, 0));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::XorDistance;
+    #[test]
+    fn tombstone_removal() {
+        for index in [IndexKind::Sort, IndexKind::Trie].iter() {
+            let mut xor_distance: XorDistance<u64> =
+                XorDistance::builder(vec![0, 1, 2, 4, 6, 8, 2])
+                    .index(*index)
+                    .removal(RemovalKind::Tombstone)
+                    .build();
+
+            assert_eq!(RemovalKind::Tombstone, xor_distance.removal_kind());
+
+            assert!(xor_distance.remove_point(2));
+            assert!(xor_distance.remove_point(6));
+            assert!(!xor_distance.remove_point(5));
+
+            // Tombstoned points are not compacted yet, but not visible any more.
+            assert_eq!(7, xor_distance.points.len());
+            assert_eq!(5, xor_distance.len());
+            assert_eq!(
+                vec![0, 1, 4, 8, 2],
+                xor_distance.points().copied().collect::<Vec<_>>()
+            );
+            assert_eq!(vec![4, 1, 0, 2, 8], xor_distance.closest(5, 10));
+            assert_eq!(Some(1), xor_distance.nth_closest(5, 1));
+            assert_eq!(None, xor_distance.nth_closest(5, 5));
+            assert_eq!(vec![4, 1, 0], xor_distance.closest_bounded(5, 10, 5));
+
+            // Re-added point is visible again.
+            xor_distance.add_point(6);
+            assert_eq!(vec![4, 6], xor_distance.closest(5, 2));
+
+            // Removing the half of the points compacts them.
+            assert!(xor_distance.remove_point(2));
+            assert!(xor_distance.remove_point(0));
+            assert_eq!(vec![1, 4, 8, 6], xor_distance.points);
+            assert_eq!(
+                vec![1, 4, 8, 6],
+                xor_distance.points().copied().collect::<Vec<_>>()
+            );
+
+            // Lookup is rebuilt by the compaction.
+            assert!(xor_distance.remove_point(8));
+            assert_eq!(vec![4, 6, 1], xor_distance.closest(5, 10));
+        }
+    }
 
     #[test]
-    fn compose_closest_points_inequalities() {
-        let points: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
-        let xor_distance = XorDistance::new(points.clone());
+    fn tombstone_scan() {
+        let mut xor_distance: XorDistance<u64> = XorDistance::builder(vec![0, 1, 2, 4, 6])
+            .removal(RemovalKind::Tombstone)
+            .build();
 
-        let closest_points: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6];
+        assert!(xor_distance.remove_point(1));
 
-        // Test first example, count < number of points.
-        let result = xor_distance.compose_closest_points_inequalities(&closest_points);
-        let expected: Vec<(u8, u8)> = vec![(0, 1), (1, 2), (2, 3), (3, 4), (4, 5), (5, 6)];
+        let generation = xor_distance.generation();
+        let mut scan = xor_distance.scan();
+        assert_eq!(Ok(vec![0, 2]), scan.next_batch(&xor_distance, 2));
+        assert_eq!(Ok(vec![4, 6]), scan.next_batch(&xor_distance, 2));
 
-        assert_eq!(expected, result);
+        // Compaction is a mutation too.
+        xor_distance.compact();
+        assert!(xor_distance.generation() > generation);
+        assert!(scan.next_batch(&xor_distance, 2).is_err());
     }
 
     #[test]
-    fn compose_further_points_inequalities() {
-        let points: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
-        let xor_distance = XorDistance::new(points.clone());
+    fn closest_by_key() {
+        let xor_distance: XorDistance<u64> = XorDistance::new(vec![0, 1, 2, 4, 6, 8, 12]);
 
-        let closest_points: Vec<u8> = vec![0, 1, 2, 3, 4, 5, 6];
+        // Ordering by the distance only equals `closest`.
+        assert_eq!(
+            xor_distance.closest(10, 5),
+            xor_distance.closest_by_key(10, 5, |_, distance| distance)
+        );
 
-        // Test first example, count < number of points.
-        let result = xor_distance.compose_further_points_inequalities(&closest_points);
-        let expected: Vec<(u8, u8)> = vec![(6, 7), (6, 8), (6, 9), (6, 10), (6, 11), (6, 12)];
+        // Distance penalized by an external score.
+        let score = |point: u64| if point == 8 { 100 } else { 0 };
+        assert_eq!(
+            vec![12, 2, 0],
+            xor_distance.closest_by_key(10, 3, |point, distance| distance + score(point))
+        );
 
-        assert_eq!(expected, result);
+        // Equal keys keep the insertion order.
+        assert_eq!(vec![0, 1, 2], xor_distance.closest_by_key(10, 3, |_, _| 0));
     }
 
     #[test]
-    fn closest_u64() {
+    fn reverse_closest_certified() {
         let points: Vec<u64> = vec![
             0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
         ];
         let xor_distance = XorDistance::new(points.clone());
+        let closest = xor_distance.closest(200, 6);
 
-        // Test first example, count < number of points.
-        let result = xor_distance.closest(300, 4);
-        let expected = vec![444, 445, 408, 409];
+        let certificate = xor_distance.reverse_closest_certified(&closest).unwrap();
 
-        assert_eq!(expected, result);
+        assert_eq!(
+            Some(certificate.position),
+            xor_distance.reverse_closest(&closest)
+        );
+        assert_eq!(points.len() - 1, certificate.steps.len());
+        assert!(verify_certificate(&points, &closest, &certificate));
 
-        // Test second example, count < number of points.
-        let result = xor_distance.closest(10, 10);
-        let expected = vec![8, 12, 2, 0, 1, 6, 4, 18, 19, 22];
+        // Missing inequality.
+        let mut incomplete = certificate.clone();
+        incomplete.steps.pop();
+        assert!(!verify_certificate(&points, &closest, &incomplete));
 
-        assert_eq!(expected, result);
+        // Wrong fixed bit.
+        let mut forged = certificate.clone();
+        forged.steps[0].value = !forged.steps[0].value;
+        assert!(!verify_certificate(&points, &closest, &forged));
 
-        // Test third example, count < number of points.
-        let result = xor_distance.closest(888, 12);
-        let expected = vec![444, 445, 408, 409, 410, 406, 407, 18, 19, 20, 21, 22];
+        // Position without the fixed bits.
+        let mut moved = certificate.clone();
+        moved.position ^= 1 << certificate.steps[0].bit_index;
+        assert!(!verify_certificate(&points, &closest, &moved));
 
-        assert_eq!(expected, result);
+        // Unknown closest point.
+        assert!(!verify_certificate(&points[1..], &closest, &certificate));
 
-        // Test situation with count = 0.
-        let result = xor_distance.closest(10, 0);
-        let expected: Vec<u64> = Vec::new();
+        assert_eq!(None, xor_distance.reverse_closest_certified(&[1, 2, 0]));
 
-        assert_eq!(expected, result);
+        // Certificates of duplicated points verify as well.
+        let mut rng = StdRng::seed_from_u64(1481);
+        let points: Vec<u8> = (0..60).map(|_| rng.gen_range(0, 40)).collect();
+        let xor_distance = XorDistance::new(points.clone());
 
-        // Test situation with count = number of points.
-        let result = xor_distance.closest(10, points.len());
-        let expected = vec![
-            8, 12, 2, 0, 1, 6, 4, 18, 19, 22, 20, 21, 410, 408, 409, 406, 407, 444, 445,
-        ];
+        for _ in 0..100 {
+            let closest = xor_distance.closest(rng.gen(), rng.gen_range(0, 62));
+            let certificate = xor_distance.reverse_closest_certified(&closest).unwrap();
 
-        assert_eq!(expected, result);
-        assert_eq!(points.len(), expected.len());
+            assert!(verify_certificate(&points, &closest, &certificate));
+        }
 
-        // Test situation with count > number of points.
-        let result = xor_distance.closest(10, points.len() + 1);
-        let expected = vec![
-            8, 12, 2, 0, 1, 6, 4, 18, 19, 22, 20, 21, 410, 408, 409, 406, 407, 444, 445,
-        ];
+        // Duplicates not listed all are rejected, except for the last closest point.
+        let points: Vec<u8> = vec![3, 3, 1];
+        let xor_distance = XorDistance::new(points.clone());
+        let certificate = xor_distance.reverse_closest_certified(&[3, 3, 1]).unwrap();
 
-        assert_eq!(expected, result);
-        assert_eq!(points.len(), expected.len());
+        assert!(verify_certificate(&points, &[3, 3, 1], &certificate));
+        assert!(!verify_certificate(&points, &[3, 1], &certificate));
+        assert!(!verify_certificate(&points, &[3, 3, 3], &certificate));
+        assert!(verify_certificate(
+            &points,
+            &[3],
+            &xor_distance.reverse_closest_certified(&[3]).unwrap()
+        ));
     }
 
     #[test]
-    fn closest_u8() {
-        let points: Vec<u8> = vec![
-            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 20, 21, 22, 23, 24, 100, 220, 230, 240, 250,
-        ];
-        let xor_distance = XorDistance::new(points.clone());
-
-        // Test first example, count < number of points.
-        let result = xor_distance.closest(18, 8);
-        let expected = vec![22, 23, 20, 21, 24, 2, 3, 0];
+    fn closest_algorithms() {
+        let points: Vec<u16> = (0..5000u16).map(|i| i.wrapping_mul(40503)).collect();
+        let expected = XorDistance::builder(points.clone())
+            .algorithm(Algorithm::Sort)
+            .build()
+            .closest(777, 3000);
 
-        assert_eq!(expected, result);
+        for algorithm in [Algorithm::Heap, Algorithm::Radix, Algorithm::Index].iter() {
+            let xor_distance = XorDistance::builder(points.clone())
+                .algorithm(*algorithm)
+                .build();
 
-        // Test second example, count < number of points.
-        let result = xor_distance.closest(200, 14);
-        let expected = vec![220, 230, 250, 240, 100, 8, 9, 10, 12, 0, 1, 2, 3, 4];
+            assert_eq!(*algorithm, xor_distance.algorithm());
+            assert_eq!(expected, xor_distance.closest(777, 3000));
+            assert_eq!(expected[..5].to_vec(), xor_distance.closest(777, 5));
+        }
 
-        assert_eq!(expected, result);
-    }
+        // Huge counts return all points, whatever the algorithm.
+        for algorithm in [Algorithm::Sort, Algorithm::Heap, Algorithm::Radix].iter() {
+            let xor_distance = XorDistance::builder(vec![3u8, 3, 1, 12])
+                .algorithm(*algorithm)
+                .build();
 
-    #[test]
-    fn reverse_closest_u64() {
-        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
-            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
-        ]);
+            assert_eq!(vec![3, 3, 1, 12], xor_distance.closest(2, usize::MAX));
+            assert_eq!(vec![12], xor_distance.closest(12, 1));
+        }
 
-        let closest_points = vec![8, 12, 2, 0, 1, 6, 4, 18, 19, 22];
-        let count = closest_points.len();
-        let guess_pos = xor_distance.reverse_closest(&closest_points).unwrap();
+        let xor_distance = XorDistance::new(vec![3u8, 3, 1]);
+        assert_eq!(vec![3, 3], xor_distance.closest(2, 2));
 
-        assert_eq!(closest_points, xor_distance.closest(guess_pos, count));
+        let xor_distance = XorDistance::builder(vec![3u8, 3, 1])
+            .algorithm(Algorithm::Radix)
+            .build();
+        assert_eq!(vec![3, 3, 1], xor_distance.closest(2, 5));
+        assert!(xor_distance.closest(2, 0).is_empty());
     }
 
     #[test]
-    fn reverse_closest_u8() {
-        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
-            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 12, 20, 21, 22, 23, 24, 100, 220, 230, 240, 250,
-        ]);
+    fn adaptive_selection() {
+        let xor_distance: XorDistance<u32> = XorDistance::builder((0..100).collect())
+            .index(IndexKind::Sort)
+            .build();
 
-        let closest_points = vec![220, 230, 250, 240, 100, 8, 9, 10, 12, 0, 1, 2, 3, 4];
-        let count = closest_points.len();
-        let guess_pos = xor_distance.reverse_closest(&closest_points).unwrap();
-
-        assert_eq!(closest_points, xor_distance.closest(guess_pos, count));
-    }
+        assert_eq!(Algorithm::Adaptive, xor_distance.algorithm());
+        assert_eq!(Algorithm::Heap, xor_distance.select_algorithm(1));
 
-    #[test]
-    fn reverse_closest_invalid_input() {
-        let xor_distance: XorDistance<u64> = XorDistance::new(vec![
-            0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
-        ]);
+        // Inspecting the selection records no queries.
+        for _ in 0..3 {
+            assert_eq!(Algorithm::Sort, xor_distance.select_algorithm(50));
+        }
+        assert_eq!(Algorithm::Heap, xor_distance.select_algorithm(10));
 
-        let closest_points = vec![8, 2, 12, 6, 1, 0, 4, 18, 22];
+        // Counts near the threshold follow the recent, mostly large, queries.
+        xor_distance.closest(0, 50);
+        xor_distance.closest(0, 50);
+        assert_eq!(Algorithm::Sort, xor_distance.select_algorithm(10));
+        assert_eq!(Algorithm::Sort, xor_distance.select_algorithm(10));
 
-        // The output is `None` as there's no `x` that would satisfy the provided closest points
-        // input.
-        assert!(xor_distance.reverse_closest(&closest_points).is_none());
+        let indexed: XorDistance<u32> = XorDistance::new((0..100).collect());
+        assert_eq!(Algorithm::Index, indexed.select_algorithm(1));
     }
 }
@@ -0,0 +1,269 @@
+//! Fixed-capacity, allocation-free variant of `XorDistance`.
+//!
+//! Points are kept in an array of the capacity `N` and queries sort a copy of it on the stack,
+//! so small hot sets (e.g. per-bucket candidate lists) never touch the heap.
+//!
+//! # Examples
+//!
+//! ```
+//! extern crate xor_distance_exercise;
+//!
+//! use xor_distance_exercise::xor_distance_array::XorDistanceArray;
+//!
+//! let mut bucket: XorDistanceArray<u64, 8> = XorDistanceArray::new();
+//!
+//! for point in [0, 1, 2, 4, 6, 8].iter() {
+//!     bucket.push(*point).unwrap();
+//! }
+//!
+//! let closest = bucket.closest(10, 3);
+//!
+//! assert_eq!(&[8, 2, 0], &closest[..]);
+//! assert_eq!(Some(10), bucket.reverse_closest(&[8, 2, 0]));
+//! ```
+
+use crate::bitops::BitOps;
+use crate::error::XorDistanceError;
+use crate::solver::restricted_bit;
+use num_traits::{PrimInt, Unsigned};
+use std::ops::Deref;
+
+/// Xor distance structure holding up to `N` `Unsigned Integer` points.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct XorDistanceArray<T, const N: usize> {
+    points: [T; N],
+    len: usize,
+}
+
+/// Closest points returned by `XorDistanceArray`, dereferencing to a slice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClosestPoints<T, const N: usize> {
+    points: [T; N],
+    len: usize,
+}
+
+impl<T, const N: usize> Deref for ClosestPoints<T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.points[..self.len]
+    }
+}
+
+impl<T: PrimInt + Unsigned, const N: usize> Default for XorDistanceArray<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: PrimInt + Unsigned, const N: usize> XorDistanceArray<T, N> {
+    /// Create a new empty structure.
+    pub fn new() -> Self {
+        XorDistanceArray {
+            points: [T::zero(); N],
+            len: 0,
+        }
+    }
+
+    /// Create a new structure holding the points.
+    ///
+    /// Returns `Err(XorDistanceError::CapacityExhausted)` if there are more points than the
+    /// capacity.
+    pub fn from_slice(points: &[T]) -> Result<Self, XorDistanceError> {
+        let mut array = Self::new();
+
+        for point in points.iter() {
+            array.push(*point)?;
+        }
+
+        Ok(array)
+    }
+
+    /// Return the maximal number of points.
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Return number of points.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return whether there are no points.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return points in their insertion order.
+    pub fn points(&self) -> &[T] {
+        &self.points[..self.len]
+    }
+
+    /// Add a new point.
+    ///
+    /// Returns `Err(XorDistanceError::CapacityExhausted)` if the structure is full.
+    pub fn push(&mut self, point: T) -> Result<(), XorDistanceError> {
+        if self.len == N {
+            return Err(XorDistanceError::CapacityExhausted { capacity: N });
+        }
+
+        self.points[self.len] = point;
+        self.len += 1;
+
+        Ok(())
+    }
+
+    /// Remove the first occurrence of the point, returning whether it was present.
+    ///
+    /// The last point takes place of the removed one, so the insertion order is not kept.
+    pub fn remove(&mut self, point: T) -> bool {
+        match self.points().iter().position(|p| *p == point) {
+            Some(position) => {
+                self.len -= 1;
+                self.points[position] = self.points[self.len];
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Return up to requested count of closest points to the provided `x`, ordered from the closest
+    /// to the n-th closest, where `n` is the count.
+    pub fn closest(&self, x: T, count: usize) -> ClosestPoints<T, N> {
+        self.closest_bounded(x, count, T::max_value())
+    }
+
+    /// Return up to requested count of closest points to the provided `x` within the XOR distance
+    /// `max_distance`, ordered from the closest to the n-th closest, where `n` is the count.
+    pub fn closest_bounded(&self, x: T, count: usize, max_distance: T) -> ClosestPoints<T, N> {
+        let mut closest = ClosestPoints {
+            points: [T::zero(); N],
+            len: 0,
+        };
+
+        for point in self.points().iter() {
+            if *point ^ x <= max_distance {
+                closest.points[closest.len] = *point;
+                closest.len += 1;
+            }
+        }
+
+        // Unstable sorting doesn't allocate, equally distant points are equal anyway.
+        closest.points[..closest.len].sort_unstable_by_key(|point| *point ^ x);
+        closest.len = closest.len.min(count);
+
+        closest
+    }
+
+    /// Return the `n`-th closest point to the provided `x`, indexed from zero, `None` if there are
+    /// not more than `n` points.
+    pub fn nth_closest(&self, x: T, n: usize) -> Option<T> {
+        if n >= self.len {
+            return None;
+        }
+
+        let mut points = self.points;
+        let (_, nth, _) = points[..self.len].select_nth_unstable_by_key(n, |point| *point ^ x);
+
+        Some(*nth)
+    }
+
+    /// Return a `Some(x)` such that `self.closest(x)` equals closest_points and return None in case
+    /// such a `x` does not exists.
+    pub fn reverse_closest(&self, closest_points: &[T]) -> Option<T> {
+        // Masks of the decided bits and their values.
+        let mut decided = T::zero();
+        let mut position = T::zero();
+
+        let closest_inequalities = closest_points.windows(2).map(|pair| (pair[0], pair[1]));
+        let further_inequalities = closest_points.last().into_iter().flat_map(|a| {
+            self.points()
+                .iter()
+                .filter(move |b| !closest_points.contains(b))
+                .map(move |b| (*a, *b))
+        });
+
+        for inequality in closest_inequalities.chain(further_inequalities) {
+            // Repeated closest points get no position.
+            let (bit_index, value) = restricted_bit(inequality).ok()?;
+
+            if decided.is_bit_set(bit_index) {
+                if position.is_bit_set(bit_index) != value {
+                    return None;
+                }
+            } else {
+                decided.set_bit(bit_index);
+
+                if value {
+                    position.set_bit(bit_index);
+                }
+            }
+        }
+
+        Some(position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::XorDistanceArray;
+    use crate::error::XorDistanceError;
+    use crate::xor_distance::XorDistance;
+
+    const POINTS: [u64; 19] = [
+        0, 1, 2, 4, 6, 8, 12, 18, 19, 20, 21, 22, 406, 407, 408, 409, 410, 444, 445,
+    ];
+
+    #[test]
+    fn same_results_as_xor_distance() {
+        let array: XorDistanceArray<u64, 32> = XorDistanceArray::from_slice(&POINTS).unwrap();
+        let xor_distance = XorDistance::new(POINTS.to_vec());
+
+        for x in [0, 10, 200, 300, 445, u64::MAX].iter() {
+            for count in [0, 1, 5, 19, 40].iter() {
+                let closest = xor_distance.closest(*x, *count);
+
+                assert_eq!(closest, array.closest(*x, *count).to_vec());
+                assert_eq!(
+                    xor_distance.closest_bounded(*x, *count, 20),
+                    array.closest_bounded(*x, *count, 20).to_vec()
+                );
+                assert_eq!(
+                    xor_distance.nth_closest(*x, *count),
+                    array.nth_closest(*x, *count)
+                );
+                assert_eq!(
+                    xor_distance.reverse_closest(&closest),
+                    array.reverse_closest(&closest)
+                );
+            }
+        }
+
+        assert_eq!(None, array.reverse_closest(&[0, 445, 1]));
+        assert_eq!(None, array.reverse_closest(&[8, 8]));
+    }
+
+    #[test]
+    fn push_and_remove() {
+        let mut array: XorDistanceArray<u8, 3> = XorDistanceArray::new();
+
+        assert!(array.is_empty());
+        assert_eq!(3, array.capacity());
+
+        array.push(1).unwrap();
+        array.push(2).unwrap();
+        array.push(3).unwrap();
+        assert_eq!(
+            Err(XorDistanceError::CapacityExhausted { capacity: 3 }),
+            array.push(4)
+        );
+
+        assert!(array.remove(1));
+        assert!(!array.remove(1));
+        assert_eq!(&[3, 2], array.points());
+        assert_eq!(2, array.len());
+
+        assert!(XorDistanceArray::<u8, 2>::from_slice(&[1, 2, 3]).is_err());
+    }
+}